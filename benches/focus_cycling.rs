@@ -0,0 +1,52 @@
+//! Rapid focus cycling (e.g. holding focus-column-right), where every
+//! focus change clears the old focus and sets the new one. Run with
+//! `cargo bench --bench focus_cycling`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use nirimap::state::{MinimapState, Window};
+use std::hint::black_box;
+
+/// 50 windows, five on each of 10 workspaces
+fn spread_state() -> MinimapState {
+    let mut state = MinimapState::new();
+    for workspace_id in 0..10u64 {
+        for i in 0..5u64 {
+            let id = workspace_id * 5 + i;
+            state.upsert_window(
+                workspace_id,
+                Window {
+                    id,
+                    workspace_id: Some(workspace_id),
+                    app_id: None,
+                    title: None,
+                    output: None,
+                    pos: None,
+                    size: (100.0, 200.0),
+                    column_index: i as usize,
+                    window_index: 0,
+                    is_focused: false,
+                    is_floating: false,
+                    is_urgent: false,
+                    is_hidden: false,
+                    is_column_active: false,
+                    is_fullscreen: false,
+                },
+            );
+        }
+    }
+    state
+}
+
+fn bench_focus_cycling(c: &mut Criterion) {
+    let mut state = spread_state();
+    let mut next = 0u64;
+    c.bench_function("set_focused_window/50_windows_10_workspaces", |b| {
+        b.iter(|| {
+            state.set_focused_window(Some(black_box(next)));
+            next = (next + 1) % 50;
+        })
+    });
+}
+
+criterion_group!(benches, bench_focus_cycling);
+criterion_main!(benches);
@@ -0,0 +1,76 @@
+//! Sizing and drawing cost for a busy workspace, which happens on every
+//! state update and every frame. Run with `cargo bench --bench frame_layout`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use gtk4::cairo::{Context, Format, ImageSurface};
+use nirimap::config::{Config, WorkspaceMode};
+use nirimap::state::{MinimapState, Window};
+use nirimap::ui::{draw_frame, frame_size};
+use std::hint::black_box;
+
+const VIEWPORT_WIDTH: f64 = 1920.0;
+
+/// 100 tiled windows in 50 columns of two on one workspace
+fn busy_state() -> MinimapState {
+    let mut state = MinimapState::new();
+    state.set_active_workspace(1);
+    for id in 0..100u64 {
+        state.upsert_window(
+            1,
+            Window {
+                id,
+                workspace_id: Some(1),
+                app_id: Some("foot".to_string()),
+                title: None,
+                output: None,
+                pos: None,
+                size: (800.0, 500.0),
+                column_index: (id / 2) as usize,
+                window_index: (id % 2) as usize,
+                is_focused: false,
+                is_floating: false,
+                is_urgent: false,
+                is_hidden: false,
+                is_column_active: false,
+                is_fullscreen: false,
+            },
+        );
+    }
+    state.set_focused_window(Some(0));
+    state
+}
+
+fn bench_frame_layout(c: &mut Criterion) {
+    let state = busy_state();
+    for (name, mode) in [
+        ("current", WorkspaceMode::Current),
+        ("all", WorkspaceMode::All),
+    ] {
+        let mut config = Config::default();
+        config.display.workspace_mode = mode;
+
+        c.bench_function(&format!("frame_size/{}", name), |b| {
+            b.iter(|| frame_size(black_box(&state), &config, 1000.0, 800.0, VIEWPORT_WIDTH))
+        });
+
+        let (width, height) = frame_size(&state, &config, 1000.0, 800.0, VIEWPORT_WIDTH);
+        let (width, height) = (width.ceil() as i32, height.ceil() as i32);
+        let surface = ImageSurface::create(Format::ARgb32, width, height).unwrap();
+        let cr = Context::new(&surface).unwrap();
+        c.bench_function(&format!("draw_frame/{}", name), |b| {
+            b.iter(|| {
+                draw_frame(
+                    &cr,
+                    width,
+                    height,
+                    black_box(&state),
+                    &config,
+                    VIEWPORT_WIDTH,
+                )
+            })
+        });
+    }
+}
+
+criterion_group!(benches, bench_frame_layout);
+criterion_main!(benches);
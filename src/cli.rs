@@ -0,0 +1,142 @@
+use anyhow::{Context, Result};
+
+/// What the process should do
+#[derive(Debug, Default, PartialEq, Eq)]
+pub enum Command {
+    /// Run the minimap
+    #[default]
+    Run,
+    /// Send a command to a running instance and print the reply
+    Msg(String),
+    /// Write the annotated default config and exit
+    WriteDefaultConfig { force: bool },
+}
+
+/// Parsed command-line arguments
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Cli {
+    pub command: Command,
+    /// Config file from `--config <path>`, or `-` for stdin
+    pub config: Option<String>,
+    /// Niri socket from `--socket <path>`, overriding `NIRI_SOCKET`
+    pub socket: Option<String>,
+}
+
+/// Parse command-line arguments (excluding the program name)
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Cli> {
+    let mut cli = Cli::default();
+    let mut args = args.into_iter();
+    let mut write_default_config = false;
+    let mut force = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--write-default-config" => write_default_config = true,
+            "--force" => force = true,
+            "--config" => {
+                let value = args
+                    .next()
+                    .context("Usage: --config <path> (or - to read from stdin)")?;
+                cli.config = Some(value);
+            }
+            "--socket" => {
+                let value = args.next().context("Usage: --socket <path>")?;
+                cli.socket = Some(value);
+            }
+            "msg" => {
+                let command = args.by_ref().collect::<Vec<_>>().join(" ");
+                if command.is_empty() {
+                    anyhow::bail!("Usage: nirimap msg <command> [args...]");
+                }
+                cli.command = Command::Msg(command);
+            }
+            other => anyhow::bail!("Unknown argument: {}", other),
+        }
+    }
+
+    if write_default_config {
+        cli.command = Command::WriteDefaultConfig { force };
+    } else if force {
+        anyhow::bail!("--force is only valid with --write-default-config");
+    }
+
+    Ok(cli)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_no_args_runs() {
+        assert_eq!(parse(args(&[])).unwrap().command, Command::Run);
+    }
+
+    #[test]
+    fn test_msg_joins_remaining_args() {
+        assert_eq!(
+            parse(args(&["msg", "preset", "minimal"])).unwrap().command,
+            Command::Msg("preset minimal".to_string())
+        );
+    }
+
+    #[test]
+    fn test_msg_requires_command() {
+        assert!(parse(args(&["msg"])).is_err());
+    }
+
+    #[test]
+    fn test_write_default_config() {
+        assert_eq!(
+            parse(args(&["--write-default-config"])).unwrap().command,
+            Command::WriteDefaultConfig { force: false }
+        );
+        assert_eq!(
+            parse(args(&["--write-default-config", "--force"]))
+                .unwrap()
+                .command,
+            Command::WriteDefaultConfig { force: true }
+        );
+        // Flag order doesn't matter
+        assert_eq!(
+            parse(args(&["--force", "--write-default-config"]))
+                .unwrap()
+                .command,
+            Command::WriteDefaultConfig { force: true }
+        );
+    }
+
+    #[test]
+    fn test_force_requires_write_default_config() {
+        assert!(parse(args(&["--force"])).is_err());
+    }
+
+    #[test]
+    fn test_config_flag() {
+        let cli = parse(args(&["--config", "/etc/nirimap.toml"])).unwrap();
+        assert_eq!(cli.command, Command::Run);
+        assert_eq!(cli.config.as_deref(), Some("/etc/nirimap.toml"));
+        assert_eq!(
+            parse(args(&["--config", "-"])).unwrap().config.as_deref(),
+            Some("-")
+        );
+        assert!(parse(args(&["--config"])).is_err());
+    }
+
+    #[test]
+    fn test_socket_flag() {
+        let cli = parse(args(&["--socket", "/tmp/niri-mock.sock"])).unwrap();
+        assert_eq!(cli.command, Command::Run);
+        assert_eq!(cli.socket.as_deref(), Some("/tmp/niri-mock.sock"));
+        assert!(parse(args(&["--socket"])).is_err());
+    }
+
+    #[test]
+    fn test_unknown_argument() {
+        assert!(parse(args(&["--bogus"])).is_err());
+    }
+}
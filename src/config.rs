@@ -1,9 +1,29 @@
 use anyhow::{Context, Result};
-use serde::Deserialize;
-use std::path::PathBuf;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Environment variable naming an explicit config file
+pub const CONFIG_ENV_VAR: &str = "NIRIMAP_CONFIG";
+
+/// Environment variable that stops a missing default config from being
+/// written to disk
+pub const NO_WRITE_DEFAULT_ENV_VAR: &str = "NIRIMAP_NO_WRITE_DEFAULT";
+
+/// Environment variable renaming the instance, see `app_name`
+pub const APP_NAME_ENV_VAR: &str = "NIRIMAP_APP_NAME";
+
+/// Instance name used without `NIRIMAP_APP_NAME`
+pub const DEFAULT_APP_NAME: &str = "nirimap";
+
+/// Where configuration is loaded from, chosen once at startup
+static CONFIG_SOURCE: OnceLock<ConfigSource> = OnceLock::new();
 
 /// Anchor position for the minimap on screen
-#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub enum Anchor {
     TopLeft,
@@ -16,8 +36,23 @@ pub enum Anchor {
     Center,
 }
 
+impl Anchor {
+    /// The anchor after this one, in declaration order, wrapping around
+    pub fn next(self) -> Self {
+        match self {
+            Anchor::TopLeft => Anchor::TopCenter,
+            Anchor::TopCenter => Anchor::TopRight,
+            Anchor::TopRight => Anchor::BottomLeft,
+            Anchor::BottomLeft => Anchor::BottomCenter,
+            Anchor::BottomCenter => Anchor::BottomRight,
+            Anchor::BottomRight => Anchor::Center,
+            Anchor::Center => Anchor::TopLeft,
+        }
+    }
+}
+
 /// Which workspaces the minimap renders
-#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub enum WorkspaceMode {
     /// Render only the currently active workspace
@@ -27,8 +62,131 @@ pub enum WorkspaceMode {
     All,
 }
 
+/// How a `current`-mode workspace wider than the minimap is drawn
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Overflow {
+    /// Shrink the workspace until all of it fits
+    #[default]
+    Compress,
+    /// Keep the scale and scroll to keep the focused column in view
+    Scroll,
+}
+
+/// How much detail each workspace column is drawn with
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Density {
+    /// One rectangle per window
+    #[default]
+    Full,
+    /// One bar per column, sized to the column
+    Compact,
+}
+
+/// Feedback shown while focus is on a floating window, which the minimap
+/// doesn't draw
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum FloatingIndicator {
+    /// No feedback
+    #[default]
+    None,
+    /// Highlight the tiled column the floating window is over
+    Column,
+    /// Draw a small floating-window glyph in the top-right corner
+    Glyph,
+}
+
+/// Keyboard focus the layer surface asks for
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyboardInteractivity {
+    /// Never take keyboard focus
+    #[default]
+    None,
+    /// Take focus when the compositor gives it (e.g. on click)
+    OnDemand,
+    /// Grab the keyboard while shown
+    Exclusive,
+}
+
+/// Where columns shorter than the minimap row sit vertically
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum VerticalAlign {
+    /// Hang from the top of the row
+    #[default]
+    Top,
+    /// Centered in the row
+    Center,
+    /// Rest on the bottom of the row
+    Bottom,
+}
+
+impl VerticalAlign {
+    /// Fraction of the free space left above a column
+    pub fn factor(self) -> f64 {
+        match self {
+            VerticalAlign::Top => 0.0,
+            VerticalAlign::Center => 0.5,
+            VerticalAlign::Bottom => 1.0,
+        }
+    }
+}
+
+/// A corner of the minimap, for overlays such as the workspace number
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Corner {
+    #[default]
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Shape of rectangle corners
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CornerStyle {
+    /// Square corners; `border_radius` is ignored
+    Sharp,
+    /// Circular arcs of `border_radius`
+    #[default]
+    Rounded,
+    /// Superellipse corners of `border_radius`, blending into the edges
+    Squircle,
+}
+
+/// Where the gap between minimap windows comes from
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum GapMode {
+    /// `appearance.gap` minimap pixels
+    #[default]
+    Fixed,
+    /// Niri's own gap, scaled like the windows. Measured from the tile
+    /// positions Niri reports; `appearance.gap` is used until it's known.
+    FromNiri,
+}
+
+/// Events that bring up the minimap when it isn't `always_visible`
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ShowTrigger {
+    /// A window was opened
+    NewWindow,
+    /// Focus moved to a different window
+    FocusChange,
+    /// Another workspace was activated
+    WorkspaceChange,
+    /// Window layouts changed (resize, move between columns)
+    LayoutChange,
+}
+
 /// Display configuration
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(default)]
 pub struct DisplayConfig {
     /// Per-workspace row height in pixels. In `current` mode this is the whole
@@ -44,8 +202,78 @@ pub struct DisplayConfig {
     pub margin_x: i32,
     /// Vertical margin from edge
     pub margin_y: i32,
+    /// Margin from the top edge (negative = `margin_y`)
+    pub margin_top: i32,
+    /// Margin from the bottom edge (negative = `margin_y`)
+    pub margin_bottom: i32,
+    /// Margin from the left edge (negative = `margin_x`)
+    pub margin_left: i32,
+    /// Margin from the right edge (negative = `margin_x`)
+    pub margin_right: i32,
     /// Which workspaces to display
     pub workspace_mode: WorkspaceMode,
+    /// Show only the focused column of the active workspace, enlarged.
+    /// Falls back to `workspace_mode` when no tiled window is focused.
+    pub focus_mode: bool,
+    /// Number of columns on each side of the focused one to include in
+    /// `focus_mode`
+    pub focus_neighbors: u32,
+    /// How a workspace wider than `max_width_percent` is drawn in `current`
+    /// mode and `focus_mode`
+    pub overflow: Overflow,
+    /// Move the minimap to whichever output holds the focused window,
+    /// keeping `anchor` as the corner on that output
+    pub follow_focus: bool,
+    /// Maximum number of columns drawn per workspace (0 = unlimited). Extra
+    /// columns are replaced by "+N" indicators at the edges.
+    pub max_columns: u32,
+    /// Draw each window, or a single bar per column
+    pub density: Density,
+    /// Switch a workspace to compact density once it holds at least this
+    /// many tiled windows (0 = never)
+    pub compact_threshold: u32,
+    /// Size the minimap to the aspect ratio of its output instead of its
+    /// content, within `max_width_percent`. Content that doesn't fit the
+    /// width is handled by `overflow`.
+    pub match_monitor_aspect: bool,
+    /// Connector name of the output to show the minimap on (e.g. "DP-1").
+    /// Empty leaves the choice to the compositor.
+    pub output: String,
+    /// Layer-shell namespace, for targeting the minimap in Niri layer rules
+    pub namespace: String,
+    /// Keyboard focus the layer surface asks for
+    pub keyboard_interactivity: KeyboardInteractivity,
+}
+
+impl DisplayConfig {
+    /// Margin from the top edge: `margin_top`, else `margin_y`
+    pub fn top_margin(&self) -> i32 {
+        edge_margin(self.margin_top, self.margin_y)
+    }
+
+    /// Margin from the bottom edge: `margin_bottom`, else `margin_y`
+    pub fn bottom_margin(&self) -> i32 {
+        edge_margin(self.margin_bottom, self.margin_y)
+    }
+
+    /// Margin from the left edge: `margin_left`, else `margin_x`
+    pub fn left_margin(&self) -> i32 {
+        edge_margin(self.margin_left, self.margin_x)
+    }
+
+    /// Margin from the right edge: `margin_right`, else `margin_x`
+    pub fn right_margin(&self) -> i32 {
+        edge_margin(self.margin_right, self.margin_x)
+    }
+}
+
+/// An edge-specific margin, or the shared one when it's unset (negative)
+fn edge_margin(margin: i32, fallback: i32) -> i32 {
+    if margin >= 0 {
+        margin
+    } else {
+        fallback
+    }
 }
 
 impl Default for DisplayConfig {
@@ -57,13 +285,28 @@ impl Default for DisplayConfig {
             anchor: Anchor::TopRight,
             margin_x: 10,
             margin_y: 10,
+            margin_top: -1,
+            margin_bottom: -1,
+            margin_left: -1,
+            margin_right: -1,
             workspace_mode: WorkspaceMode::default(),
+            focus_mode: false,
+            focus_neighbors: 0,
+            overflow: Overflow::default(),
+            follow_focus: false,
+            max_columns: 0,
+            density: Density::default(),
+            compact_threshold: 0,
+            match_monitor_aspect: false,
+            output: String::new(),
+            namespace: String::new(),
+            keyboard_interactivity: KeyboardInteractivity::None,
         }
     }
 }
 
 /// Appearance configuration
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(default)]
 pub struct AppearanceConfig {
     /// Background color (hex)
@@ -78,8 +321,18 @@ pub struct AppearanceConfig {
     pub border_width: f64,
     /// Corner radius for window rectangles
     pub border_radius: f64,
+    /// Corner radius for windows in columns of more than one window
+    /// (negative = same as `border_radius`)
+    pub stacked_border_radius: f64,
+    /// Shape of the corners drawn with `border_radius`
+    pub corner_style: CornerStyle,
     /// Gap between windows (in minimap pixels)
     pub gap: f64,
+    /// Whether to use `gap` or mirror Niri's gaps
+    pub gap_mode: GapMode,
+    /// Place on-screen columns at their real distance from each other, as
+    /// reported by Niri, instead of packing them edge to edge
+    pub preserve_column_spacing: bool,
     /// Background opacity (0.0 = transparent, 1.0 = opaque)
     pub background_opacity: f64,
     /// Fill opacity for unfocused windows (0.0 = transparent, just borders)
@@ -92,6 +345,87 @@ pub struct AppearanceConfig {
     pub active_workspace_border_color: String,
     /// Border thickness for the active workspace in `all` mode
     pub active_workspace_border_width: f64,
+    /// Fill color for windows requesting attention (hex)
+    pub urgent_color: String,
+    /// Blink urgent windows between `urgent_color` and `window_color`
+    pub urgent_blink: bool,
+    /// Duration of each blink phase in milliseconds
+    pub urgent_blink_ms: u32,
+    /// Nudge the brightness of window fills too similar to an adjacent
+    /// window's, or to the painted background
+    pub enforce_contrast: bool,
+    /// Keep the background translucent so compositor blur shows through
+    pub blur_behind: bool,
+    /// Mirror the x-axis so column 0 is drawn on the right
+    pub reverse_columns: bool,
+    /// Mirror the y-axis: windows stack up from the bottom of their row and
+    /// `all`-mode workspaces run bottom to top, e.g. for a bottom anchor
+    pub flip_vertical: bool,
+    /// Vertical placement of each column within its row
+    pub vertical_align: VerticalAlign,
+    /// Color of the visible-viewport rectangle (hex), see `behavior.show_viewport`
+    pub viewport_color: String,
+    /// Thickness of the extra ring drawn around the focused window (0 = off)
+    pub focus_ring_width: f64,
+    /// Color of the focused-window ring (hex)
+    pub focus_ring_color: String,
+    /// Draw the number of windows at the top of columns holding more than one
+    pub show_column_counts: bool,
+    /// Text color for labels drawn on the minimap (hex)
+    pub label_color: String,
+    /// Fill color for the active window of a multi-window column, distinct
+    /// from the globally focused one. Empty disables the highlight.
+    pub column_active_color: String,
+    /// Fill color for the focused window while it's fullscreen (hex). Empty
+    /// keeps `focused_color`.
+    pub fullscreen_color: String,
+    /// Treat `border_width`/`border_radius` as workspace pixels and scale them
+    /// with the minimap, instead of fixed minimap pixels
+    pub scale_borders: bool,
+    /// Scale/fade windows in when they open and out when they close
+    pub window_animations: bool,
+    /// Duration of the horizontal slide between workspaces in `current`
+    /// mode, in milliseconds (0 = switch instantly)
+    pub workspace_transition_ms: u32,
+    /// Paint a rounded panel behind each column's windows
+    pub column_backdrop: bool,
+    /// Fill color of the per-column backdrop panels
+    pub column_backdrop_color: String,
+    /// Width of the line drawn between neighbouring columns (0 = disabled)
+    pub column_separator_width: f64,
+    /// Color of the lines between columns
+    pub column_separator_color: String,
+    /// Fade unfocused windows by how long ago (in focus changes) they were
+    /// last focused
+    pub recency_fade: bool,
+    /// Round window rectangles to whole device pixels for crisp edges, at
+    /// the cost of slightly uneven gaps and sizes
+    pub pixel_snap: bool,
+    /// Anti-alias shapes and text. Off gives hard (jagged) rounded corners
+    pub antialias: bool,
+    /// Draw each window's application icon (looked up by app_id in the
+    /// icon theme) inside its rectangle, when there's room
+    pub show_icons: bool,
+    /// Outline the whole minimap in black or white, whichever contrasts
+    /// with `background`, so it stands out on any wallpaper
+    pub auto_contrast_border: bool,
+    /// Feedback shown while a (not drawn) floating window has focus
+    pub floating_indicator: FloatingIndicator,
+    /// List the active workspace's floating windows as a row of small boxes
+    /// below the tiled layout (not positioned, just listed)
+    pub floating_strip: bool,
+    /// Space between the window layout and auxiliary elements drawn beside
+    /// it, such as the `floating_strip`
+    pub element_spacing: f64,
+    /// Background color (hex) used instead of `background` while a workspace
+    /// is active, keyed by workspace name or id
+    pub workspace_colors: BTreeMap<String, String>,
+    /// Draw the active workspace's name (or index) as a badge in a corner
+    pub show_workspace_number: bool,
+    /// Corner holding the workspace number badge
+    pub workspace_number_corner: Corner,
+    /// Text color of the workspace number badge (hex)
+    pub workspace_number_color: String,
 }
 
 impl Default for AppearanceConfig {
@@ -103,19 +437,57 @@ impl Default for AppearanceConfig {
             border_color: "#6c7086".to_string(),
             border_width: 1.0,
             border_radius: 2.0,
+            stacked_border_radius: -1.0,
+            corner_style: CornerStyle::Rounded,
             gap: 2.0,
+            gap_mode: GapMode::Fixed,
+            preserve_column_spacing: false,
             background_opacity: 0.0,
             window_opacity: 0.7,
             focused_opacity: 1.0,
             workspace_gap: 4.0,
             active_workspace_border_color: "#89b4fa".to_string(),
             active_workspace_border_width: 2.0,
+            urgent_color: "#f38ba8".to_string(),
+            urgent_blink: false,
+            urgent_blink_ms: 500,
+            enforce_contrast: false,
+            blur_behind: false,
+            reverse_columns: false,
+            flip_vertical: false,
+            vertical_align: VerticalAlign::Top,
+            viewport_color: "#cdd6f4".to_string(),
+            focus_ring_width: 0.0,
+            focus_ring_color: "#f9e2af".to_string(),
+            show_column_counts: false,
+            label_color: "#cdd6f4".to_string(),
+            column_active_color: String::new(),
+            fullscreen_color: String::new(),
+            scale_borders: false,
+            window_animations: false,
+            workspace_transition_ms: 0,
+            column_backdrop: false,
+            column_backdrop_color: "#313244".to_string(),
+            column_separator_width: 0.0,
+            column_separator_color: "#45475a".to_string(),
+            recency_fade: false,
+            pixel_snap: false,
+            antialias: true,
+            show_icons: false,
+            auto_contrast_border: false,
+            floating_indicator: FloatingIndicator::None,
+            floating_strip: false,
+            element_spacing: 2.0,
+            workspace_colors: BTreeMap::new(),
+            show_workspace_number: false,
+            workspace_number_corner: Corner::TopLeft,
+            workspace_number_color: "#cdd6f4".to_string(),
         }
     }
 }
 
 /// Behavior configuration
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(default)]
 pub struct BehaviorConfig {
     /// Keep visible in Niri overview mode
@@ -129,6 +501,50 @@ pub struct BehaviorConfig {
     /// rendered on the minimap, so surfacing it for transient popups, dialogs,
     /// or returning focus from a popup is rarely useful.
     pub show_for_floating_windows: bool,
+    /// Outline the part of each workspace currently visible on the monitor
+    pub show_viewport: bool,
+    /// Accept pointer input on the minimap instead of being click-through.
+    /// Enables hover tracking, title tooltips and click-to-focus; read once at startup.
+    pub interactive: bool,
+    /// Hide the minimap entirely while the active workspace has no tiled
+    /// windows. Takes priority over `always_visible`.
+    pub hide_when_empty: bool,
+    /// Re-fetch the full state from Niri every this many milliseconds as a
+    /// safety net against missed events (0 = disabled). Read at startup.
+    pub poll_interval_ms: u64,
+    /// Which events show the minimap (see `ShowTrigger`)
+    pub show_triggers: Vec<ShowTrigger>,
+    /// Opacity to fade to after `hide_timeout_ms` of inactivity instead of
+    /// hiding (1.0 = disabled). Also applies with `always_visible`.
+    pub idle_dim_opacity: f64,
+    /// Workspaces (by name or id) on which the minimap stays fully shown,
+    /// as if `always_visible` were set and `idle_dim_opacity` off
+    pub sticky_workspaces: Vec<String>,
+    /// Opacity while Niri's overview is open, so the overview stays visible
+    /// beneath the minimap (1.0 = unchanged). Needs `show_on_overview`.
+    pub overview_opacity: f64,
+    /// While Niri's overview is open, show every workspace (as in `all`
+    /// mode) with the inactive ones dimmed, whatever `workspace_mode` says
+    pub overview_all_workspaces: bool,
+    /// Also re-fetch the full state from Niri whenever the config reloads
+    pub reload_resyncs_state: bool,
+    /// Leave Niri's empty trailing workspace out of `all` mode, even while
+    /// it's active. Named workspaces are always shown.
+    pub hide_empty_trailing_workspace: bool,
+    /// Minimum milliseconds between workspace-switch shows, so holding a
+    /// switch keybind doesn't re-show on every event (0 = no cooldown)
+    pub workspace_switch_cooldown_ms: u32,
+    /// Milliseconds a focus change must stick before it's applied when it
+    /// follows another one, so drag churn doesn't flicker (0 = off)
+    pub focus_hold_ms: u32,
+    /// Answer the `metrics` control command with runtime counters
+    pub metrics_enable: bool,
+    /// Broadcast every state update as JSON on `ws://127.0.0.1:<port>`
+    /// (0 = off). Needs the `export-ws` build feature.
+    pub export_ws_port: u16,
+    /// App ids of windows to leave out of the minimap entirely, as if they
+    /// didn't exist. `*` matches any run of characters.
+    pub ignore_app_ids: Vec<String>,
 }
 
 impl Default for BehaviorConfig {
@@ -138,10 +554,44 @@ impl Default for BehaviorConfig {
             always_visible: true,
             hide_timeout_ms: 2000,
             show_for_floating_windows: false,
+            show_viewport: false,
+            interactive: false,
+            hide_when_empty: false,
+            poll_interval_ms: 0,
+            show_triggers: vec![
+                ShowTrigger::NewWindow,
+                ShowTrigger::FocusChange,
+                ShowTrigger::WorkspaceChange,
+                ShowTrigger::LayoutChange,
+            ],
+            idle_dim_opacity: 1.0,
+            sticky_workspaces: Vec::new(),
+            overview_opacity: 1.0,
+            overview_all_workspaces: false,
+            reload_resyncs_state: false,
+            hide_empty_trailing_workspace: true,
+            workspace_switch_cooldown_ms: 150,
+            focus_hold_ms: 50,
+            metrics_enable: false,
+            export_ws_port: 0,
+            ignore_app_ids: Vec::new(),
         }
     }
 }
 
+/// A named bundle of display/appearance overrides that can be applied at
+/// runtime (see `Config::with_preset`)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Preset {
+    /// Name used to select the preset
+    pub name: String,
+    /// Overrides for `[display]` keys
+    pub display: toml::Table,
+    /// Overrides for `[appearance]` keys
+    pub appearance: toml::Table,
+}
+
 /// Main configuration struct
 #[derive(Debug, Clone, Default, Deserialize)]
 #[serde(default)]
@@ -149,49 +599,467 @@ pub struct Config {
     pub display: DisplayConfig,
     pub appearance: AppearanceConfig,
     pub behavior: BehaviorConfig,
+    pub presets: Vec<Preset>,
+}
+
+/// Where the configuration comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// `$XDG_CONFIG_HOME/nirimap/config.toml`, created with defaults if
+    /// missing (unless `NIRIMAP_NO_WRITE_DEFAULT` is set)
+    Default,
+    /// An explicit file from `--config <path>` or `NIRIMAP_CONFIG`. Never
+    /// written to, so it can live somewhere read-only like the Nix store.
+    File(PathBuf),
+    /// TOML read from stdin once at startup (`--config -`)
+    Stdin(String),
+}
+
+impl ConfigSource {
+    /// Resolve the config source with precedence
+    /// `--config` flag > `NIRIMAP_CONFIG` > default path.
+    ///
+    /// `read_stdin` is only called for `--config -`. An empty environment
+    /// variable is treated as unset.
+    pub fn resolve(
+        flag: Option<&str>,
+        env: Option<OsString>,
+        read_stdin: impl FnOnce() -> Result<String>,
+    ) -> Result<Self> {
+        match flag {
+            Some("-") => Ok(Self::Stdin(
+                read_stdin().context("Failed to read config from stdin")?,
+            )),
+            Some(path) => Ok(Self::File(PathBuf::from(path))),
+            None => Ok(env
+                .filter(|value| !value.is_empty())
+                .map(|value| Self::File(PathBuf::from(value)))
+                .unwrap_or(Self::Default)),
+        }
+    }
+
+    /// The file to watch for hot reload, if any
+    pub fn watch_path(&self) -> Result<Option<PathBuf>> {
+        match self {
+            Self::Default => Config::config_path().map(Some),
+            Self::File(path) => Ok(Some(path.clone())),
+            Self::Stdin(_) => Ok(None),
+        }
+    }
+
+    /// The file `cycle-anchor --persist` writes to: only the default config,
+    /// since explicit ones are never written to
+    #[cfg(feature = "ui")]
+    pub fn persist_path(&self) -> Result<PathBuf> {
+        match self {
+            Self::Default => Config::config_path(),
+            Self::File(path) => anyhow::bail!(
+                "--persist only writes the default config file, not {}",
+                path.display()
+            ),
+            Self::Stdin(_) => anyhow::bail!(
+                "The config was read from stdin, so there is no file to save the anchor to"
+            ),
+        }
+    }
+}
+
+/// The config file inside the user's config directory, if there is one
+fn config_path_in(config_dir: Option<PathBuf>, app_name: &str) -> Result<PathBuf> {
+    let config_dir = config_dir.context(
+        "Failed to determine the config directory. Set XDG_CONFIG_HOME or HOME, \
+         or pass --config <path>.",
+    )?;
+    Ok(config_dir.join(app_name).join("config.toml"))
+}
+
+/// Name of this instance: the config subdirectory, the GTK application id
+/// suffix, the control socket name and the default layer namespace. Set with
+/// `NIRIMAP_APP_NAME` so several instances can run side by side.
+pub fn app_name() -> &'static str {
+    static APP_NAME: OnceLock<String> = OnceLock::new();
+    APP_NAME.get_or_init(|| resolve_app_name(std::env::var_os(APP_NAME_ENV_VAR)))
+}
+
+/// The app name from `NIRIMAP_APP_NAME`. Empty or unset means the default;
+/// names that can't be part of a GTK application id (letters, digits, `_`
+/// and `-`, not starting with a digit) are rejected with a warning.
+fn resolve_app_name(env: Option<OsString>) -> String {
+    let Some(name) = env.filter(|value| !value.is_empty()) else {
+        return DEFAULT_APP_NAME.to_string();
+    };
+    match name.to_str() {
+        Some(name)
+            if !name.starts_with(|c: char| c.is_ascii_digit())
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') =>
+        {
+            name.to_string()
+        }
+        _ => {
+            tracing::warn!(
+                "Ignoring invalid {} {:?}, using \"{}\"",
+                APP_NAME_ENV_VAR,
+                name,
+                DEFAULT_APP_NAME
+            );
+            DEFAULT_APP_NAME.to_string()
+        }
+    }
+}
+
+/// Clamp `value` of the setting `key` to `[min, max]`, warning when it was
+/// outside. NaN becomes `min`.
+fn clamp_setting(key: &str, value: &mut f64, min: f64, max: f64) {
+    if (min..=max).contains(value) {
+        return;
+    }
+    let clamped = if value.is_nan() {
+        min
+    } else {
+        value.clamp(min, max)
+    };
+    tracing::warn!("{} = {} is out of range, using {}", key, value, clamped);
+    *value = clamped;
+}
+
+/// Whether a boolean environment variable is set. Empty, `0` and `false`
+/// count as unset.
+pub fn env_flag_set(value: Option<OsString>) -> bool {
+    value.is_some_and(|value| !matches!(value.to_str(), Some("" | "0" | "false")))
+}
+
+impl BehaviorConfig {
+    /// Whether windows with `app_id` are listed in `ignore_app_ids`
+    pub fn ignores_app_id(&self, app_id: Option<&str>) -> bool {
+        app_id.is_some_and(|app_id| {
+            self.ignore_app_ids
+                .iter()
+                .any(|pattern| glob_match(pattern, app_id))
+        })
+    }
+}
+
+/// Match `text` against `pattern`, where `*` stands for any run of
+/// characters (including none) and everything else matches literally
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let Some((first, rest)) = pattern.split_once('*') else {
+        return pattern == text;
+    };
+    let Some(mut remaining) = text.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = rest.split('*').collect();
+    let last = parts.pop().unwrap_or_default();
+    for part in parts {
+        match remaining.find(part) {
+            Some(at) => remaining = &remaining[at + part.len()..],
+            None => return false,
+        }
+    }
+    remaining.len() >= last.len() && remaining.ends_with(last)
+}
+
+impl std::fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => match Config::config_path() {
+                Ok(path) => write!(f, "{}", path.display()),
+                Err(_) => write!(f, "the default config path"),
+            },
+            Self::File(path) => write!(f, "{}", path.display()),
+            Self::Stdin(_) => write!(f, "stdin"),
+        }
+    }
 }
 
 impl Config {
-    /// Load configuration from the default path or create default config
+    /// Select where `load` reads from. Only the first call has an effect;
+    /// without one, the default path is used.
+    pub fn set_source(source: ConfigSource) {
+        if CONFIG_SOURCE.set(source).is_err() {
+            tracing::warn!("Config source already set, ignoring");
+        }
+    }
+
+    /// The active config source
+    pub fn source() -> &'static ConfigSource {
+        CONFIG_SOURCE.get_or_init(|| ConfigSource::Default)
+    }
+
+    /// Load configuration from the active source. Only the default path is
+    /// ever created with defaults; explicit sources are never written.
     pub fn load() -> Result<Self> {
-        let config_path = Self::config_path();
+        match Self::source() {
+            ConfigSource::Default => {
+                let config_path = Self::config_path()?;
+                if config_path.exists() {
+                    Self::load_file(&config_path)
+                } else if env_flag_set(std::env::var_os(NO_WRITE_DEFAULT_ENV_VAR)) {
+                    tracing::info!(
+                        "No config at {}, using defaults without writing them ({} is set)",
+                        config_path.display(),
+                        NO_WRITE_DEFAULT_ENV_VAR
+                    );
+                    Ok(Config::default())
+                } else {
+                    // Create default config file
+                    let config = Config::default();
+                    config.save_default()?;
+                    Ok(config)
+                }
+            }
+            ConfigSource::File(path) => Self::load_file(path),
+            ConfigSource::Stdin(contents) => {
+                Self::parse(contents).context("Failed to parse config from stdin")
+            }
+        }
+    }
 
-        if config_path.exists() {
-            let contents = std::fs::read_to_string(&config_path).with_context(|| {
-                format!("Failed to read config file: {}", config_path.display())
-            })?;
+    /// Read and parse a config file
+    fn load_file(config_path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(config_path)
+            .with_context(|| format!("Failed to read config file: {}", config_path.display()))?;
 
-            let config: Config = toml::from_str(&contents).with_context(|| {
-                format!("Failed to parse config file: {}", config_path.display())
-            })?;
+        Self::parse(&contents)
+            .with_context(|| format!("Failed to parse config file: {}", config_path.display()))
+    }
 
-            Ok(config)
-        } else {
-            // Create default config file
-            let config = Config::default();
-            config.save_default()?;
-            Ok(config)
+    /// Parse and validate configuration from TOML text
+    ///
+    /// `#[serde(default)]` would silently ignore typos, so keys are first
+    /// checked against the known fields of each section.
+    pub fn parse(contents: &str) -> Result<Self> {
+        let table: toml::Table = toml::from_str(contents)?;
+        check_unknown_keys(&table)?;
+
+        // Deserialize from the text (not the table) to keep line numbers in
+        // type errors
+        let mut config: Config = toml::from_str(contents)?;
+        config.clamp_values();
+        config.validate_presets()?;
+        Ok(config)
+    }
+
+    /// Pull numeric values that would break the layout or reach Cairo out of
+    /// range back into range, warning about each one
+    fn clamp_values(&mut self) {
+        let defaults = DisplayConfig::default();
+        for (key, value, default) in [
+            (
+                "display.max_width_percent",
+                &mut self.display.max_width_percent,
+                defaults.max_width_percent,
+            ),
+            (
+                "display.max_height_percent",
+                &mut self.display.max_height_percent,
+                defaults.max_height_percent,
+            ),
+        ] {
+            // A fraction of the screen in (0.0, 1.0]; nothing sensible is
+            // left of a zero or negative one
+            let clamped = if *value > 1.0 {
+                1.0
+            } else if *value > 0.0 {
+                continue;
+            } else {
+                default
+            };
+            tracing::warn!("{} = {} is out of range, using {}", key, value, clamped);
+            *value = clamped;
+        }
+
+        let appearance = &mut self.appearance;
+        let behavior = &mut self.behavior;
+        for (key, value) in [
+            (
+                "appearance.background_opacity",
+                &mut appearance.background_opacity,
+            ),
+            ("appearance.window_opacity", &mut appearance.window_opacity),
+            (
+                "appearance.focused_opacity",
+                &mut appearance.focused_opacity,
+            ),
+            ("behavior.idle_dim_opacity", &mut behavior.idle_dim_opacity),
+            ("behavior.overview_opacity", &mut behavior.overview_opacity),
+        ] {
+            clamp_setting(key, value, 0.0, 1.0);
+        }
+        for (key, value) in [
+            ("appearance.border_width", &mut appearance.border_width),
+            ("appearance.border_radius", &mut appearance.border_radius),
+            ("appearance.gap", &mut appearance.gap),
+            (
+                "appearance.element_spacing",
+                &mut appearance.element_spacing,
+            ),
+        ] {
+            clamp_setting(key, value, 0.0, f64::INFINITY);
         }
     }
 
-    /// Get the configuration file path
-    pub fn config_path() -> PathBuf {
-        dirs::config_dir()
-            .expect("Failed to determine config directory. Please set XDG_CONFIG_HOME or HOME environment variable.")
-            .join("nirimap")
-            .join("config.toml")
+    /// Return a copy of this config with the named preset's overrides merged
+    /// on top of the `[display]` and `[appearance]` sections.
+    pub fn with_preset(&self, name: &str) -> Result<Self> {
+        let preset = self
+            .presets
+            .iter()
+            .find(|p| p.name == name)
+            .with_context(|| format!("Unknown preset: {}", name))?;
+
+        let mut config = self.clone();
+        config.display = merge_section(&self.display, &preset.display)
+            .with_context(|| format!("Invalid [display] override in preset '{}'", name))?;
+        config.appearance = merge_section(&self.appearance, &preset.appearance)
+            .with_context(|| format!("Invalid [appearance] override in preset '{}'", name))?;
+        config.clamp_values();
+        Ok(config)
+    }
+
+    /// Check that preset names are unique and every preset applies cleanly
+    fn validate_presets(&self) -> Result<()> {
+        let mut seen = HashSet::new();
+        for preset in &self.presets {
+            if preset.name.is_empty() {
+                anyhow::bail!("Preset is missing a name");
+            }
+            if !seen.insert(preset.name.as_str()) {
+                anyhow::bail!("Duplicate preset name: {}", preset.name);
+            }
+            self.with_preset(&preset.name)?;
+        }
+        Ok(())
+    }
+
+    /// Get the configuration file path. Fails when neither
+    /// `XDG_CONFIG_HOME` nor `HOME` is set.
+    pub fn config_path() -> Result<PathBuf> {
+        config_path_in(dirs::config_dir(), app_name())
     }
 
     /// Save default configuration to disk
     fn save_default(&self) -> Result<()> {
-        let config_path = Self::config_path();
-        if let Some(parent) = config_path.parent() {
+        let config_path = Self::config_path()?;
+        Self::write_default_config(&config_path, false)?;
+        tracing::info!("Created default config at {}", config_path.display());
+        Ok(())
+    }
+
+    /// Write the documented default config (`DEFAULT_CONFIG`) to `path`,
+    /// creating parent directories. Refuses to overwrite an existing file
+    /// unless `force` is set.
+    pub fn write_default_config(path: &Path, force: bool) -> Result<()> {
+        if path.exists() && !force {
+            anyhow::bail!(
+                "{} already exists (pass --force to overwrite)",
+                path.display()
+            );
+        }
+
+        if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent).with_context(|| {
                 format!("Failed to create config directory: {}", parent.display())
             })?;
         }
 
-        let default_config = r##"[display]
+        std::fs::write(path, DEFAULT_CONFIG)
+            .with_context(|| format!("Failed to write default config: {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Set `display.anchor` in the config file, leaving the rest of it
+    /// (comments included) untouched
+    #[cfg(feature = "ui")]
+    pub fn persist_anchor(anchor: Anchor) -> Result<()> {
+        let path = Self::source().persist_path()?;
+        let contents = if path.exists() {
+            std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read config file: {}", path.display()))?
+        } else {
+            String::new()
+        };
+        let updated = with_display_anchor(&contents, anchor)?;
+        // Never write back a file that no longer loads
+        Self::parse(&updated).context("Refusing to save the anchor: the config file is invalid")?;
+        std::fs::write(&path, updated)
+            .with_context(|| format!("Failed to write config file: {}", path.display()))
+    }
+}
+
+/// `contents` with its `[display]` `anchor` set to `anchor`. The existing
+/// line is rewritten in place, keeping its comment where it was; otherwise
+/// the key is added at the top of the section (created if missing).
+#[cfg(feature = "ui")]
+fn with_display_anchor(contents: &str, anchor: Anchor) -> Result<String> {
+    let value = toml::Value::try_from(anchor)?.to_string();
+    let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+
+    let mut section = None;
+    let mut display_header = None;
+    for (i, line) in lines.iter_mut().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('[') {
+            let name = trimmed
+                .trim_start_matches('[')
+                .split(']')
+                .next()
+                .unwrap_or_default()
+                .trim();
+            section = Some(name.to_string());
+            if name == "display" && display_header.is_none() {
+                display_header = Some(i);
+            }
+            continue;
+        }
+        let Some((key, rest)) = line.split_once('=') else {
+            continue;
+        };
+        if section.as_deref() != Some("display") || key.trim() != "anchor" {
+            continue;
+        }
+        let (old_value, comment) = rest.split_at(rest.find('#').unwrap_or(rest.len()));
+        let new_value = format!(" {}", value);
+        *line = if comment.is_empty() {
+            format!("{}={}", key, new_value)
+        } else {
+            // Keep the comment in its column when there's room
+            let padding = old_value.len().saturating_sub(new_value.len()).max(1);
+            format!("{}={}{}{}", key, new_value, " ".repeat(padding), comment)
+        };
+        return Ok(join_lines(&lines, contents));
+    }
+
+    let entry = format!("anchor = {}", value);
+    match display_header {
+        Some(header) => lines.insert(header + 1, entry),
+        None => {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push("[display]".to_string());
+            lines.push(entry);
+        }
+    }
+    Ok(join_lines(&lines, contents))
+}
+
+/// Join rewritten lines, keeping a trailing newline if `original` had one
+/// (or was empty)
+#[cfg(feature = "ui")]
+fn join_lines(lines: &[String], original: &str) -> String {
+    let mut text = lines.join("\n");
+    if original.is_empty() || original.ends_with('\n') {
+        text.push('\n');
+    }
+    text
+}
+
+/// The annotated default config file, written on first run or by
+/// `nirimap --write-default-config`
+pub const DEFAULT_CONFIG: &str = r##"[display]
 height = 100              # Per-workspace row height in pixels
                           # In "current" mode: total widget height
                           # In "all" mode: height of one workspace row
@@ -201,9 +1069,34 @@ anchor = "top-right"      # Position: top-left, top-center, top-right,
                           #           bottom-left, bottom-center, bottom-right, center
 margin_x = 10             # Horizontal margin from edge
 margin_y = 10             # Vertical margin from edge
+margin_top = -1           # Per-edge margins, e.g. margin_top = 40 to sit below a bar
+margin_bottom = -1        # (-1 = margin_y for top/bottom, margin_x for left/right)
+margin_left = -1
+margin_right = -1
 workspace_mode = "all"    # Which workspaces to show:
                           #   "all"     - stack every workspace vertically (Overview-style)
                           #   "current" - show only the active workspace
+focus_mode = false        # Zoom into the focused column of the active workspace
+                          # (falls back to workspace_mode when nothing is focused)
+focus_neighbors = 0       # Columns on each side of the focused one shown in focus_mode
+overflow = "compress"     # Workspaces wider than the minimap ("current"/focus mode):
+                          #   "compress" - shrink until the whole workspace fits
+                          #   "scroll"   - keep the scale, scroll to the focused column
+follow_focus = false      # Move to the output with the focused window (same anchor corner)
+max_columns = 0           # Max columns drawn per workspace, centered on the focused one;
+                          # the rest are shown as "+N" at the edges (0 = unlimited)
+density = "full"          # "full" draws every window, "compact" one bar per column
+compact_threshold = 0     # Use compact density on workspaces with at least this many
+                          # tiled windows (0 = never)
+match_monitor_aspect = false # Shape the minimap like its output (width = height x aspect,
+                          # capped by max_width_percent) instead of fitting the content;
+                          # wider content follows overflow
+output = ""               # Output connector to show the minimap on, e.g. "DP-1"
+                          # (empty = compositor default). Requires a restart.
+namespace = ""            # Layer-shell namespace matched by Niri layer rules (empty = the
+                          # app name, "nirimap" by default). Requires a restart.
+keyboard_interactivity = "none" # "none", "on-demand" or "exclusive" (grabs the keyboard!)
+                          # Requires a restart.
 
 [appearance]
 background = "#1e1e2e"    # Background color (hex)
@@ -212,7 +1105,11 @@ focused_color = "#89b4fa" # Focused window highlight
 border_color = "#6c7086"  # Window border color
 border_width = 1          # Window border thickness
 border_radius = 2         # Corner radius for window rectangles
+stacked_border_radius = -1 # Corner radius in columns of several windows (-1 = border_radius)
+corner_style = "rounded"  # "rounded", "squircle" (smooth superellipse) or "sharp" (no radius)
 gap = 2                   # Gap between windows (in minimap pixels)
+gap_mode = "fixed"        # "fixed" (use gap) or "from-niri" (Niri's gaps, scaled like windows)
+preserve_column_spacing = false # Keep the real distance between on-screen columns (false = packed)
 background_opacity = 0.0  # Background opacity (0.0 = transparent, 1.0 = opaque)
                           # Applies in both "current" and "all" modes
 window_opacity = 0.7      # Fill opacity for unfocused windows (0 = outlines only)
@@ -220,6 +1117,49 @@ focused_opacity = 1.0     # Fill opacity for the focused window
 workspace_gap = 4                            # Vertical gap between stacked workspaces ("all" mode)
 active_workspace_border_color = "#89b4fa"    # Highlight border for active workspace ("all" mode)
 active_workspace_border_width = 2            # Highlight border thickness ("all" mode)
+urgent_color = "#f38ba8"  # Fill color for windows requesting attention
+urgent_blink = false      # Blink urgent windows between urgent_color and window_color
+urgent_blink_ms = 500     # Duration of each blink phase in milliseconds
+enforce_contrast = false  # Nudge brightness of adjacent windows with near-identical fills,
+                          # and of fills too close to the background color
+blur_behind = false       # Cap background_opacity at 0.5 so compositor blur shows through
+reverse_columns = false   # Mirror horizontally: first column on the right (right-to-left)
+flip_vertical = false     # Mirror vertically: stack windows and workspaces bottom-up
+vertical_align = "top"    # Where shorter columns sit in their row: "top", "center" or "bottom"
+viewport_color = "#cdd6f4" # Outline color of the visible viewport (behavior.show_viewport)
+focus_ring_width = 0      # Extra ring around the focused window (0 = disabled)
+focus_ring_color = "#f9e2af" # Color of the focused-window ring
+show_column_counts = false # Show the window count on columns with more than one window
+label_color = "#cdd6f4"   # Text color for labels (column counts)
+column_active_color = ""  # Fill for each stacked/tabbed column's active window, e.g. "#74c7ec"
+                          # (empty = off; the focused window keeps focused_color)
+fullscreen_color = ""     # Fill for the focused window while fullscreen (empty = focused_color)
+scale_borders = false     # Scale border_width/border_radius with the minimap. When true they
+                          # are in workspace pixels (e.g. border_width = 16, border_radius = 24)
+window_animations = false # Fade/scale windows in on open and out on close (~120 ms)
+workspace_transition_ms = 0 # Slide between workspaces in "current" mode over this many ms
+                          # (0 = switch instantly), e.g. 200
+column_backdrop = false   # Paint a panel behind each column's windows
+column_backdrop_color = "#313244" # Color of the per-column panels
+column_separator_width = 0.0 # Line between neighbouring columns (0 = disabled)
+column_separator_color = "#45475a" # Color of the lines between columns
+recency_fade = false      # Fade windows not focused recently (stale ones down to 30% of window_opacity)
+pixel_snap = false        # Snap window rects to whole pixels: crisper edges (esp. with
+                          # border_width = 1), but gaps/sizes may vary by a pixel
+antialias = true          # Smooth edges; false gives hard pixels and jagged corners
+show_icons = false        # Draw app icons inside window rectangles (skipped when too small)
+auto_contrast_border = false # 1px black or white outline around the minimap, contrasting background
+floating_indicator = "none" # While a floating window is focused: "none", "column" (highlight
+                          # the tiled column under it) or "glyph" (small corner icon)
+floating_strip = false    # List the active workspace's floating windows as small boxes
+                          # in a strip below the tiled layout
+element_spacing = 2       # Space between the window layout and extras like the
+                          # floating strip
+workspace_colors = {}     # Background per active workspace, by name or id, e.g.
+                          # { main = "#1e3a5f", "2" = "#3b1f2b" } (needs background_opacity > 0)
+show_workspace_number = false # Badge with the active workspace's name (or index)
+workspace_number_corner = "top-left" # "top-left", "top-right", "bottom-left" or "bottom-right"
+workspace_number_color = "#cdd6f4" # Text color of the workspace number badge
 
 [behavior]
 show_on_overview = true        # Keep visible in Niri overview mode
@@ -229,19 +1169,170 @@ show_for_floating_windows = false # When always_visible = false, surface the min
                                   # floating-window events (focus to/from a floating window,
                                   # floating window spawn). Off by default since floating
                                   # windows aren't drawn on the minimap.
+show_viewport = false          # Outline the slice of each workspace visible on the monitor
+interactive = false            # Receive pointer input: hovering keeps the minimap shown
+                               # and shows window titles, clicking a window (or
+                               # workspace row) focuses it.
+                               # false = fully click-through. Requires a restart.
+hide_when_empty = false        # Hide while the active workspace has no tiled windows
+                               # (overrides always_visible)
+poll_interval_ms = 0           # Periodically re-sync the full state from Niri in case
+                               # events were missed (0 = off). Requires a restart.
+show_triggers = ["new-window", "focus-change", "workspace-change", "layout-change"]
+                               # Events that show the minimap (when not always_visible)
+idle_dim_opacity = 1.0         # Dim to this opacity after hide_timeout_ms instead of
+                               # hiding; also with always_visible (1.0 = disabled)
+sticky_workspaces = []         # Workspace names or ids that keep the minimap up, e.g. ["main"]
+overview_opacity = 1.0         # Opacity while Niri's overview is open (1.0 = unchanged)
+overview_all_workspaces = false # While the overview is open, show every workspace with the
+                               # inactive ones dimmed (back to workspace_mode on close)
+reload_resyncs_state = false   # Also re-sync the full state from Niri on config reload
+hide_empty_trailing_workspace = true # Don't draw Niri's empty last workspace in "all"
+                               # mode, even while on it (named workspaces are always shown)
+workspace_switch_cooldown_ms = 150 # Minimum time between shows for workspace switches
+                               # (0 = show on every switch)
+focus_hold_ms = 50             # A focus change right after another must stick this long
+                               # before it's drawn, e.g. while dragging (0 = off)
+metrics_enable = false         # Report runtime counters via `nirimap msg metrics`
+export_ws_port = 0             # Stream state updates as JSON over a websocket on this local
+                               # port (0 = off). Needs the export-ws build feature and a restart.
+ignore_app_ids = []            # Windows left out entirely (no space, no shows), `*` is a
+                               # wildcard, e.g. ["xdg-desktop-portal*"]
+
+# Named presets override [display]/[appearance] keys at runtime:
+#   nirimap msg preset minimal
+#   nirimap msg clear-preset
+#
+# [[presets]]
+# name = "minimal"
+# appearance = { border_width = 0, window_opacity = 0.4 }
+#
+# [[presets]]
+# name = "detailed"
+# display = { height = 160 }
+# appearance = { border_width = 2, background_opacity = 0.6 }
 "##;
 
-        std::fs::write(&config_path, default_config).with_context(|| {
-            format!("Failed to write default config: {}", config_path.display())
-        })?;
+/// Overlay `overrides` onto a serialized copy of `base` and deserialize the
+/// result. Keys that don't exist on `base` are rejected.
+fn merge_section<T: Serialize + DeserializeOwned>(base: &T, overrides: &toml::Table) -> Result<T> {
+    let mut table = match toml::Value::try_from(base)? {
+        toml::Value::Table(table) => table,
+        _ => anyhow::bail!("Config section did not serialize to a table"),
+    };
+    for (key, value) in overrides {
+        if !table.contains_key(key) {
+            anyhow::bail!("Unknown key: {}", key);
+        }
+        table.insert(key.clone(), value.clone());
+    }
+    Ok(toml::Value::Table(table).try_into()?)
+}
 
-        tracing::info!("Created default config at {}", config_path.display());
-        Ok(())
+/// Keys of a config section, taken from its serialized defaults. Every field
+/// must serialize (no skipped `None`s) for this to be complete.
+fn section_keys<T: Serialize + Default>() -> HashSet<String> {
+    match toml::Value::try_from(T::default()) {
+        Ok(toml::Value::Table(table)) => table.keys().cloned().collect(),
+        _ => HashSet::new(),
+    }
+}
+
+/// Reject keys that don't match any config field, listing each one with the
+/// section it appeared in and the closest known key when there is one.
+fn check_unknown_keys(table: &toml::Table) -> Result<()> {
+    let top_level: HashSet<String> = ["display", "appearance", "behavior", "presets"]
+        .iter()
+        .map(|k| k.to_string())
+        .collect();
+    let preset_keys: HashSet<String> = ["name", "display", "appearance"]
+        .iter()
+        .map(|k| k.to_string())
+        .collect();
+
+    let mut unknown = Vec::new();
+    check_section_keys("", table.keys(), &top_level, &mut unknown);
+    for (section, value) in table {
+        let known = match section.as_str() {
+            "display" => section_keys::<DisplayConfig>(),
+            "appearance" => section_keys::<AppearanceConfig>(),
+            "behavior" => section_keys::<BehaviorConfig>(),
+            "presets" => {
+                // Override keys are checked when the preset is applied
+                for (i, preset) in value.as_array().into_iter().flatten().enumerate() {
+                    if let Some(preset) = preset.as_table() {
+                        check_section_keys(
+                            &format!("presets.{}", i),
+                            preset.keys(),
+                            &preset_keys,
+                            &mut unknown,
+                        );
+                    }
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        if let Some(section_table) = value.as_table() {
+            check_section_keys(section, section_table.keys(), &known, &mut unknown);
+        }
     }
+
+    if !unknown.is_empty() {
+        anyhow::bail!("Unknown config keys: {}", unknown.join(", "));
+    }
+    Ok(())
+}
+
+/// Record every key of `section` not in `known` into `unknown`
+fn check_section_keys<'a>(
+    section: &str,
+    keys: impl Iterator<Item = &'a String>,
+    known: &HashSet<String>,
+    unknown: &mut Vec<String>,
+) {
+    for key in keys.filter(|key| !known.contains(*key)) {
+        let location = if section.is_empty() {
+            format!("'{}'", key)
+        } else {
+            format!("'{}' in [{}]", key, section)
+        };
+        match closest_key(key, known) {
+            Some(suggestion) => {
+                unknown.push(format!("{} (did you mean '{}'?)", location, suggestion))
+            }
+            None => unknown.push(location),
+        }
+    }
+}
+
+/// The known key within edit distance 2 of `key`, if any
+fn closest_key<'a>(key: &str, known: &'a HashSet<String>) -> Option<&'a str> {
+    known
+        .iter()
+        .map(|candidate| (edit_distance(key, candidate), candidate))
+        .filter(|(distance, _)| *distance <= 2)
+        .min()
+        .map(|(_, candidate)| candidate.as_str())
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != *cb);
+            current.push(substitution.min(prev[j + 1] + 1).min(current[j] + 1));
+        }
+        prev = current;
+    }
+    prev[b.len()]
 }
 
 /// RGBA color representation
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Color {
     pub r: f64,
     pub g: f64,
@@ -269,12 +1360,162 @@ impl Color {
             a: 1.0,
         })
     }
+
+    /// Format as a `#rrggbb` hex string (alpha is dropped)
+    pub fn to_hex(self) -> String {
+        let channel = |v: f64| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        format!(
+            "#{:02x}{:02x}{:02x}",
+            channel(self.r),
+            channel(self.g),
+            channel(self.b)
+        )
+    }
+
+    /// The same color with alpha `a`
+    pub fn with_alpha(self, a: f64) -> Self {
+        Self { a, ..self }
+    }
+
+    /// Linear interpolation towards `other`: `t = 0.0` is this color,
+    /// `t = 1.0` is `other`. `t` is clamped to that range.
+    pub fn blend(self, other: Color, t: f64) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: f64, b: f64| a + (b - a) * t;
+        Self {
+            r: mix(self.r, other.r),
+            g: mix(self.g, other.g),
+            b: mix(self.b, other.b),
+            a: mix(self.a, other.a),
+        }
+    }
+
+    /// Perceived luminance (0.0 - 1.0) using the Rec. 601 weights
+    pub fn luminance(&self) -> f64 {
+        0.299 * self.r + 0.587 * self.g + 0.114 * self.b
+    }
+
+    /// Black or white, whichever contrasts more with this color
+    pub fn contrasting(&self) -> Self {
+        let v = if self.luminance() < 0.5 { 1.0 } else { 0.0 };
+        Self {
+            r: v,
+            g: v,
+            b: v,
+            a: 1.0,
+        }
+    }
+
+    /// Multiply the RGB channels by `factor`, clamping to the valid range.
+    /// Alpha is left unchanged.
+    pub fn scale_brightness(self, factor: f64) -> Self {
+        Self {
+            r: (self.r * factor).clamp(0.0, 1.0),
+            g: (self.g * factor).clamp(0.0, 1.0),
+            b: (self.b * factor).clamp(0.0, 1.0),
+            a: self.a,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn no_stdin() -> Result<String> {
+        panic!("stdin should not be read")
+    }
+
+    #[test]
+    fn test_config_source_precedence() {
+        // Flag beats the environment variable
+        assert_eq!(
+            ConfigSource::resolve(
+                Some("/etc/nirimap.toml"),
+                Some("/env.toml".into()),
+                no_stdin
+            )
+            .unwrap(),
+            ConfigSource::File(PathBuf::from("/etc/nirimap.toml"))
+        );
+        // Environment variable beats the default path
+        assert_eq!(
+            ConfigSource::resolve(None, Some("/env.toml".into()), no_stdin).unwrap(),
+            ConfigSource::File(PathBuf::from("/env.toml"))
+        );
+        assert_eq!(
+            ConfigSource::resolve(None, None, no_stdin).unwrap(),
+            ConfigSource::Default
+        );
+        // An empty variable counts as unset
+        assert_eq!(
+            ConfigSource::resolve(None, Some("".into()), no_stdin).unwrap(),
+            ConfigSource::Default
+        );
+    }
+
+    #[test]
+    fn test_config_path_without_home() {
+        let err = config_path_in(None, DEFAULT_APP_NAME).unwrap_err();
+        assert!(err.to_string().contains("XDG_CONFIG_HOME"));
+
+        assert_eq!(
+            config_path_in(Some(PathBuf::from("/home/user/.config")), "work").unwrap(),
+            PathBuf::from("/home/user/.config/work/config.toml")
+        );
+    }
+
+    #[test]
+    fn test_resolve_app_name() {
+        assert_eq!(resolve_app_name(None), "nirimap");
+        assert_eq!(resolve_app_name(Some("".into())), "nirimap");
+        assert_eq!(
+            resolve_app_name(Some("nirimap-work".into())),
+            "nirimap-work"
+        );
+        assert_eq!(resolve_app_name(Some("second_map".into())), "second_map");
+        // Not valid in a GTK application id (or a path component)
+        assert_eq!(resolve_app_name(Some("a.b".into())), "nirimap");
+        assert_eq!(resolve_app_name(Some("../x".into())), "nirimap");
+        assert_eq!(resolve_app_name(Some("2nd".into())), "nirimap");
+    }
+
+    #[test]
+    fn test_env_flag_set() {
+        assert!(env_flag_set(Some("1".into())));
+        assert!(env_flag_set(Some("yes".into())));
+        assert!(!env_flag_set(None));
+        assert!(!env_flag_set(Some("".into())));
+        assert!(!env_flag_set(Some("0".into())));
+        assert!(!env_flag_set(Some("false".into())));
+    }
+
+    #[test]
+    #[cfg(feature = "ui")]
+    fn test_persist_path_refuses_explicit_config() {
+        let source = ConfigSource::File(PathBuf::from("/nix/store/nirimap.toml"));
+        let err = source.persist_path().unwrap_err().to_string();
+        assert!(
+            err.contains("only writes the default config file"),
+            "{}",
+            err
+        );
+        assert!(ConfigSource::Stdin(String::new()).persist_path().is_err());
+    }
+
+    #[test]
+    fn test_config_source_stdin() {
+        let source = ConfigSource::resolve(Some("-"), Some("/env.toml".into()), || {
+            Ok("[display]\nheight = 42\n".to_string())
+        })
+        .unwrap();
+        assert_eq!(source.watch_path().unwrap(), None);
+        let ConfigSource::Stdin(contents) = source else {
+            panic!("expected stdin source");
+        };
+        assert_eq!(Config::parse(&contents).unwrap().display.height, 42);
+    }
+
     #[test]
     fn test_default_config_values() {
         let config = Config::default();
@@ -283,10 +1524,28 @@ mod tests {
         assert_eq!(config.display.height, 100);
         assert_eq!(config.display.max_width_percent, 0.5);
         assert_eq!(config.display.max_height_percent, 0.8);
+        assert!(!config.display.match_monitor_aspect);
         assert_eq!(config.display.anchor, Anchor::TopRight);
         assert_eq!(config.display.margin_x, 10);
         assert_eq!(config.display.margin_y, 10);
+        assert_eq!(config.display.margin_top, -1);
+        assert_eq!(config.display.margin_bottom, -1);
+        assert_eq!(config.display.margin_left, -1);
+        assert_eq!(config.display.margin_right, -1);
         assert_eq!(config.display.workspace_mode, WorkspaceMode::All);
+        assert!(!config.display.focus_mode);
+        assert_eq!(config.display.focus_neighbors, 0);
+        assert_eq!(config.display.overflow, Overflow::Compress);
+        assert!(!config.display.follow_focus);
+        assert_eq!(config.display.max_columns, 0);
+        assert_eq!(config.display.density, Density::Full);
+        assert_eq!(config.display.compact_threshold, 0);
+        assert_eq!(config.display.output, "");
+        assert_eq!(config.display.namespace, "");
+        assert_eq!(
+            config.display.keyboard_interactivity,
+            KeyboardInteractivity::None
+        );
 
         // Test appearance defaults
         assert_eq!(config.appearance.background, "#1e1e2e");
@@ -295,19 +1554,331 @@ mod tests {
         assert_eq!(config.appearance.border_color, "#6c7086");
         assert_eq!(config.appearance.border_width, 1.0);
         assert_eq!(config.appearance.border_radius, 2.0);
+        assert_eq!(config.appearance.stacked_border_radius, -1.0);
+        assert_eq!(config.appearance.corner_style, CornerStyle::Rounded);
         assert_eq!(config.appearance.gap, 2.0);
+        assert_eq!(config.appearance.gap_mode, GapMode::Fixed);
+        assert!(!config.appearance.preserve_column_spacing);
         assert_eq!(config.appearance.background_opacity, 0.0);
         assert_eq!(config.appearance.window_opacity, 0.7);
         assert_eq!(config.appearance.focused_opacity, 1.0);
         assert_eq!(config.appearance.workspace_gap, 4.0);
         assert_eq!(config.appearance.active_workspace_border_color, "#89b4fa");
         assert_eq!(config.appearance.active_workspace_border_width, 2.0);
+        assert_eq!(config.appearance.urgent_color, "#f38ba8");
+        assert!(!config.appearance.urgent_blink);
+        assert_eq!(config.appearance.urgent_blink_ms, 500);
+        assert!(!config.appearance.enforce_contrast);
+        assert!(!config.appearance.blur_behind);
+        assert!(!config.appearance.reverse_columns);
+        assert!(!config.appearance.flip_vertical);
+        assert_eq!(config.appearance.vertical_align, VerticalAlign::Top);
+        assert_eq!(config.appearance.viewport_color, "#cdd6f4");
+        assert_eq!(config.appearance.focus_ring_width, 0.0);
+        assert_eq!(config.appearance.focus_ring_color, "#f9e2af");
+        assert!(!config.appearance.show_column_counts);
+        assert_eq!(config.appearance.label_color, "#cdd6f4");
+        assert_eq!(config.appearance.column_active_color, "");
+        assert_eq!(config.appearance.fullscreen_color, "");
+        assert!(!config.appearance.scale_borders);
+        assert!(!config.appearance.window_animations);
+        assert!(!config.appearance.column_backdrop);
+        assert_eq!(config.appearance.column_backdrop_color, "#313244");
+        assert_eq!(config.appearance.column_separator_width, 0.0);
+        assert_eq!(config.appearance.column_separator_color, "#45475a");
+        assert!(!config.appearance.recency_fade);
+        assert!(!config.appearance.pixel_snap);
+        assert!(config.appearance.antialias);
+        assert!(!config.appearance.show_icons);
+        assert!(!config.appearance.auto_contrast_border);
+        assert_eq!(
+            config.appearance.floating_indicator,
+            FloatingIndicator::None
+        );
+        assert!(config.appearance.workspace_colors.is_empty());
+        assert!(!config.appearance.floating_strip);
+        assert_eq!(config.appearance.element_spacing, 2.0);
+        assert!(!config.appearance.show_workspace_number);
+        assert_eq!(config.appearance.workspace_transition_ms, 0);
+        assert_eq!(config.appearance.workspace_number_corner, Corner::TopLeft);
+        assert_eq!(config.appearance.workspace_number_color, "#cdd6f4");
 
         // Test behavior defaults
         assert!(config.behavior.show_on_overview);
         assert!(config.behavior.always_visible);
         assert_eq!(config.behavior.hide_timeout_ms, 2000);
         assert!(!config.behavior.show_for_floating_windows);
+        assert!(!config.behavior.show_viewport);
+        assert!(!config.behavior.interactive);
+        assert!(!config.behavior.hide_when_empty);
+        assert_eq!(config.behavior.poll_interval_ms, 0);
+        assert!(!config.behavior.reload_resyncs_state);
+        assert!(config.behavior.hide_empty_trailing_workspace);
+        assert_eq!(config.behavior.workspace_switch_cooldown_ms, 150);
+        assert_eq!(config.behavior.focus_hold_ms, 50);
+        assert!(!config.behavior.metrics_enable);
+        assert_eq!(config.behavior.export_ws_port, 0);
+        assert!(config.behavior.ignore_app_ids.is_empty());
+        assert_eq!(
+            config.behavior.show_triggers,
+            vec![
+                ShowTrigger::NewWindow,
+                ShowTrigger::FocusChange,
+                ShowTrigger::WorkspaceChange,
+                ShowTrigger::LayoutChange,
+            ]
+        );
+        assert_eq!(config.behavior.idle_dim_opacity, 1.0);
+        assert_eq!(config.behavior.overview_opacity, 1.0);
+        assert!(!config.behavior.overview_all_workspaces);
+        assert!(config.behavior.sticky_workspaces.is_empty());
+    }
+
+    #[test]
+    fn test_ignores_app_id() {
+        let behavior = BehaviorConfig {
+            ignore_app_ids: vec![
+                "xdg-desktop-portal*".to_string(),
+                "org.gnome.Calculator".to_string(),
+                "*Picker*".to_string(),
+            ],
+            ..Default::default()
+        };
+        assert!(behavior.ignores_app_id(Some("xdg-desktop-portal-gtk")));
+        assert!(behavior.ignores_app_id(Some("xdg-desktop-portal")));
+        assert!(behavior.ignores_app_id(Some("org.gnome.Calculator")));
+        assert!(behavior.ignores_app_id(Some("com.example.ColorPicker-dialog")));
+        // Matching is case-sensitive, like app ids themselves
+        assert!(!behavior.ignores_app_id(Some("com.example.colorpicker")));
+        assert!(!behavior.ignores_app_id(Some("org.gnome.Calculator2")));
+        assert!(!behavior.ignores_app_id(Some("firefox")));
+        assert!(!behavior.ignores_app_id(None));
+
+        assert!(glob_match("a*b*c", "abc"));
+        assert!(glob_match("a*b*c", "a-b-b-c"));
+        assert!(!glob_match("a*b*c", "a-c"));
+        assert!(!glob_match("ab*ba", "aba"));
+        assert!(glob_match("*", ""));
+    }
+
+    #[test]
+    fn test_out_of_range_values_are_clamped() {
+        let config = Config::parse(
+            r#"
+            [display]
+            max_width_percent = 1.5
+            max_height_percent = -0.2
+
+            [appearance]
+            background_opacity = -0.2
+            window_opacity = 3.0
+            focused_opacity = nan
+            border_width = -1
+            border_radius = -4
+            gap = -2
+
+            [behavior]
+            idle_dim_opacity = 1.2
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.display.max_width_percent, 1.0);
+        // Nothing sensible to clamp a non-positive fraction to
+        assert_eq!(config.display.max_height_percent, 0.8);
+        assert_eq!(config.appearance.background_opacity, 0.0);
+        assert_eq!(config.appearance.window_opacity, 1.0);
+        assert_eq!(config.appearance.focused_opacity, 0.0);
+        assert_eq!(config.appearance.border_width, 0.0);
+        assert_eq!(config.appearance.border_radius, 0.0);
+        assert_eq!(config.appearance.gap, 0.0);
+        assert_eq!(config.behavior.idle_dim_opacity, 1.0);
+
+        // In-range values are kept as they are
+        let config = Config::parse(
+            r#"
+            [display]
+            max_width_percent = 1.0
+
+            [appearance]
+            background_opacity = 0.0
+            gap = 12
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.display.max_width_percent, 1.0);
+        assert_eq!(config.appearance.background_opacity, 0.0);
+        assert_eq!(config.appearance.gap, 12.0);
+    }
+
+    #[test]
+    fn test_preset_values_are_clamped() {
+        let config = Config::parse(
+            r#"
+            [[presets]]
+            name = "loud"
+            appearance = { window_opacity = 2.0 }
+            "#,
+        )
+        .unwrap();
+        let loud = config.with_preset("loud").unwrap();
+        assert_eq!(loud.appearance.window_opacity, 1.0);
+    }
+
+    #[test]
+    fn test_anchor_next_cycles_through_every_anchor() {
+        let mut anchor = Anchor::TopLeft;
+        let mut seen = Vec::new();
+        for _ in 0..7 {
+            seen.push(anchor);
+            anchor = anchor.next();
+        }
+        assert_eq!(anchor, Anchor::TopLeft);
+        // Every anchor is visited once
+        for (i, anchor) in seen.iter().enumerate() {
+            assert!(!seen[i + 1..].contains(anchor));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "ui")]
+    fn test_with_display_anchor() {
+        // Rewritten in place, comment kept in its column
+        let updated = with_display_anchor(DEFAULT_CONFIG, Anchor::BottomLeft).unwrap();
+        assert!(updated.contains(
+            "anchor = \"bottom-left\"    # Position: top-left, top-center, top-right,\n"
+        ));
+        assert_eq!(updated.lines().count(), DEFAULT_CONFIG.lines().count());
+        assert_eq!(
+            Config::parse(&updated).unwrap().display.anchor,
+            Anchor::BottomLeft
+        );
+
+        // Added to an existing section; other sections' keys are left alone
+        let updated = with_display_anchor(
+            "[display]\nheight = 80\n\n[appearance]\nanchor_note = 1\n",
+            Anchor::Center,
+        )
+        .unwrap();
+        assert_eq!(
+            updated,
+            "[display]\nanchor = \"center\"\nheight = 80\n\n[appearance]\nanchor_note = 1\n"
+        );
+
+        // Section created when missing
+        let updated =
+            with_display_anchor("[behavior]\nalways_visible = false\n", Anchor::TopLeft).unwrap();
+        assert_eq!(
+            updated,
+            "[behavior]\nalways_visible = false\n\n[display]\nanchor = \"top-left\"\n"
+        );
+        assert_eq!(
+            with_display_anchor("", Anchor::TopLeft).unwrap(),
+            "[display]\nanchor = \"top-left\"\n"
+        );
+    }
+
+    #[test]
+    fn test_edge_margins_fall_back_to_axis_margins() {
+        // Legacy configs only set margin_x/margin_y
+        let legacy = Config::parse(
+            r#"
+            [display]
+            margin_x = 4
+            margin_y = 8
+            "#,
+        )
+        .unwrap();
+        assert_eq!(legacy.display.top_margin(), 8);
+        assert_eq!(legacy.display.bottom_margin(), 8);
+        assert_eq!(legacy.display.left_margin(), 4);
+        assert_eq!(legacy.display.right_margin(), 4);
+
+        let below_bar = Config::parse(
+            r#"
+            [display]
+            margin_y = 8
+            margin_top = 40
+            margin_left = 0
+            "#,
+        )
+        .unwrap();
+        assert_eq!(below_bar.display.top_margin(), 40);
+        assert_eq!(below_bar.display.bottom_margin(), 8);
+        assert_eq!(below_bar.display.left_margin(), 0);
+        assert_eq!(below_bar.display.right_margin(), 10);
+    }
+
+    #[test]
+    fn test_parse_workspace_colors() {
+        let config = Config::parse(
+            r##"
+            [appearance]
+            background_opacity = 0.8
+
+            [appearance.workspace_colors]
+            main = "#1e3a5f"
+            "2" = "#3b1f2b"
+            "##,
+        )
+        .unwrap();
+        assert_eq!(config.appearance.background_opacity, 0.8);
+        assert_eq!(
+            config
+                .appearance
+                .workspace_colors
+                .get("main")
+                .map(String::as_str),
+            Some("#1e3a5f")
+        );
+        assert_eq!(
+            config
+                .appearance
+                .workspace_colors
+                .get("2")
+                .map(String::as_str),
+            Some("#3b1f2b")
+        );
+    }
+
+    #[test]
+    fn test_default_config_template_matches_defaults() {
+        let parsed = Config::parse(DEFAULT_CONFIG).unwrap();
+        let defaults = Config::default();
+
+        assert_eq!(parsed.display.height, defaults.display.height);
+        assert_eq!(parsed.display.anchor, defaults.display.anchor);
+        assert_eq!(
+            parsed.display.workspace_mode,
+            defaults.display.workspace_mode
+        );
+        assert_eq!(parsed.appearance.background, defaults.appearance.background);
+        assert_eq!(parsed.appearance.gap, defaults.appearance.gap);
+        assert_eq!(
+            parsed.behavior.hide_timeout_ms,
+            defaults.behavior.hide_timeout_ms
+        );
+        assert!(parsed.presets.is_empty());
+    }
+
+    #[test]
+    fn test_write_default_config_refuses_overwrite_without_force() {
+        let dir =
+            std::env::temp_dir().join(format!("nirimap-write-default-{}", std::process::id()));
+        let path = dir.join("nested").join("config.toml");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        // Creates missing parent directories
+        Config::write_default_config(&path, false).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), DEFAULT_CONFIG);
+
+        std::fs::write(&path, "# customized").unwrap();
+        assert!(Config::write_default_config(&path, false).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "# customized");
+
+        Config::write_default_config(&path, true).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), DEFAULT_CONFIG);
+
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 
     #[test]
@@ -363,6 +1934,134 @@ mod tests {
         assert_eq!(config.appearance.background, "#1e1e2e");
     }
 
+    #[test]
+    fn test_with_preset_merges_overrides() {
+        let toml = r##"
+            [appearance]
+            border_width = 3
+            window_color = "#111111"
+
+            [[presets]]
+            name = "minimal"
+            display = { height = 50 }
+            appearance = { border_width = 0 }
+        "##;
+        let config = Config::parse(toml).unwrap();
+        let preset = config.with_preset("minimal").unwrap();
+
+        assert_eq!(preset.display.height, 50);
+        assert_eq!(preset.appearance.border_width, 0.0);
+        // Untouched keys keep the base config's values, not the defaults
+        assert_eq!(preset.appearance.window_color, "#111111");
+        // Base config is unchanged
+        assert_eq!(config.appearance.border_width, 3.0);
+
+        assert!(config.with_preset("missing").is_err());
+    }
+
+    #[test]
+    fn test_presets_reject_duplicate_names() {
+        let toml = r#"
+            [[presets]]
+            name = "a"
+
+            [[presets]]
+            name = "a"
+        "#;
+        assert!(Config::parse(toml).is_err());
+    }
+
+    #[test]
+    fn test_show_triggers_parse() {
+        let config = Config::parse(
+            r#"
+            [behavior]
+            show_triggers = ["workspace-change"]
+        "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.behavior.show_triggers,
+            vec![ShowTrigger::WorkspaceChange]
+        );
+
+        // An empty list disables event-driven showing entirely
+        let config = Config::parse("[behavior]\nshow_triggers = []").unwrap();
+        assert!(config.behavior.show_triggers.is_empty());
+
+        assert!(Config::parse("[behavior]\nshow_triggers = [\"hover\"]").is_err());
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected_with_section() {
+        let err = Config::parse(
+            r##"
+            [appearance]
+            backround = "#000000"
+        "##,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("'backround' in [appearance]"), "{}", err);
+        assert!(err.contains("did you mean 'background'"), "{}", err);
+
+        // Misplaced key: valid in [behavior], not in [display]
+        let err = Config::parse(
+            r#"
+            [display]
+            always_visible = false
+        "#,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("'always_visible' in [display]"), "{}", err);
+
+        // Unknown section and unknown preset key
+        assert!(Config::parse("[colors]\n").is_err());
+        let err = Config::parse(
+            r#"
+            [[presets]]
+            name = "minimal"
+            behaviour = { always_visible = false }
+        "#,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("'behaviour' in [presets.0]"), "{}", err);
+    }
+
+    #[test]
+    fn test_all_known_keys_are_accepted() {
+        // Every key of the documented default config must pass the check
+        Config::parse(DEFAULT_CONFIG).unwrap();
+        Config::parse("").unwrap();
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("gap", "gap"), 0);
+        assert_eq!(edit_distance("backround", "background"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_presets_reject_unknown_or_invalid_keys() {
+        let unknown = r##"
+            [[presets]]
+            name = "typo"
+            appearance = { backround = "#000000" }
+        "##;
+        assert!(Config::parse(unknown).is_err());
+
+        let wrong_type = r#"
+            [[presets]]
+            name = "bad"
+            display = { height = "tall" }
+        "#;
+        assert!(Config::parse(wrong_type).is_err());
+    }
+
     #[test]
     fn test_color_from_hex_with_hash() {
         let color = Color::from_hex("#1e1e2e").unwrap();
@@ -413,6 +2112,67 @@ mod tests {
         assert_eq!(white.b, 1.0);
     }
 
+    #[test]
+    fn test_color_contrasting() {
+        let white = Color::from_hex("#ffffff").unwrap();
+        let black = Color::from_hex("#000000").unwrap();
+        assert_eq!(Color::from_hex("#1e1e2e").unwrap().contrasting(), white);
+        assert_eq!(Color::from_hex("#f5e0dc").unwrap().contrasting(), black);
+        assert_eq!(white.contrasting(), black);
+        assert_eq!(black.contrasting(), white);
+    }
+
+    #[test]
+    fn test_color_luminance() {
+        assert_eq!(Color::from_hex("#000000").unwrap().luminance(), 0.0);
+        assert!((Color::from_hex("#ffffff").unwrap().luminance() - 1.0).abs() < 1e-9);
+        // Green contributes more than blue
+        let green = Color::from_hex("#00ff00").unwrap();
+        let blue = Color::from_hex("#0000ff").unwrap();
+        assert!(green.luminance() > blue.luminance());
+    }
+
+    #[test]
+    fn test_color_hex_round_trip() {
+        for hex in ["#1e1e2e", "#000000", "#ffffff", "#89b4fa"] {
+            assert_eq!(Color::from_hex(hex).unwrap().to_hex(), hex);
+        }
+        let out_of_range = Color {
+            r: 1.5,
+            g: -0.2,
+            b: 0.5,
+            a: 1.0,
+        };
+        assert_eq!(out_of_range.to_hex(), "#ff0080");
+    }
+
+    #[test]
+    fn test_color_blend_and_alpha() {
+        let black = Color::from_hex("#000000").unwrap();
+        let white = Color::from_hex("#ffffff").unwrap().with_alpha(0.0);
+
+        let mid = black.blend(white, 0.5);
+        assert_eq!((mid.r, mid.g, mid.b, mid.a), (0.5, 0.5, 0.5, 0.5));
+        assert_eq!(black.blend(white, 0.0), black);
+        assert_eq!(black.blend(white, 2.0), white);
+
+        assert_eq!(black.with_alpha(0.3).a, 0.3);
+        assert_eq!(black.with_alpha(0.3).r, 0.0);
+    }
+
+    #[test]
+    fn test_color_scale_brightness_clamps() {
+        let color = Color::from_hex("#808080").unwrap();
+        let brighter = color.scale_brightness(1.5);
+        assert!(brighter.luminance() > color.luminance());
+        let darker = color.scale_brightness(0.5);
+        assert!(darker.luminance() < color.luminance());
+
+        let white = Color::from_hex("#ffffff").unwrap().scale_brightness(2.0);
+        assert_eq!(white.r, 1.0);
+        assert_eq!(white.a, 1.0);
+    }
+
     #[test]
     fn test_color_alpha_is_always_one() {
         // Verify that alpha is always 1.0 regardless of input
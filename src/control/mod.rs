@@ -0,0 +1,81 @@
+//! The control socket: `nirimap msg` on the client side, and the server
+//! the UI runs to receive its commands
+
+use anyhow::{Context, Result};
+use std::io::{BufReader, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "ui")]
+mod server;
+
+#[cfg(feature = "ui")]
+pub use server::{
+    run_control_socket, snapshot_path, wake_control_socket, ControlCommand, ControlRequest,
+    WorkspaceModeChange,
+};
+
+/// Path of the control socket (`$XDG_RUNTIME_DIR/<app name>.sock`, see
+/// `config::app_name`)
+pub fn socket_path() -> PathBuf {
+    runtime_path(format!("{}.sock", crate::config::app_name()))
+}
+
+fn runtime_path(file_name: impl AsRef<Path>) -> PathBuf {
+    dirs::runtime_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join(file_name)
+}
+
+/// `command` with a relative `snapshot` path made absolute against `cwd`, so
+/// `nirimap msg snapshot out.png` writes next to the caller
+pub fn resolve_snapshot_path(command: &str, cwd: &Path) -> String {
+    let Some(path) = command
+        .trim()
+        .strip_prefix("snapshot")
+        .filter(|rest| rest.starts_with(char::is_whitespace))
+        .map(str::trim)
+    else {
+        return command.to_string();
+    };
+    format!("snapshot {}", cwd.join(path).display())
+}
+
+/// Send a command to a running instance and return its reply
+pub fn send_command(command: &str) -> Result<String> {
+    let path = socket_path();
+    let stream = UnixStream::connect(&path).with_context(|| {
+        format!(
+            "Failed to connect to {}. Is nirimap running?",
+            path.display()
+        )
+    })?;
+
+    let mut writer = &stream;
+    writeln!(writer, "{}", command)?;
+
+    // The server closes the connection after its reply
+    let mut reply = String::new();
+    BufReader::new(&stream).read_to_string(&mut reply)?;
+    Ok(reply.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_snapshot_path() {
+        let cwd = Path::new("/home/user");
+        assert_eq!(
+            resolve_snapshot_path("snapshot shots/minimap.png", cwd),
+            "snapshot /home/user/shots/minimap.png"
+        );
+        assert_eq!(
+            resolve_snapshot_path("snapshot /tmp/minimap.png", cwd),
+            "snapshot /tmp/minimap.png"
+        );
+        assert_eq!(resolve_snapshot_path("snapshot", cwd), "snapshot");
+        assert_eq!(resolve_snapshot_path("metrics", cwd), "metrics");
+    }
+}
@@ -0,0 +1,282 @@
+use anyhow::{Context, Result};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use super::{runtime_path, socket_path};
+use crate::config::WorkspaceMode;
+
+/// How long a client waits for the UI thread to answer a command
+const REPLY_TIMEOUT_MS: u64 = 2000;
+
+/// How long a connected client may take to send its command line. Clients
+/// are served one at a time, so a silent one would block every later
+/// command (and shutdown).
+const READ_TIMEOUT_MS: u64 = 1000;
+
+/// Commands accepted on the control socket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlCommand {
+    /// Apply a named preset on top of the base config
+    Preset(String),
+    /// Drop the active preset and return to the base config
+    ClearPreset,
+    /// Override `display.workspace_mode` at runtime
+    WorkspaceMode(WorkspaceModeChange),
+    /// Render the minimap to a PNG file, at `snapshot_path()` unless given
+    /// (as an absolute path)
+    Snapshot(Option<PathBuf>),
+    /// Report runtime counters in Prometheus text format
+    Metrics,
+    /// Move the minimap to the next `display.anchor`, also writing it to the
+    /// config file when `persist` is set
+    CycleAnchor { persist: bool },
+}
+
+/// Argument of the `workspace-mode` command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkspaceModeChange {
+    /// Switch to the given mode
+    Set(WorkspaceMode),
+    /// Switch between `all` and `current`
+    Toggle,
+    /// Drop the override and use the config's mode again
+    Reset,
+}
+
+impl ControlCommand {
+    /// Parse a single command line, e.g. `preset minimal`
+    pub fn parse(line: &str) -> Result<Self> {
+        let mut parts = line.split_whitespace();
+        let command = parts.next().context("Empty command")?;
+
+        match command {
+            "preset" => {
+                let name = parts.next().context("Usage: preset <name>")?;
+                Ok(Self::Preset(name.to_string()))
+            }
+            "clear-preset" => Ok(Self::ClearPreset),
+            "workspace-mode" => {
+                let usage = "Usage: workspace-mode <all|current|toggle|reset>";
+                let change = match parts.next().context(usage)? {
+                    "all" => WorkspaceModeChange::Set(WorkspaceMode::All),
+                    "current" => WorkspaceModeChange::Set(WorkspaceMode::Current),
+                    "toggle" => WorkspaceModeChange::Toggle,
+                    "reset" => WorkspaceModeChange::Reset,
+                    _ => anyhow::bail!(usage),
+                };
+                Ok(Self::WorkspaceMode(change))
+            }
+            "snapshot" => {
+                // The rest of the line, so paths may contain spaces
+                let path = line.trim()[command.len()..].trim();
+                if path.is_empty() {
+                    return Ok(Self::Snapshot(None));
+                }
+                let path = PathBuf::from(path);
+                // Relative paths would resolve against nirimap's working
+                // directory, not the caller's
+                if !path.is_absolute() {
+                    anyhow::bail!("Usage: snapshot [absolute path]");
+                }
+                Ok(Self::Snapshot(Some(path)))
+            }
+            "metrics" => Ok(Self::Metrics),
+            "cycle-anchor" => match parts.next() {
+                None => Ok(Self::CycleAnchor { persist: false }),
+                Some("--persist") => Ok(Self::CycleAnchor { persist: true }),
+                Some(_) => anyhow::bail!("Usage: cycle-anchor [--persist]"),
+            },
+            other => anyhow::bail!("Unknown command: {}", other),
+        }
+    }
+}
+
+/// A command received on the control socket, with a channel for the reply
+pub struct ControlRequest {
+    pub command: ControlCommand,
+    pub reply: mpsc::Sender<String>,
+}
+
+/// Default path of `snapshot` images
+/// (`$XDG_RUNTIME_DIR/<app name>-snapshot.png`)
+pub fn snapshot_path() -> PathBuf {
+    runtime_path(format!("{}-snapshot.png", crate::config::app_name()))
+}
+
+/// Listen on the control socket and forward commands to the UI thread.
+///
+/// Each connection carries one command line and receives one reply line
+/// (`ok`, or `error: ...`); `metrics` replies with several lines. Once
+/// `shutdown` is set, the next connection (see `wake_control_socket`) makes
+/// it remove the socket and return.
+pub fn run_control_socket(tx: mpsc::Sender<ControlRequest>, shutdown: &AtomicBool) -> Result<()> {
+    let path = socket_path();
+
+    if path.exists() {
+        // Refuse to steal the socket from a running instance; otherwise it's
+        // stale (left behind by a crash) and safe to remove.
+        if UnixStream::connect(&path).is_ok() {
+            anyhow::bail!(
+                "Control socket {} is in use by another nirimap instance",
+                path.display()
+            );
+        }
+        std::fs::remove_file(&path).with_context(|| {
+            format!("Failed to remove stale control socket: {}", path.display())
+        })?;
+    }
+
+    let listener = UnixListener::bind(&path)
+        .with_context(|| format!("Failed to bind control socket: {}", path.display()))?;
+    tracing::info!("Listening for commands on {}", path.display());
+
+    for stream in listener.incoming() {
+        if shutdown.load(Ordering::Relaxed) {
+            break;
+        }
+        match stream {
+            Ok(stream) => {
+                if let Err(e) = handle_client(stream, &tx) {
+                    tracing::warn!("Control client error: {}", e);
+                }
+            }
+            Err(e) => tracing::warn!("Control socket accept error: {}", e),
+        }
+    }
+
+    drop(listener);
+    std::fs::remove_file(&path)
+        .with_context(|| format!("Failed to remove control socket: {}", path.display()))?;
+    tracing::debug!("Removed control socket {}", path.display());
+    Ok(())
+}
+
+/// Unblock a `run_control_socket` waiting for connections, so it notices
+/// the shutdown flag
+pub fn wake_control_socket() {
+    let _ = UnixStream::connect(socket_path());
+}
+
+/// Read one command from a client, hand it to the UI thread and write back
+/// the reply.
+fn handle_client(stream: UnixStream, tx: &mpsc::Sender<ControlRequest>) -> Result<()> {
+    stream.set_read_timeout(Some(Duration::from_millis(READ_TIMEOUT_MS)))?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+
+    let reply = match ControlCommand::parse(line.trim()) {
+        Ok(command) => {
+            let (reply_tx, reply_rx) = mpsc::channel();
+            tx.send(ControlRequest {
+                command,
+                reply: reply_tx,
+            })
+            .context("UI thread is gone")?;
+            reply_rx
+                .recv_timeout(Duration::from_millis(REPLY_TIMEOUT_MS))
+                .unwrap_or_else(|_| "error: timed out waiting for nirimap".to_string())
+        }
+        Err(e) => format!("error: {}", e),
+    };
+
+    let mut writer = &stream;
+    writeln!(writer, "{}", reply)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_preset() {
+        assert_eq!(
+            ControlCommand::parse("preset minimal").unwrap(),
+            ControlCommand::Preset("minimal".to_string())
+        );
+        // Surrounding whitespace is ignored
+        assert_eq!(
+            ControlCommand::parse("  preset   detailed \n").unwrap(),
+            ControlCommand::Preset("detailed".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_preset_requires_name() {
+        assert!(ControlCommand::parse("preset").is_err());
+    }
+
+    #[test]
+    fn test_parse_clear_preset() {
+        assert_eq!(
+            ControlCommand::parse("clear-preset").unwrap(),
+            ControlCommand::ClearPreset
+        );
+    }
+
+    #[test]
+    fn test_parse_workspace_mode() {
+        assert_eq!(
+            ControlCommand::parse("workspace-mode current").unwrap(),
+            ControlCommand::WorkspaceMode(WorkspaceModeChange::Set(WorkspaceMode::Current))
+        );
+        assert_eq!(
+            ControlCommand::parse("workspace-mode toggle").unwrap(),
+            ControlCommand::WorkspaceMode(WorkspaceModeChange::Toggle)
+        );
+        assert_eq!(
+            ControlCommand::parse("workspace-mode reset").unwrap(),
+            ControlCommand::WorkspaceMode(WorkspaceModeChange::Reset)
+        );
+        assert!(ControlCommand::parse("workspace-mode").is_err());
+        assert!(ControlCommand::parse("workspace-mode overview").is_err());
+    }
+
+    #[test]
+    fn test_parse_snapshot() {
+        assert_eq!(
+            ControlCommand::parse("snapshot").unwrap(),
+            ControlCommand::Snapshot(None)
+        );
+        assert_eq!(
+            ControlCommand::parse("snapshot /tmp/minimap.png").unwrap(),
+            ControlCommand::Snapshot(Some(PathBuf::from("/tmp/minimap.png")))
+        );
+        assert_eq!(
+            ControlCommand::parse("snapshot /tmp/my minimap.png").unwrap(),
+            ControlCommand::Snapshot(Some(PathBuf::from("/tmp/my minimap.png")))
+        );
+        assert!(ControlCommand::parse("snapshot minimap.png").is_err());
+    }
+
+    #[test]
+    fn test_parse_metrics() {
+        assert_eq!(
+            ControlCommand::parse("metrics").unwrap(),
+            ControlCommand::Metrics
+        );
+    }
+
+    #[test]
+    fn test_parse_cycle_anchor() {
+        assert_eq!(
+            ControlCommand::parse("cycle-anchor").unwrap(),
+            ControlCommand::CycleAnchor { persist: false }
+        );
+        assert_eq!(
+            ControlCommand::parse("cycle-anchor --persist").unwrap(),
+            ControlCommand::CycleAnchor { persist: true }
+        );
+        assert!(ControlCommand::parse("cycle-anchor top-left").is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_and_empty() {
+        assert!(ControlCommand::parse("").is_err());
+        assert!(ControlCommand::parse("explode").is_err());
+    }
+}
@@ -0,0 +1,155 @@
+//! Broadcast state updates as JSON over a local websocket
+//! (`behavior.export_ws_port`), so an external renderer can draw the minimap.
+//! The websocket server needs the `export-ws` build feature.
+
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use crate::ipc::StateUpdate;
+
+#[cfg(feature = "export-ws")]
+use {
+    crate::ipc,
+    anyhow::{Context, Result},
+    std::io::ErrorKind,
+    std::net::{TcpListener, TcpStream},
+    std::sync::atomic::Ordering,
+    std::sync::Mutex,
+    std::thread,
+    std::time::Duration,
+    tungstenite::handshake::server::{ErrorResponse, Request, Response},
+    tungstenite::http::StatusCode,
+    tungstenite::{Message, WebSocket},
+};
+
+/// How long a send may block on a client that stopped reading before the
+/// client is dropped
+#[cfg(feature = "export-ws")]
+const CLIENT_WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// How long a new connection may take to send its upgrade request. The
+/// handshake runs on the accept thread, so a silent client would otherwise
+/// hold up every later one and shutdown.
+#[cfg(feature = "export-ws")]
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Handle for broadcasting to the connected websocket clients
+#[derive(Clone, Default)]
+pub struct Exporter {
+    #[cfg(feature = "export-ws")]
+    clients: Arc<Mutex<Vec<WebSocket<TcpStream>>>>,
+}
+
+impl Exporter {
+    /// Serve `ws://127.0.0.1:<port>` from a background thread until
+    /// `shutdown` is set. `None` when `port` is 0 (off) or the feature is
+    /// missing.
+    pub fn start(port: u16, shutdown: Arc<AtomicBool>) -> Option<(Self, JoinHandle<()>)> {
+        if port == 0 {
+            return None;
+        }
+        #[cfg(not(feature = "export-ws"))]
+        {
+            let _ = shutdown;
+            tracing::warn!(
+                "behavior.export_ws_port is set, but nirimap was built without the \
+                 `export-ws` feature"
+            );
+            None
+        }
+        #[cfg(feature = "export-ws")]
+        {
+            let exporter = Self::default();
+            let listener = exporter.clone();
+            let handle = thread::spawn(move || {
+                if let Err(e) = listener.listen(port, &shutdown) {
+                    tracing::error!("State export error: {:#}", e);
+                }
+            });
+            Some((exporter, handle))
+        }
+    }
+
+    /// Send `update` to every client, dropping the ones that went away
+    pub fn broadcast(&self, update: &StateUpdate) {
+        #[cfg(not(feature = "export-ws"))]
+        let _ = update;
+        #[cfg(feature = "export-ws")]
+        {
+            let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+            if clients.is_empty() {
+                return;
+            }
+            let text = match serde_json::to_string(update) {
+                Ok(text) => text,
+                Err(e) => {
+                    tracing::warn!("Failed to serialize state update: {}", e);
+                    return;
+                }
+            };
+            clients.retain_mut(|socket| match socket.send(Message::text(text.clone())) {
+                Ok(()) => true,
+                Err(e) => {
+                    tracing::debug!("Dropping state export client: {}", e);
+                    false
+                }
+            });
+        }
+    }
+
+    /// Accept clients until `shutdown` is set. Every new client is followed
+    /// by a fresh `full-state` snapshot (sent to all clients), so it never
+    /// has to wait for one.
+    #[cfg(feature = "export-ws")]
+    fn listen(&self, port: u16, shutdown: &AtomicBool) -> Result<()> {
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .with_context(|| format!("Failed to bind state export port {}", port))?;
+        // Non-blocking, so the shutdown flag is checked between accepts
+        listener.set_nonblocking(true)?;
+        tracing::info!("Exporting state updates on ws://127.0.0.1:{}", port);
+
+        while !shutdown.load(Ordering::Relaxed) {
+            let stream = match listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(ipc::SHUTDOWN_CHECK_INTERVAL);
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            stream.set_nonblocking(false)?;
+            stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+            stream.set_write_timeout(Some(CLIENT_WRITE_TIMEOUT))?;
+            match tungstenite::accept_hdr(stream, reject_browsers) {
+                Ok(socket) => {
+                    self.clients
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .push(socket);
+                    tracing::debug!("State export client connected");
+                    ipc::resync_state(|update| self.broadcast(&update));
+                }
+                Err(e) => tracing::warn!("State export handshake failed: {}", e),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Refuse handshakes from web pages. Browsers always send an `Origin`
+/// header, and any page could otherwise open the local port and read every
+/// window title and app id; local tools and scripts don't send one.
+#[cfg(feature = "export-ws")]
+// The error type is fixed by tungstenite's handshake callback
+#[allow(clippy::result_large_err)]
+fn reject_browsers(request: &Request, response: Response) -> Result<Response, ErrorResponse> {
+    if request.headers().contains_key("origin") {
+        let mut rejection = ErrorResponse::new(Some(
+            "Connections from web pages are not allowed".to_string(),
+        ));
+        *rejection.status_mut() = StatusCode::FORBIDDEN;
+        return Err(rejection);
+    }
+    Ok(response)
+}
@@ -1,5 +1,7 @@
 use anyhow::{Context, Result};
 use niri_ipc::socket::Socket;
+#[cfg(feature = "ui")]
+use niri_ipc::{Action, WorkspaceReferenceArg};
 use niri_ipc::{Reply, Request, Response};
 
 /// Client for communicating with Niri via IPC
@@ -9,14 +11,20 @@ pub struct NiriClient {
 
 impl NiriClient {
     /// Create a new client connected to the Niri socket
+    #[cfg(feature = "ui")]
     pub fn connect() -> Result<Self> {
         // Validate socket path before connecting
-        let socket_path =
-            std::env::var("NIRI_SOCKET").context("NIRI_SOCKET environment variable not set")?;
-        super::events::validate_socket_path(&socket_path)?;
+        Self::connect_to(&super::events::socket_path()?)
+    }
 
-        let socket =
-            Socket::connect().context("Failed to connect to Niri socket. Is Niri running?")?;
+    /// Create a new client connected to the Niri socket at `socket_path`
+    pub fn connect_to(socket_path: &str) -> Result<Self> {
+        let socket = Socket::connect_to(socket_path).with_context(|| {
+            format!(
+                "Failed to connect to Niri socket at {}. Is Niri running?",
+                socket_path
+            )
+        })?;
         Ok(Self { socket })
     }
 
@@ -38,6 +46,24 @@ impl NiriClient {
         }
     }
 
+    /// Focus the window with the given id
+    #[cfg(feature = "ui")]
+    pub fn focus_window(&mut self, id: u64) -> Result<()> {
+        let reply = self
+            .send(focus_window_request(id))
+            .with_context(|| format!("Failed to focus window {}", id))?;
+        expect_handled(reply, "FocusWindow")
+    }
+
+    /// Focus the workspace with the given id
+    #[cfg(feature = "ui")]
+    pub fn focus_workspace(&mut self, id: u64) -> Result<()> {
+        let reply = self
+            .send(focus_workspace_request(id))
+            .with_context(|| format!("Failed to focus workspace {}", id))?;
+        expect_handled(reply, "FocusWorkspace")
+    }
+
     /// Send a request and get a response
     fn send(&mut self, request: Request) -> Result<Response> {
         let reply: Reply = self
@@ -48,3 +74,58 @@ impl NiriClient {
         reply.map_err(|e| anyhow::anyhow!("Niri returned an error: {}", e))
     }
 }
+
+/// Build the action request that focuses a window
+#[cfg(feature = "ui")]
+fn focus_window_request(id: u64) -> Request {
+    Request::Action(Action::FocusWindow { id })
+}
+
+/// Build the action request that focuses a workspace by id
+#[cfg(feature = "ui")]
+fn focus_workspace_request(id: u64) -> Request {
+    Request::Action(Action::FocusWorkspace {
+        reference: WorkspaceReferenceArg::Id(id),
+    })
+}
+
+/// Actions are acknowledged with `Response::Handled`; anything else is a
+/// protocol mismatch.
+#[cfg(feature = "ui")]
+fn expect_handled(response: Response, action: &str) -> Result<()> {
+    match response {
+        Response::Handled => Ok(()),
+        other => anyhow::bail!("Unexpected response for {} action: {:?}", action, other),
+    }
+}
+
+#[cfg(all(test, feature = "ui"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_focus_window_request_serialization() {
+        let json = serde_json::to_value(focus_window_request(42)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "Action": { "FocusWindow": { "id": 42 } } })
+        );
+    }
+
+    #[test]
+    fn test_focus_workspace_request_serialization() {
+        let json = serde_json::to_value(focus_workspace_request(7)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "Action": { "FocusWorkspace": { "reference": { "Id": 7 } } } })
+        );
+    }
+
+    #[test]
+    fn test_expect_handled() {
+        assert!(expect_handled(Response::Handled, "FocusWindow").is_ok());
+
+        let err = expect_handled(Response::Windows(Vec::new()), "FocusWindow").unwrap_err();
+        assert!(err.to_string().contains("FocusWindow"));
+    }
+}
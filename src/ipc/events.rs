@@ -1,20 +1,26 @@
 use anyhow::{Context, Result};
 use niri_ipc::{Event, Request};
-use std::io::{BufRead, BufReader};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, ErrorKind};
 use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
 
+use crate::metrics;
 use crate::state::{MinimapState, Window, Workspace};
 
-/// State update messages sent to the UI
-#[derive(Debug, Clone)]
+/// State update messages sent to the UI, and as JSON
+/// (`{"update": "window-closed", "data": 12}`) to `export_ws_port` clients
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "update", content = "data", rename_all = "kebab-case")]
 pub enum StateUpdate {
     /// Full state refresh
     FullState(MinimapState),
-    /// A window was opened or changed
-    WindowChanged {
-        window: Window,
-        workspace_id: Option<u64>,
-    },
+    /// A window was opened or changed (possibly moving to another workspace)
+    WindowChanged(Window),
     /// A window was closed
     WindowClosed(u64),
     /// Window focus changed
@@ -30,22 +36,197 @@ pub enum StateUpdate {
         workspace_id: u64,
         active_window_id: Option<u64>,
     },
+    /// A window started or stopped requesting attention
+    WindowUrgencyChanged { id: u64, urgent: bool },
+    /// The overview opened or closed
+    OverviewToggled(bool),
+}
+
+/// Log target for state transitions, for bug reports:
+/// `RUST_LOG=nirimap::state=trace`
+pub const STATE_LOG_TARGET: &str = "nirimap::state";
+
+/// How often blocking background loops check the shutdown flag
+pub const SHUTDOWN_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Wait before the first attempt to reconnect to Niri; doubled after each
+/// failed attempt, up to `MAX_RECONNECT_DELAY`
+const RECONNECT_DELAY: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+impl StateUpdate {
+    /// Emit this update at trace level on `STATE_LOG_TARGET`, with its key
+    /// fields as structured fields
+    pub fn trace(&self) {
+        match self {
+            StateUpdate::FullState(state) => tracing::trace!(
+                target: STATE_LOG_TARGET,
+                update = "full-state",
+                windows = state.window_count(),
+                workspaces = state.workspaces.len(),
+                active_workspace_id = ?state.active_workspace_id,
+                focused_window_id = ?state.focused_window_id,
+            ),
+            StateUpdate::WindowChanged(window) => tracing::trace!(
+                target: STATE_LOG_TARGET,
+                update = "window-changed",
+                window_id = window.id,
+                workspace_id = ?window.workspace_id,
+                is_focused = window.is_focused,
+                is_floating = window.is_floating,
+                column_index = window.column_index,
+            ),
+            StateUpdate::WindowClosed(id) => tracing::trace!(
+                target: STATE_LOG_TARGET,
+                update = "window-closed",
+                window_id = id,
+            ),
+            StateUpdate::FocusChanged(id) => tracing::trace!(
+                target: STATE_LOG_TARGET,
+                update = "focus-changed",
+                window_id = ?id,
+            ),
+            StateUpdate::WorkspaceActivated { id, focused } => tracing::trace!(
+                target: STATE_LOG_TARGET,
+                update = "workspace-activated",
+                workspace_id = id,
+                focused,
+            ),
+            StateUpdate::LayoutsChanged(changes) => tracing::trace!(
+                target: STATE_LOG_TARGET,
+                update = "layouts-changed",
+                window_ids = ?changes.iter().map(|(id, _)| id).collect::<Vec<_>>(),
+            ),
+            StateUpdate::WorkspacesChanged(workspaces) => tracing::trace!(
+                target: STATE_LOG_TARGET,
+                update = "workspaces-changed",
+                workspace_ids = ?workspaces.iter().map(|ws| ws.id).collect::<Vec<_>>(),
+            ),
+            StateUpdate::WorkspaceActiveWindowChanged {
+                workspace_id,
+                active_window_id,
+            } => tracing::trace!(
+                target: STATE_LOG_TARGET,
+                update = "workspace-active-window-changed",
+                workspace_id,
+                window_id = ?active_window_id,
+            ),
+            StateUpdate::WindowUrgencyChanged { id, urgent } => tracing::trace!(
+                target: STATE_LOG_TARGET,
+                update = "window-urgency-changed",
+                window_id = id,
+                urgent,
+            ),
+            StateUpdate::OverviewToggled(is_open) => tracing::trace!(
+                target: STATE_LOG_TARGET,
+                update = "overview-toggled",
+                is_open,
+            ),
+        }
+    }
+}
+
+/// Run the event loop, sending state updates to the provided sender, until
+/// `shutdown` is set. When the event stream ends (e.g. Niri restarted) it
+/// reconnects and starts over with a `FullState`.
+pub fn run_event_loop<F>(shutdown: &AtomicBool, on_update: F) -> Result<()>
+where
+    F: FnMut(StateUpdate) + Send,
+{
+    run_event_loop_at(&socket_path()?, shutdown, on_update)
 }
 
-/// Run the event loop, sending state updates to the provided sender
-pub fn run_event_loop<F>(mut on_update: F) -> Result<()>
+/// `run_event_loop` against the Niri socket at `socket_path`. Fails if the
+/// first connection does; returns within `SHUTDOWN_CHECK_INTERVAL` of
+/// `shutdown` being set.
+fn run_event_loop_at<F>(socket_path: &str, shutdown: &AtomicBool, mut on_update: F) -> Result<()>
 where
     F: FnMut(StateUpdate) + Send,
 {
-    // First, get initial state
-    let initial_state = fetch_initial_state()?;
+    let mut reader = connect(socket_path, &mut on_update)?;
+    loop {
+        if let Err(e) = stream_events(&mut reader, shutdown, &mut on_update) {
+            tracing::warn!("{:#}", e);
+        }
+        if shutdown.load(Ordering::Relaxed) {
+            tracing::debug!("Event loop shutting down");
+            return Ok(());
+        }
+        tracing::warn!("Niri event stream ended, reconnecting");
+        match reconnect(socket_path, shutdown, &mut on_update) {
+            Some(new_reader) => reader = new_reader,
+            None => return Ok(()),
+        }
+    }
+}
+
+/// Fetch the complete state, emit it as a `FullState` and subscribe to the
+/// event stream
+fn connect<F>(socket_path: &str, on_update: &mut F) -> Result<BufReader<UnixStream>>
+where
+    F: FnMut(StateUpdate),
+{
+    let initial_state = fetch_initial_state(socket_path)?;
     on_update(StateUpdate::FullState(initial_state));
 
-    // Then subscribe to event stream
-    let reader = connect_event_stream()?;
+    // Reads time out so the shutdown flag is checked even while Niri is
+    // quiet
+    let reader = connect_event_stream(socket_path)?;
+    reader
+        .get_ref()
+        .set_read_timeout(Some(SHUTDOWN_CHECK_INTERVAL))?;
+    Ok(reader)
+}
+
+/// Retry `connect` with backoff until it succeeds, or until `shutdown` is
+/// set (`None`)
+fn reconnect<F>(
+    socket_path: &str,
+    shutdown: &AtomicBool,
+    on_update: &mut F,
+) -> Option<BufReader<UnixStream>>
+where
+    F: FnMut(StateUpdate),
+{
+    let mut delay = RECONNECT_DELAY;
+    loop {
+        if !sleep_unless_shutdown(delay, shutdown) {
+            return None;
+        }
+        match connect(socket_path, on_update) {
+            Ok(reader) => {
+                tracing::info!("Reconnected to Niri");
+                return Some(reader);
+            }
+            Err(e) => {
+                tracing::debug!("Reconnecting to Niri failed: {:#}", e);
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+            }
+        }
+    }
+}
 
-    for line in reader.lines() {
-        let line = line.context("Failed to read from event stream")?;
+/// Forward events from the stream until it ends or `shutdown` is set
+fn stream_events<F>(
+    reader: &mut BufReader<UnixStream>,
+    shutdown: &AtomicBool,
+    on_update: &mut F,
+) -> Result<()>
+where
+    F: FnMut(StateUpdate),
+{
+    // A line cut short by a timeout stays in the buffer and is completed by
+    // the next read
+    let mut buffer = Vec::new();
+    while !shutdown.load(Ordering::Relaxed) {
+        match reader.read_until(b'\n', &mut buffer) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => continue,
+            Err(e) => return Err(e).context("Failed to read from event stream"),
+        }
+        let line = String::from_utf8(std::mem::take(&mut buffer))
+            .context("Failed to read from event stream")?;
 
         // Skip empty lines
         if line.trim().is_empty() {
@@ -65,6 +246,8 @@ where
             }
         };
 
+        metrics::increment(&metrics::METRICS.events_received);
+
         // Convert to state update
         if let Some(update) = event_to_update(event) {
             on_update(update);
@@ -74,13 +257,66 @@ where
     Ok(())
 }
 
+/// Periodically re-fetch the complete state and emit it as a `FullState`.
+///
+/// A safety net for drift between the event stream and our derived state.
+/// Every poll uses its own short-lived connection, independent of the
+/// event-stream thread. Returns once `shutdown` is set.
+pub fn run_poll_loop<F>(interval: Duration, shutdown: &AtomicBool, mut on_update: F)
+where
+    F: FnMut(StateUpdate) + Send,
+{
+    while sleep_unless_shutdown(interval, shutdown) {
+        match socket_path().and_then(|path| fetch_initial_state(&path)) {
+            Ok(state) => on_update(StateUpdate::FullState(state)),
+            Err(e) => tracing::warn!("State poll failed: {:#}", e),
+        }
+    }
+}
+
+/// Sleep for `duration` in `SHUTDOWN_CHECK_INTERVAL` steps. Returns false
+/// (early) once `shutdown` is set.
+fn sleep_unless_shutdown(duration: Duration, shutdown: &AtomicBool) -> bool {
+    let deadline = Instant::now() + duration;
+    loop {
+        if shutdown.load(Ordering::Relaxed) {
+            return false;
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return true;
+        }
+        thread::sleep(remaining.min(SHUTDOWN_CHECK_INTERVAL));
+    }
+}
+
+/// Fetch the complete state once over a short-lived connection and emit it
+/// as a `FullState`. Blocks, so call it off the UI thread.
+pub fn resync_state<F>(on_update: F)
+where
+    F: FnOnce(StateUpdate),
+{
+    match socket_path().and_then(|path| fetch_initial_state(&path)) {
+        Ok(state) => on_update(StateUpdate::FullState(state)),
+        Err(e) => tracing::warn!("State resync failed: {:#}", e),
+    }
+}
+
 /// Fetch the initial complete state from Niri
-fn fetch_initial_state() -> Result<MinimapState> {
-    let mut client = super::client::NiriClient::connect()?;
+fn fetch_initial_state(socket_path: &str) -> Result<MinimapState> {
+    let mut client = super::client::NiriClient::connect_to(socket_path)?;
 
     let workspaces = client.get_workspaces()?;
     let windows = client.get_windows()?;
 
+    Ok(state_from_snapshot(workspaces, windows))
+}
+
+/// Build the complete state from Niri's workspace and window lists
+fn state_from_snapshot(
+    workspaces: Vec<niri_ipc::Workspace>,
+    windows: Vec<niri_ipc::Window>,
+) -> MinimapState {
     let mut state = MinimapState::new();
 
     // Process workspaces
@@ -88,9 +324,11 @@ fn fetch_initial_state() -> Result<MinimapState> {
         let workspace = Workspace {
             id: ws.id,
             idx: ws.idx,
+            name: ws.name.clone(),
             output: ws.output.clone(),
             is_active: ws.is_active,
             active_window_id: ws.active_window_id,
+            reported: true,
             ..Default::default()
         };
         state.workspaces.insert(ws.id, workspace);
@@ -112,7 +350,39 @@ fn fetch_initial_state() -> Result<MinimapState> {
         }
     }
 
-    Ok(state)
+    state
+}
+
+/// Niri socket path from `--socket`, overriding `NIRI_SOCKET`
+static SOCKET_OVERRIDE: OnceLock<String> = OnceLock::new();
+
+/// Connect to `path` instead of `$NIRI_SOCKET` from now on. Only the first
+/// call has an effect.
+pub fn set_socket_path(path: String) {
+    if SOCKET_OVERRIDE.set(path).is_err() {
+        tracing::warn!("Niri socket path already set, ignoring");
+    }
+}
+
+/// The validated Niri socket path: `--socket` > `NIRI_SOCKET`
+pub(super) fn socket_path() -> Result<String> {
+    let socket_path = resolve_socket_path(
+        SOCKET_OVERRIDE.get().map(String::as_str),
+        std::env::var("NIRI_SOCKET").ok(),
+    )?;
+    validate_socket_path(&socket_path)?;
+    Ok(socket_path)
+}
+
+/// Pick the socket path with precedence override > environment. An empty
+/// environment variable is treated as unset.
+fn resolve_socket_path(override_path: Option<&str>, env: Option<String>) -> Result<String> {
+    match override_path {
+        Some(path) => Ok(path.to_string()),
+        None => env.filter(|value| !value.is_empty()).context(
+            "NIRI_SOCKET environment variable not set (and no --socket given). Is Niri running?",
+        ),
+    }
 }
 
 /// Validate the socket path for security
@@ -123,7 +393,7 @@ pub(super) fn validate_socket_path(socket_path: &str) -> Result<()> {
 
     // Ensure the path is absolute (prevents relative path attacks)
     if !path.is_absolute() {
-        anyhow::bail!("NIRI_SOCKET must be an absolute path, got: {}", socket_path);
+        anyhow::bail!("Niri socket must be an absolute path, got: {}", socket_path);
     }
 
     // Check if the path is in expected locations for security
@@ -133,7 +403,7 @@ pub(super) fn validate_socket_path(socket_path: &str) -> Result<()> {
 
     if !is_expected_location {
         tracing::warn!(
-            "NIRI_SOCKET is in an unexpected location: {}. Expected /run/user/<uid>/ or /tmp/",
+            "Niri socket is in an unexpected location: {}. Expected /run/user/<uid>/ or /tmp/",
             socket_path
         );
     }
@@ -142,14 +412,8 @@ pub(super) fn validate_socket_path(socket_path: &str) -> Result<()> {
 }
 
 /// Connect to the event stream
-fn connect_event_stream() -> Result<BufReader<UnixStream>> {
-    let socket_path = std::env::var("NIRI_SOCKET")
-        .context("NIRI_SOCKET environment variable not set. Is Niri running?")?;
-
-    // Validate the socket path for security
-    validate_socket_path(&socket_path)?;
-
-    let stream = UnixStream::connect(&socket_path)
+fn connect_event_stream(socket_path: &str) -> Result<BufReader<UnixStream>> {
+    let stream = UnixStream::connect(socket_path)
         .with_context(|| format!("Failed to connect to Niri socket at {}", socket_path))?;
 
     // Send the EventStream request
@@ -181,7 +445,7 @@ fn connect_event_stream() -> Result<BufReader<UnixStream>> {
 
 /// Validate and convert 1-based indices from Niri to 0-based indices
 /// Returns (column_index, window_index) as 0-based values
-pub fn validate_and_convert_indices(col: usize, win_idx: usize, window_id: u64) -> (usize, usize) {
+fn validate_and_convert_indices(col: usize, win_idx: usize, window_id: u64) -> (usize, usize) {
     // Validate indices are >= 1 (Niri uses 1-based indexing)
     if col == 0 {
         tracing::warn!(
@@ -200,16 +464,77 @@ pub fn validate_and_convert_indices(col: usize, win_idx: usize, window_id: u64)
     (col.saturating_sub(1), win_idx.saturating_sub(1))
 }
 
+/// Smallest tile dimension kept from Niri, in logical pixels. Zero or
+/// negative sizes (e.g. mid-transition) are raised to this so layout math
+/// never divides by zero.
+pub const MIN_TILE_SIZE: f64 = 1.0;
+
+/// Clamp a tile size reported by Niri to at least `MIN_TILE_SIZE` per axis.
+/// Non-finite values are passed through (the layout skips such windows).
+pub fn sanitize_tile_size(size: (f64, f64), window_id: u64) -> (f64, f64) {
+    let clamp = |v: f64| {
+        if !v.is_finite() {
+            tracing::warn!("Non-finite tile size {} for window {}", v, window_id);
+            v
+        } else if v < MIN_TILE_SIZE {
+            tracing::debug!("Clamping tile size {} for window {}", v, window_id);
+            MIN_TILE_SIZE
+        } else {
+            v
+        }
+    };
+    (clamp(size.0), clamp(size.1))
+}
+
+/// Drop focus changes that are immediately superseded by another one.
+///
+/// Holding a focus keybind produces a burst of `FocusChanged` events; only
+/// the last of each consecutive run affects what is drawn.
+pub fn coalesce_focus_changes(updates: Vec<StateUpdate>) -> Vec<StateUpdate> {
+    let mut coalesced: Vec<StateUpdate> = Vec::with_capacity(updates.len());
+    for update in updates {
+        if matches!(update, StateUpdate::FocusChanged(_))
+            && matches!(coalesced.last(), Some(StateUpdate::FocusChanged(_)))
+        {
+            coalesced.pop();
+        }
+        coalesced.push(update);
+    }
+    coalesced
+}
+
+/// Drop window and focus changes for windows that close later in the same
+/// batch.
+///
+/// A window that opens and closes between two frames (e.g. a focused popup)
+/// would otherwise be added, show the minimap as a new or newly focused
+/// window and be removed again. The close is kept, so a window that existed
+/// before the batch is still removed. Run `coalesce_focus_changes` after this
+/// so the focus changes left over are merged.
+pub fn drop_changes_to_closed_windows(updates: Vec<StateUpdate>) -> Vec<StateUpdate> {
+    let mut closed_later = HashSet::new();
+    let mut kept: Vec<StateUpdate> = updates
+        .into_iter()
+        .rev()
+        .filter(|update| match update {
+            StateUpdate::WindowClosed(id) => {
+                closed_later.insert(*id);
+                true
+            }
+            StateUpdate::WindowChanged(window) => !closed_later.contains(&window.id),
+            StateUpdate::FocusChanged(Some(id)) => !closed_later.contains(id),
+            _ => true,
+        })
+        .collect();
+    kept.reverse();
+    kept
+}
+
 /// Convert a Niri event to a state update
 fn event_to_update(event: Event) -> Option<StateUpdate> {
     match event {
         Event::WindowOpenedOrChanged { window } => {
-            let workspace_id = window.workspace_id;
-            let model_window = niri_window_to_model(&window);
-            Some(StateUpdate::WindowChanged {
-                window: model_window,
-                workspace_id,
-            })
+            Some(StateUpdate::WindowChanged(niri_window_to_model(&window)))
         }
         Event::WindowClosed { id } => Some(StateUpdate::WindowClosed(id)),
         Event::WindowFocusChanged { id } => Some(StateUpdate::FocusChanged(id)),
@@ -225,6 +550,10 @@ fn event_to_update(event: Event) -> Option<StateUpdate> {
             workspace_id,
             active_window_id,
         }),
+        Event::WindowUrgencyChanged { id, urgent } => {
+            Some(StateUpdate::WindowUrgencyChanged { id, urgent })
+        }
+        Event::OverviewOpenedOrClosed { is_open } => Some(StateUpdate::OverviewToggled(is_open)),
         // Ignore other events for now
         _ => None,
     }
@@ -232,31 +561,83 @@ fn event_to_update(event: Event) -> Option<StateUpdate> {
 
 /// Convert a niri-ipc Window to our model Window
 fn niri_window_to_model(win: &niri_ipc::Window) -> Window {
-    let layout = &win.layout;
-
-    // Floating windows have pos_in_scrolling_layout = None
-    let is_floating = layout.pos_in_scrolling_layout.is_none();
-
-    // Extract position in scrolling layout (column, window_in_column)
-    let (column_index, window_index) = layout
-        .pos_in_scrolling_layout
-        .map(|(c, w)| validate_and_convert_indices(c, w, win.id))
-        .unwrap_or((0, 0));
-
-    Window {
+    let mut window = Window {
         id: win.id,
-        pos: layout.tile_pos_in_workspace_view,
-        size: layout.tile_size,
-        column_index,
-        window_index,
+        workspace_id: win.workspace_id,
+        app_id: win.app_id.clone(),
+        title: win.title.clone(),
+        // Filled in from the workspace when the window is filed
+        output: None,
+        pos: None,
+        size: (MIN_TILE_SIZE, MIN_TILE_SIZE),
+        column_index: 0,
+        window_index: 0,
         is_focused: win.is_focused,
-        is_floating,
+        is_floating: false,
+        is_urgent: win.is_urgent,
+        // Inferred from sibling tiles once the window is in the state
+        is_hidden: false,
+        is_column_active: false,
+        // Inferred from the output size by the UI
+        is_fullscreen: false,
+    };
+    apply_niri_layout(&mut window, &win.layout);
+    window
+}
+
+/// Copy a niri-ipc `WindowLayout` onto a model window.
+///
+/// This and `scrolling_position` are the only places that read niri-ipc
+/// layout fields (written against niri-ipc 26.x), so a representation change
+/// in a future niri-ipc release is a fix in this file only. Floating windows
+/// keep their last tiled indices.
+pub fn apply_niri_layout(window: &mut Window, layout: &niri_ipc::WindowLayout) {
+    window.pos = layout
+        .tile_pos_in_workspace_view
+        .filter(|(x, y)| x.is_finite() && y.is_finite());
+    window.size = sanitize_tile_size(layout.tile_size, window.id);
+
+    match scrolling_position(layout, window.id) {
+        Some((column_index, window_index)) => {
+            window.is_floating = false;
+            window.column_index = column_index;
+            window.window_index = window_index;
+        }
+        None => window.is_floating = true,
     }
 }
 
+/// 0-based (column, window-in-column) of a tiled window, or `None` for a
+/// floating one.
+///
+/// niri-ipc 26.x reports a 1-based `(column, tile)` pair. Should a future
+/// release add components (e.g. a tab index), only the destructuring here
+/// needs to change; out-of-range values are warned about and saturated by
+/// `validate_and_convert_indices`.
+fn scrolling_position(layout: &niri_ipc::WindowLayout, window_id: u64) -> Option<(usize, usize)> {
+    let (column, tile) = layout.pos_in_scrolling_layout?;
+    Some(validate_and_convert_indices(column, tile, window_id))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ipc::mock::MockNiri;
+
+    #[test]
+    fn test_resolve_socket_path_precedence() {
+        let env = Some("/run/user/1000/niri.sock".to_string());
+        assert_eq!(
+            resolve_socket_path(Some("/tmp/mock.sock"), env.clone()).unwrap(),
+            "/tmp/mock.sock"
+        );
+        assert_eq!(
+            resolve_socket_path(None, env).unwrap(),
+            "/run/user/1000/niri.sock"
+        );
+        assert!(resolve_socket_path(None, None).is_err());
+        assert!(resolve_socket_path(None, Some(String::new())).is_err());
+    }
 
     #[test]
     fn test_validate_socket_path_valid_absolute_paths() {
@@ -322,6 +703,105 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_sanitize_tile_size() {
+        assert_eq!(sanitize_tile_size((800.0, 600.0), 100), (800.0, 600.0));
+        // Zero and negative sizes are raised to the minimum
+        assert_eq!(
+            sanitize_tile_size((0.0, -5.0), 100),
+            (MIN_TILE_SIZE, MIN_TILE_SIZE)
+        );
+        // Non-finite sizes are left for the layout to skip
+        let (w, h) = sanitize_tile_size((f64::NAN, f64::INFINITY), 100);
+        assert!(w.is_nan());
+        assert!(h.is_infinite());
+    }
+
+    #[test]
+    fn test_coalesce_focus_changes() {
+        let updates = vec![
+            StateUpdate::FocusChanged(Some(1)),
+            StateUpdate::FocusChanged(Some(2)),
+            StateUpdate::FocusChanged(Some(3)),
+            StateUpdate::WindowClosed(3),
+            StateUpdate::FocusChanged(None),
+            StateUpdate::FocusChanged(Some(4)),
+        ];
+
+        let coalesced = coalesce_focus_changes(updates);
+        assert_eq!(coalesced.len(), 3);
+        assert!(matches!(coalesced[0], StateUpdate::FocusChanged(Some(3))));
+        assert!(matches!(coalesced[1], StateUpdate::WindowClosed(3)));
+        assert!(matches!(coalesced[2], StateUpdate::FocusChanged(Some(4))));
+    }
+
+    #[test]
+    fn test_state_update_json() {
+        let json = |update: StateUpdate| serde_json::to_value(update).unwrap();
+        assert_eq!(
+            json(StateUpdate::WindowClosed(12)),
+            serde_json::json!({ "update": "window-closed", "data": 12 })
+        );
+        assert_eq!(
+            json(StateUpdate::WorkspaceActivated {
+                id: 3,
+                focused: true
+            }),
+            serde_json::json!({
+                "update": "workspace-activated",
+                "data": { "id": 3, "focused": true }
+            })
+        );
+        assert_eq!(
+            json(StateUpdate::FocusChanged(None)),
+            serde_json::json!({ "update": "focus-changed", "data": null })
+        );
+
+        let mut state = MinimapState::new();
+        state.set_active_workspace(1);
+        let full = json(StateUpdate::FullState(state));
+        assert_eq!(full["update"], "full-state");
+        assert_eq!(full["data"]["active_workspace_id"], 1);
+        // Bookkeeping fields stay private
+        assert!(full["data"].get("focus_sequence").is_none());
+    }
+
+    #[test]
+    fn test_drop_changes_to_closed_windows() {
+        let window = |id| {
+            niri_window_to_model(&serde_json::from_value(window_json(id, Some((1, 1)))).unwrap())
+        };
+        let updates = vec![
+            StateUpdate::WindowChanged(window(5)),
+            StateUpdate::FocusChanged(Some(5)),
+            StateUpdate::WindowChanged(window(5)),
+            StateUpdate::WindowChanged(window(6)),
+            StateUpdate::WindowClosed(5),
+            StateUpdate::FocusChanged(Some(6)),
+            StateUpdate::WindowChanged(window(7)),
+        ];
+
+        // Window 5 opened, took focus and closed within the batch; only its
+        // close is left
+        let kept = drop_changes_to_closed_windows(updates);
+        assert_eq!(kept.len(), 4, "got {:?}", kept);
+        assert!(matches!(&kept[0], StateUpdate::WindowChanged(w) if w.id == 6));
+        assert!(matches!(kept[1], StateUpdate::WindowClosed(5)));
+        assert!(matches!(kept[2], StateUpdate::FocusChanged(Some(6))));
+        assert!(matches!(&kept[3], StateUpdate::WindowChanged(w) if w.id == 7));
+
+        // A popup focused and closed straight away leaves the focus alone
+        let updates = vec![
+            StateUpdate::FocusChanged(Some(4)),
+            StateUpdate::FocusChanged(Some(5)),
+            StateUpdate::WindowClosed(5),
+        ];
+        let kept = coalesce_focus_changes(drop_changes_to_closed_windows(updates));
+        assert_eq!(kept.len(), 2, "got {:?}", kept);
+        assert!(matches!(kept[0], StateUpdate::FocusChanged(Some(4))));
+        assert!(matches!(kept[1], StateUpdate::WindowClosed(5)));
+    }
+
     #[test]
     fn test_validate_and_convert_indices_large_values() {
         // Large values should convert correctly
@@ -331,4 +811,344 @@ mod tests {
             (999998, 123455)
         );
     }
+
+    /// Parse an event the way the event stream does, so these tests also
+    /// catch wire-format changes in niri-ipc.
+    fn event(json: serde_json::Value) -> Event {
+        serde_json::from_value(json).expect("event should deserialize")
+    }
+
+    fn window_json(id: u64, pos_in_scrolling_layout: Option<(usize, usize)>) -> serde_json::Value {
+        serde_json::json!({
+            "id": id,
+            "title": "Terminal",
+            "app_id": "foot",
+            "pid": 1234,
+            "workspace_id": 3,
+            "is_focused": true,
+            "is_floating": pos_in_scrolling_layout.is_none(),
+            "is_urgent": false,
+            "layout": {
+                "pos_in_scrolling_layout": pos_in_scrolling_layout,
+                "tile_size": [800.0, 600.0],
+                "window_size": [796, 596],
+                "tile_pos_in_workspace_view": [16.0, 8.0],
+                "window_offset_in_tile": [2.0, 2.0]
+            }
+        })
+    }
+
+    #[test]
+    fn test_event_to_update_window_opened_or_changed() {
+        let update = event_to_update(event(serde_json::json!({
+            "WindowOpenedOrChanged": { "window": window_json(42, Some((2, 1))) }
+        })));
+
+        let Some(StateUpdate::WindowChanged(window)) = update else {
+            panic!("expected WindowChanged, got {:?}", update);
+        };
+        assert_eq!(window.id, 42);
+        assert_eq!(window.workspace_id, Some(3));
+        assert_eq!(window.app_id.as_deref(), Some("foot"));
+        assert_eq!(window.title.as_deref(), Some("Terminal"));
+        assert_eq!(window.pos, Some((16.0, 8.0)));
+        assert_eq!(window.size, (800.0, 600.0));
+        // Niri's indices are 1-based
+        assert_eq!((window.column_index, window.window_index), (1, 0));
+        assert!(window.is_focused);
+        assert!(!window.is_floating);
+        assert!(!window.is_urgent);
+    }
+
+    #[test]
+    fn test_event_to_update_floating_window() {
+        let update = event_to_update(event(serde_json::json!({
+            "WindowOpenedOrChanged": { "window": window_json(7, None) }
+        })));
+
+        let Some(StateUpdate::WindowChanged(window)) = update else {
+            panic!("expected WindowChanged, got {:?}", update);
+        };
+        assert!(window.is_floating);
+        assert_eq!((window.column_index, window.window_index), (0, 0));
+    }
+
+    #[test]
+    fn test_event_to_update_overview() {
+        let update = event_to_update(event(serde_json::json!({
+            "OverviewOpenedOrClosed": { "is_open": true }
+        })));
+        assert!(matches!(update, Some(StateUpdate::OverviewToggled(true))));
+    }
+
+    #[test]
+    fn test_event_to_update_window_closed() {
+        let update = event_to_update(event(serde_json::json!({
+            "WindowClosed": { "id": 42 }
+        })));
+        assert!(matches!(update, Some(StateUpdate::WindowClosed(42))));
+    }
+
+    #[test]
+    fn test_event_to_update_window_focus_changed() {
+        let update = event_to_update(event(serde_json::json!({
+            "WindowFocusChanged": { "id": 42 }
+        })));
+        assert!(matches!(update, Some(StateUpdate::FocusChanged(Some(42)))));
+
+        let update = event_to_update(event(serde_json::json!({
+            "WindowFocusChanged": { "id": null }
+        })));
+        assert!(matches!(update, Some(StateUpdate::FocusChanged(None))));
+    }
+
+    #[test]
+    fn test_event_to_update_workspace_activated() {
+        let update = event_to_update(event(serde_json::json!({
+            "WorkspaceActivated": { "id": 5, "focused": true }
+        })));
+        assert!(matches!(
+            update,
+            Some(StateUpdate::WorkspaceActivated {
+                id: 5,
+                focused: true
+            })
+        ));
+    }
+
+    #[test]
+    fn test_event_to_update_window_layouts_changed() {
+        let update = event_to_update(event(serde_json::json!({
+            "WindowLayoutsChanged": {
+                "changes": [[42, window_json(42, Some((1, 1)))["layout"].clone()]]
+            }
+        })));
+
+        let Some(StateUpdate::LayoutsChanged(changes)) = update else {
+            panic!("expected LayoutsChanged, got {:?}", update);
+        };
+        assert_eq!(changes.len(), 1);
+        let (window_id, layout) = &changes[0];
+        assert_eq!(*window_id, 42);
+        assert_eq!(layout.pos_in_scrolling_layout, Some((1, 1)));
+        assert_eq!(layout.tile_size, (800.0, 600.0));
+        assert_eq!(layout.tile_pos_in_workspace_view, Some((16.0, 8.0)));
+    }
+
+    #[test]
+    fn test_event_to_update_workspace_events() {
+        let update = event_to_update(event(serde_json::json!({
+            "WorkspacesChanged": { "workspaces": [{
+                "id": 5,
+                "idx": 1,
+                "name": null,
+                "output": "DP-1",
+                "is_urgent": false,
+                "is_active": true,
+                "is_focused": true,
+                "active_window_id": 42
+            }] }
+        })));
+        let Some(StateUpdate::WorkspacesChanged(workspaces)) = update else {
+            panic!("expected WorkspacesChanged, got {:?}", update);
+        };
+        assert_eq!(workspaces.len(), 1);
+        assert_eq!(workspaces[0].id, 5);
+        assert_eq!(workspaces[0].output.as_deref(), Some("DP-1"));
+
+        let update = event_to_update(event(serde_json::json!({
+            "WorkspaceActiveWindowChanged": { "workspace_id": 5, "active_window_id": 42 }
+        })));
+        assert!(matches!(
+            update,
+            Some(StateUpdate::WorkspaceActiveWindowChanged {
+                workspace_id: 5,
+                active_window_id: Some(42)
+            })
+        ));
+
+        let update = event_to_update(event(serde_json::json!({
+            "WindowUrgencyChanged": { "id": 42, "urgent": true }
+        })));
+        assert!(matches!(
+            update,
+            Some(StateUpdate::WindowUrgencyChanged {
+                id: 42,
+                urgent: true
+            })
+        ));
+    }
+
+    /// An active workspace; workspace 3 is the focused one
+    fn workspace(id: u64, output: &str) -> niri_ipc::Workspace {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "idx": 1,
+            "name": null,
+            "output": output,
+            "is_urgent": false,
+            "is_active": true,
+            "is_focused": id == 3,
+            "active_window_id": null
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_state_from_snapshot_sets_window_outputs() {
+        let mut on_hdmi: serde_json::Value = window_json(2, Some((1, 1)));
+        on_hdmi["workspace_id"] = serde_json::json!(4);
+        on_hdmi["is_focused"] = serde_json::json!(false);
+
+        let state = state_from_snapshot(
+            vec![workspace(3, "DP-1"), workspace(4, "HDMI-A-1")],
+            vec![
+                serde_json::from_value(window_json(1, Some((1, 1)))).unwrap(),
+                serde_json::from_value(on_hdmi).unwrap(),
+            ],
+        );
+
+        assert_eq!(
+            state.find_window(1).unwrap().output.as_deref(),
+            Some("DP-1")
+        );
+        assert_eq!(
+            state.find_window(2).unwrap().output.as_deref(),
+            Some("HDMI-A-1")
+        );
+        assert_eq!(state.active_workspace_id, Some(3));
+        assert_eq!(state.focused_window_id, Some(1));
+    }
+
+    fn layout(pos_in_scrolling_layout: Option<(usize, usize)>) -> niri_ipc::WindowLayout {
+        serde_json::from_value(window_json(1, pos_in_scrolling_layout)["layout"].clone()).unwrap()
+    }
+
+    #[test]
+    fn test_scrolling_position_edge_cases() {
+        assert_eq!(scrolling_position(&layout(None), 1), None);
+        assert_eq!(scrolling_position(&layout(Some((1, 1))), 1), Some((0, 0)));
+        // Invalid 0 indices saturate instead of underflowing
+        assert_eq!(scrolling_position(&layout(Some((0, 0))), 1), Some((0, 0)));
+        assert_eq!(
+            scrolling_position(&layout(Some((usize::MAX, 1000))), 1),
+            Some((usize::MAX - 1, 999))
+        );
+    }
+
+    #[test]
+    fn test_apply_niri_layout() {
+        let mut window =
+            niri_window_to_model(&serde_json::from_value(window_json(1, Some((3, 2)))).unwrap());
+        assert_eq!((window.column_index, window.window_index), (2, 1));
+        assert!(!window.is_floating);
+
+        // Floating keeps the last tiled indices
+        let mut floating = layout(None);
+        floating.tile_size = (0.0, 300.0);
+        floating.tile_pos_in_workspace_view = Some((f64::NAN, 0.0));
+        apply_niri_layout(&mut window, &floating);
+        assert!(window.is_floating);
+        assert_eq!((window.column_index, window.window_index), (2, 1));
+        assert_eq!(window.size, (MIN_TILE_SIZE, 300.0));
+        assert_eq!(window.pos, None);
+    }
+
+    #[test]
+    fn test_event_to_update_ignores_unhandled_events() {
+        let update = event_to_update(event(serde_json::json!({
+            "KeyboardLayoutSwitched": { "idx": 1 }
+        })));
+        assert!(update.is_none());
+    }
+
+    #[test]
+    fn test_event_loop_against_mock_niri() {
+        let mock = MockNiri::start(
+            vec![workspace(3, "DP-1")],
+            vec![serde_json::from_value(window_json(1, Some((1, 1)))).unwrap()],
+            vec![
+                event(serde_json::json!({
+                    "WindowOpenedOrChanged": { "window": window_json(2, Some((2, 1))) }
+                })),
+                event(serde_json::json!({ "WindowFocusChanged": { "id": 2 } })),
+                event(serde_json::json!({ "WindowClosed": { "id": 1 } })),
+            ],
+        );
+
+        // The loop reconnects after the mock closes the stream, so stop it
+        // once the scripted events are in
+        let mut updates = Vec::new();
+        let shutdown = AtomicBool::new(false);
+        run_event_loop_at(mock.path(), &shutdown, |update| {
+            updates.push(update);
+            if updates.len() == 4 {
+                shutdown.store(true, Ordering::Relaxed);
+            }
+        })
+        .unwrap();
+
+        assert_eq!(updates.len(), 4, "got {:?}", updates);
+        let StateUpdate::FullState(state) = &updates[0] else {
+            panic!("expected FullState first, got {:?}", updates[0]);
+        };
+        assert_eq!(state.active_workspace_id, Some(3));
+        assert_eq!(state.focused_window_id, Some(1));
+        assert!(matches!(&updates[1], StateUpdate::WindowChanged(w) if w.id == 2));
+        assert!(matches!(updates[2], StateUpdate::FocusChanged(Some(2))));
+        assert!(matches!(updates[3], StateUpdate::WindowClosed(1)));
+    }
+
+    #[test]
+    fn test_fetch_initial_state_from_mock_niri() {
+        // What each poll-loop resync does
+        let mock = MockNiri::start(
+            vec![workspace(3, "DP-1"), workspace(4, "HDMI-A-1")],
+            vec![serde_json::from_value(window_json(1, Some((1, 1)))).unwrap()],
+            Vec::new(),
+        );
+
+        let state = fetch_initial_state(mock.path()).unwrap();
+        assert_eq!(state.workspaces.len(), 2);
+        assert_eq!(
+            state.find_window(1).unwrap().output.as_deref(),
+            Some("DP-1")
+        );
+    }
+
+    #[test]
+    fn test_event_loop_fails_without_niri() {
+        let mock = MockNiri::start(Vec::new(), Vec::new(), Vec::new());
+        let path = mock.path().to_string();
+        drop(mock);
+        assert!(run_event_loop_at(&path, &AtomicBool::new(false), |_| {}).is_err());
+    }
+
+    #[test]
+    fn test_event_loop_stops_on_shutdown() {
+        let mock = MockNiri::start(
+            vec![workspace(3, "DP-1")],
+            Vec::new(),
+            vec![event(serde_json::json!({ "WindowClosed": { "id": 1 } }))],
+        );
+
+        // Only the initial state gets through once shutdown is requested
+        let mut updates = Vec::new();
+        let shutdown = AtomicBool::new(true);
+        run_event_loop_at(mock.path(), &shutdown, |update| updates.push(update)).unwrap();
+        assert_eq!(updates.len(), 1, "got {:?}", updates);
+        assert!(matches!(updates[0], StateUpdate::FullState(_)));
+    }
+
+    #[test]
+    fn test_sleep_unless_shutdown() {
+        let shutdown = AtomicBool::new(false);
+        assert!(sleep_unless_shutdown(Duration::from_millis(10), &shutdown));
+
+        // Returns right away rather than after the full duration
+        shutdown.store(true, Ordering::Relaxed);
+        let start = Instant::now();
+        assert!(!sleep_unless_shutdown(Duration::from_secs(60), &shutdown));
+        assert!(start.elapsed() < SHUTDOWN_CHECK_INTERVAL);
+    }
 }
@@ -0,0 +1,114 @@
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use niri_ipc::{Event, Reply, Request, Response};
+
+/// Distinguishes the sockets of mocks running in parallel tests
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Mock Niri server listening on a socket in the temp directory.
+///
+/// Answers `Workspaces` and `Windows` requests with canned lists, and
+/// streams the scripted events to the first `EventStream` client before
+/// closing the stream (like Niri restarting). Later `EventStream` clients
+/// are subscribed but get no events. Any other request gets an error reply.
+/// The server runs until the mock is dropped.
+pub struct MockNiri {
+    path: PathBuf,
+    stopped: Arc<AtomicBool>,
+}
+
+impl MockNiri {
+    pub fn start(
+        workspaces: Vec<niri_ipc::Workspace>,
+        windows: Vec<niri_ipc::Window>,
+        events: Vec<Event>,
+    ) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "nirimap-mock-{}-{}.sock",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).expect("mock socket should bind");
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let server_stopped = Arc::clone(&stopped);
+        thread::spawn(move || {
+            let streamed = Arc::new(AtomicBool::new(false));
+            for stream in listener.incoming() {
+                if server_stopped.load(Ordering::Relaxed) {
+                    break;
+                }
+                let Ok(stream) = stream else {
+                    break;
+                };
+                let (workspaces, windows, events) =
+                    (workspaces.clone(), windows.clone(), events.clone());
+                let streamed = Arc::clone(&streamed);
+                // A quiet event stream stays open, so each connection gets
+                // its own thread
+                thread::spawn(move || serve(stream, &workspaces, &windows, &events, &streamed));
+            }
+        });
+
+        Self { path, stopped }
+    }
+
+    /// Socket path to connect to
+    pub fn path(&self) -> &str {
+        self.path.to_str().expect("temp dir should be UTF-8")
+    }
+}
+
+impl Drop for MockNiri {
+    fn drop(&mut self) {
+        // Wake the accept loop so it sees the flag
+        self.stopped.store(true, Ordering::Relaxed);
+        let _ = UnixStream::connect(&self.path);
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Answer requests on one connection until the client hangs up, or until
+/// the scripted events have been streamed
+fn serve(
+    stream: UnixStream,
+    workspaces: &[niri_ipc::Workspace],
+    windows: &[niri_ipc::Window],
+    events: &[Event],
+    streamed: &AtomicBool,
+) {
+    let mut writer = stream.try_clone().expect("mock stream should clone");
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        let reply: Reply = match serde_json::from_str::<Request>(&line) {
+            Ok(Request::Workspaces) => Ok(Response::Workspaces(workspaces.to_vec())),
+            Ok(Request::Windows) => Ok(Response::Windows(windows.to_vec())),
+            Ok(Request::EventStream) => {
+                write_line(&mut writer, &Reply::Ok(Response::Handled));
+                if streamed.swap(true, Ordering::Relaxed) {
+                    continue;
+                }
+                for event in events {
+                    write_line(&mut writer, event);
+                }
+                return;
+            }
+            Ok(other) => Err(format!("mock doesn't handle {:?}", other)),
+            Err(err) => Err(format!("invalid request: {}", err)),
+        };
+        write_line(&mut writer, &reply);
+    }
+}
+
+fn write_line(writer: &mut UnixStream, value: &impl serde::Serialize) {
+    let json = serde_json::to_string(value).expect("mock reply should serialize");
+    let _ = writeln!(writer, "{}", json);
+}
@@ -1,4 +1,11 @@
 mod client;
 mod events;
+#[cfg(test)]
+mod mock;
 
-pub use events::{run_event_loop, validate_and_convert_indices, StateUpdate};
+pub use client::NiriClient;
+pub use events::{
+    apply_niri_layout, coalesce_focus_changes, drop_changes_to_closed_windows, resync_state,
+    run_event_loop, run_poll_loop, sanitize_tile_size, set_socket_path, StateUpdate,
+    SHUTDOWN_CHECK_INTERVAL, STATE_LOG_TARGET,
+};
@@ -0,0 +1,10 @@
+//! State tracking, Niri IPC and rendering behind the `nirimap` binary
+
+pub mod config;
+pub mod control;
+pub mod export;
+pub mod ipc;
+pub mod metrics;
+pub mod state;
+#[cfg(feature = "ui")]
+pub mod ui;
@@ -1,35 +1,93 @@
-mod config;
-mod ipc;
-mod state;
-mod ui;
-
-use std::cell::RefCell;
-use std::rc::Rc;
-use std::sync::mpsc;
-use std::thread;
-use std::time::{Duration, Instant};
+mod cli;
 
 use anyhow::Result;
-use gtk4::glib;
-use gtk4::prelude::*;
-use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use nirimap::{config, control, export, ipc};
+#[cfg(feature = "ui")]
+use nirimap::{metrics, ui};
 
 use config::Config;
-use ipc::StateUpdate;
-use ui::{create_layer_window, MinimapWidget};
-
-const APP_ID: &str = "com.github.nirimap";
+use export::Exporter;
+
+#[cfg(feature = "ui")]
+use {
+    config::{ShowTrigger, WorkspaceMode},
+    control::{ControlCommand, ControlRequest, WorkspaceModeChange},
+    gtk4::glib,
+    gtk4::prelude::*,
+    ipc::StateUpdate,
+    notify::{Event, RecommendedWatcher, RecursiveMode, Watcher},
+    std::cell::RefCell,
+    std::rc::Rc,
+    std::sync::atomic::{AtomicBool, Ordering},
+    std::sync::{mpsc, Arc},
+    std::thread,
+    std::time::{Duration, Instant},
+    ui::{create_layer_window, MinimapWidget},
+};
+
+/// Prefix of the GTK application id; the app name completes it
+#[cfg(feature = "ui")]
+const APP_ID_PREFIX: &str = "com.github";
 
 /// Debounce duration for config reloads in milliseconds
 /// Prevents excessive reloads when config file is modified multiple times rapidly
+#[cfg(feature = "ui")]
 const CONFIG_RELOAD_DEBOUNCE_MS: u64 = 500;
 
+/// Interval of the main-loop tick that drains background channels (~60 fps)
+#[cfg(feature = "ui")]
+const FRAME_INTERVAL_MS: u64 = 16;
+
+/// Maximum number of state updates applied per tick
+#[cfg(feature = "ui")]
+const MAX_UPDATES_PER_FRAME: usize = 64;
+
+/// Lower bound for `behavior.poll_interval_ms`, so a tiny value can't hammer
+/// the Niri socket
+#[cfg(feature = "ui")]
+const MIN_POLL_INTERVAL_MS: u64 = 1000;
+
+/// How long quitting waits for the background threads before leaving the
+/// stragglers behind, so a stuck thread can't keep the process alive
+#[cfg(feature = "ui")]
+const SHUTDOWN_JOIN_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Set by the SIGUSR1 handler, picked up by the frame tick
+#[cfg(feature = "ui")]
+static RELOAD_REQUESTED: AtomicBool = AtomicBool::new(false);
+
 /// Messages for config reload
+#[cfg(feature = "ui")]
 enum ConfigMessage {
+    /// Config file changed on disk (debounced)
     Reload,
+    /// Explicit reload request via SIGUSR1 (bypasses the debounce)
+    ForceReload,
 }
 
 fn main() -> Result<()> {
+    let cli = cli::parse(std::env::args().skip(1))?;
+
+    match &cli.command {
+        cli::Command::Run => {}
+        // `nirimap msg ...` talks to a running instance and exits
+        cli::Command::Msg(command) => {
+            let command = control::resolve_snapshot_path(command, &std::env::current_dir()?);
+            let reply = control::send_command(&command)?;
+            println!("{}", reply);
+            if reply.starts_with("error") {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        cli::Command::WriteDefaultConfig { force } => {
+            let config_path = Config::config_path()?;
+            Config::write_default_config(&config_path, *force)?;
+            println!("Wrote default config to {}", config_path.display());
+            return Ok(());
+        }
+    }
+
     // Initialize logging
     tracing_subscriber::fmt()
         .with_env_filter(
@@ -41,19 +99,44 @@ fn main() -> Result<()> {
     tracing::info!("Starting nirimap");
 
     // Load configuration
+    Config::set_source(config::ConfigSource::resolve(
+        cli.config.as_deref(),
+        std::env::var_os(config::CONFIG_ENV_VAR),
+        || Ok(std::io::read_to_string(std::io::stdin())?),
+    )?);
     let config = Config::load()?;
-    tracing::info!("Loaded configuration from {:?}", Config::config_path());
+    tracing::info!("Loaded configuration from {}", Config::source());
 
+    if let Some(socket) = cli.socket {
+        ipc::set_socket_path(socket);
+    }
+
+    run(config)
+}
+
+/// Run the minimap until the GTK application quits
+#[cfg(feature = "ui")]
+fn run(config: Config) -> Result<()> {
     // Create GTK application
-    let app = gtk4::Application::builder().application_id(APP_ID).build();
+    let app_id = format!("{}.{}", APP_ID_PREFIX, config::app_name());
+    let app = gtk4::Application::builder()
+        .application_id(app_id.as_str())
+        .build();
 
     // Wrap config in Rc<RefCell> for hot reload support
     let config = Rc::new(RefCell::new(config));
     let config_for_activate = config.clone();
 
+    // Activation errors (e.g. no layer-shell support) end the process with
+    // a non-zero exit status instead of leaving it running without a window
+    let activate_error: Rc<RefCell<Option<anyhow::Error>>> = Rc::new(RefCell::new(None));
+    let activate_error_slot = activate_error.clone();
+
     app.connect_activate(move |app| {
         if let Err(e) = activate(app, config_for_activate.clone()) {
-            tracing::error!("Failed to activate application: {}", e);
+            tracing::error!("Failed to activate application: {:#}", e);
+            *activate_error_slot.borrow_mut() = Some(e);
+            app.quit();
         }
     });
 
@@ -61,12 +144,43 @@ fn main() -> Result<()> {
     let empty: Vec<String> = vec![];
     app.run_with_args(&empty);
 
-    Ok(())
+    match activate_error.take() {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// Headless build (without the `ui` feature): follow Niri's event stream and
+/// only log the state updates, for debugging IPC without GTK. With
+/// `export_ws_port` they are streamed to an external renderer as well.
+#[cfg(not(feature = "ui"))]
+fn run(config: Config) -> Result<()> {
+    tracing::warn!(
+        "Built without the `ui` feature, nothing is drawn; \
+         use RUST_LOG={}=trace to see state updates",
+        ipc::STATE_LOG_TARGET
+    );
+    let shutdown = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let exporter = Exporter::start(config.behavior.export_ws_port, shutdown.clone())
+        .map(|(exporter, _)| exporter);
+    ipc::run_event_loop(&shutdown, |update| {
+        update.trace();
+        if let Some(exporter) = &exporter {
+            exporter.broadcast(&update);
+        }
+    })
+}
+
+/// SIGUSR1 handler: request a forced config reload
+#[cfg(feature = "ui")]
+extern "C" fn request_reload(_signal: libc::c_int) {
+    RELOAD_REQUESTED.store(true, Ordering::Relaxed);
 }
 
+#[cfg(feature = "ui")]
 fn activate(app: &gtk4::Application, config: Rc<RefCell<Config>>) -> Result<()> {
     // Create the layer-shell window
-    let window = create_layer_window(app, &config.borrow());
+    let window = create_layer_window(app, &config.borrow())?;
 
     // Create the minimap widget
     let minimap = MinimapWidget::new(config.clone());
@@ -80,57 +194,180 @@ fn activate(app: &gtk4::Application, config: Rc<RefCell<Config>>) -> Result<()>
     // Set up channel for state updates from IPC thread
     let (tx, rx) = mpsc::channel::<StateUpdate>();
 
+    // Background threads watch this flag and return once the app shuts down
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let mut threads = Vec::new();
+
+    // Optional websocket feed of the state updates for external renderers
+    let exporter = Exporter::start(config.borrow().behavior.export_ws_port, shutdown.clone()).map(
+        |(exporter, handle)| {
+            threads.push(handle);
+            exporter
+        },
+    );
+
+    // Optional periodic full-state poll, feeding the same channel
+    let poll_interval_ms = config.borrow().behavior.poll_interval_ms;
+    if let Some(poll_interval) = poll_interval(poll_interval_ms) {
+        let poll_tx = tx.clone();
+        let shutdown = shutdown.clone();
+        threads.push(thread::spawn(move || {
+            ipc::run_poll_loop(poll_interval, &shutdown, move |update| {
+                let _ = poll_tx.send(update);
+            });
+        }));
+    }
+
+    // Config reloads may re-sync the state through the same channel
+    let resync_tx = tx.clone();
+
     // Start IPC event loop in a background thread
-    thread::spawn(move || {
-        if let Err(e) = ipc::run_event_loop(move |update| {
+    let event_shutdown = shutdown.clone();
+    threads.push(thread::spawn(move || {
+        if let Err(e) = ipc::run_event_loop(&event_shutdown, move |update| {
             if tx.send(update).is_err() {
                 tracing::warn!("Failed to send state update, receiver dropped");
             }
         }) {
             tracing::error!("IPC event loop error: {}", e);
         }
-    });
+    }));
 
     // Set up channel for config reload messages
     let (config_tx, config_rx) = mpsc::channel::<ConfigMessage>();
 
-    // Start file watcher in a background thread
-    let config_path = Config::config_path();
-    thread::spawn(move || {
-        if let Err(e) = watch_config_file(config_path, config_tx) {
-            tracing::error!("Config watcher error: {}", e);
+    // SIGUSR1 forces a reload. The handler only sets a flag; the frame tick
+    // turns it into a message on the same channel as the file watcher, so
+    // both paths are serialized.
+    let signal_tx = config_tx.clone();
+    // SAFETY: the handler only stores to an atomic, which is signal-safe
+    unsafe {
+        libc::signal(
+            libc::SIGUSR1,
+            request_reload as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+
+    // Start file watcher in a background thread (nothing to watch when the
+    // config came from stdin; SIGUSR1 still re-parses it)
+    if let Some(config_path) = Config::source().watch_path()? {
+        let shutdown = shutdown.clone();
+        threads.push(thread::spawn(move || {
+            if let Err(e) = watch_config_file(config_path, config_tx, &shutdown) {
+                tracing::error!("Config watcher error: {}", e);
+            }
+        }));
+    }
+
+    // Set up channel for control socket commands
+    let (control_tx, control_rx) = mpsc::channel::<ControlRequest>();
+
+    // Start the control socket listener in a background thread
+    let control_shutdown = shutdown.clone();
+    let control_thread = thread::spawn(move || {
+        if let Err(e) = control::run_control_socket(control_tx, &control_shutdown) {
+            tracing::error!("Control socket error: {}", e);
+        }
+    });
+
+    // On quit, stop the background threads and remove the control socket
+    let control_thread = RefCell::new(Some(control_thread));
+    let threads = RefCell::new(threads);
+    app.connect_shutdown(move |_| {
+        shutdown.store(true, Ordering::Relaxed);
+        let deadline = Instant::now() + SHUTDOWN_JOIN_TIMEOUT;
+        let mut stopped = true;
+        if let Some(handle) = control_thread.take() {
+            // Not finished when it's waiting for a connection
+            if !handle.is_finished() {
+                control::wake_control_socket();
+            }
+            stopped &= join_until(handle, deadline);
+        }
+        for handle in threads.take() {
+            stopped &= join_until(handle, deadline);
+        }
+        if stopped {
+            tracing::info!("Background threads stopped");
+        } else {
+            tracing::warn!("Background threads still busy, exiting without them");
         }
     });
 
     // Set up glib idle handler to process state updates and config reloads
     let minimap_clone = minimap.clone();
+    let loop_config = config.clone();
     let last_config_reload = Rc::new(RefCell::new(Instant::now()));
     let config_reload_debounce = Duration::from_millis(CONFIG_RELOAD_DEBOUNCE_MS);
 
-    glib::timeout_add_local(Duration::from_millis(50), move || {
-        // Process a batch of state updates
-        for _ in 0..10 {
-            if let Ok(update) = rx.try_recv() {
-                apply_state_update(&minimap_clone, update);
-            } else {
-                break;
-            }
+    glib::timeout_add_local(Duration::from_millis(FRAME_INTERVAL_MS), move || {
+        // Process a batch of state updates as a single state mutation, so a
+        // burst of focus changes (e.g. a held keybind) costs one redraw and a
+        // window opened and closed within the batch never shows the minimap
+        let updates: Vec<StateUpdate> = rx.try_iter().take(MAX_UPDATES_PER_FRAME).collect();
+        if !updates.is_empty() {
+            let batch_size = updates.len();
+            minimap_clone.batch_updates(|| {
+                let updates = ipc::drop_changes_to_closed_windows(updates);
+                for update in ipc::coalesce_focus_changes(updates) {
+                    update.trace();
+                    if let Some(exporter) = &exporter {
+                        exporter.broadcast(&update);
+                    }
+                    apply_state_update(&minimap_clone, update);
+                }
+            });
+            minimap_clone.with_state(|state| {
+                tracing::trace!(
+                    target: ipc::STATE_LOG_TARGET,
+                    batch = batch_size,
+                    windows = state.window_count(),
+                    active_workspace_id = ?state.active_workspace_id,
+                    focused_window_id = ?state.focused_window_id,
+                    "Applied state updates",
+                );
+            });
+        }
+
+        if RELOAD_REQUESTED.swap(false, Ordering::Relaxed) {
+            tracing::debug!("Received SIGUSR1, forcing config reload");
+            let _ = signal_tx.send(ConfigMessage::ForceReload);
         }
 
         // Process config reload messages with debouncing
-        while let Ok(ConfigMessage::Reload) = config_rx.try_recv() {
+        while let Ok(message) = config_rx.try_recv() {
             let now = Instant::now();
             let mut last_reload = last_config_reload.borrow_mut();
 
-            // Only reload if enough time has passed since the last reload
-            if now.duration_since(*last_reload) >= config_reload_debounce {
+            // Only reload if enough time has passed since the last reload,
+            // unless explicitly forced. A forced reload still records its
+            // time, so watcher events from the same write get debounced.
+            let forced = matches!(message, ConfigMessage::ForceReload);
+            if forced || now.duration_since(*last_reload) >= config_reload_debounce {
                 minimap_clone.reload_config();
                 *last_reload = now;
+
+                // Re-fetch off the UI thread; the result arrives as a
+                // FullState on a later tick
+                if loop_config.borrow().behavior.reload_resyncs_state {
+                    let resync_tx = resync_tx.clone();
+                    thread::spawn(move || {
+                        ipc::resync_state(move |update| {
+                            let _ = resync_tx.send(update);
+                        });
+                    });
+                }
             } else {
                 tracing::debug!("Config reload debounced (too soon after last reload)");
             }
         }
 
+        // Process control socket commands
+        while let Ok(request) = control_rx.try_recv() {
+            let reply = handle_control_command(&minimap_clone, request.command);
+            let _ = request.reply.send(reply);
+        }
+
         glib::ControlFlow::Continue
     });
 
@@ -147,10 +384,75 @@ fn activate(app: &gtk4::Application, config: Rc<RefCell<Config>>) -> Result<()>
     Ok(())
 }
 
-/// Watch the config file for changes and send reload messages
+/// Execute a control socket command and produce the reply line
+#[cfg(feature = "ui")]
+fn handle_control_command(minimap: &MinimapWidget, command: ControlCommand) -> String {
+    let result = match command {
+        ControlCommand::Preset(name) => minimap.apply_preset(&name),
+        ControlCommand::ClearPreset => minimap.clear_preset(),
+        ControlCommand::WorkspaceMode(change) => {
+            let mode = match change {
+                WorkspaceModeChange::Set(mode) => Some(mode),
+                WorkspaceModeChange::Toggle => Some(match minimap.workspace_mode() {
+                    WorkspaceMode::All => WorkspaceMode::Current,
+                    WorkspaceMode::Current => WorkspaceMode::All,
+                }),
+                WorkspaceModeChange::Reset => None,
+            };
+            minimap.set_workspace_mode_override(mode);
+            Ok(())
+        }
+        ControlCommand::CycleAnchor { persist } => minimap.cycle_anchor(persist).map(|anchor| {
+            tracing::info!("Minimap anchor -> {:?}", anchor);
+        }),
+        ControlCommand::Snapshot(path) => {
+            minimap.snapshot(&path.unwrap_or_else(control::snapshot_path))
+        }
+        // Replies with the metrics text instead of `ok`
+        ControlCommand::Metrics => match minimap.metrics() {
+            Ok(text) => return text,
+            Err(e) => Err(e),
+        },
+    };
+
+    match result {
+        Ok(()) => "ok".to_string(),
+        Err(e) => {
+            tracing::warn!("Control command failed: {:#}", e);
+            format!("error: {:#}", e)
+        }
+    }
+}
+
+/// Interval of the periodic full-state poll for `behavior.poll_interval_ms`,
+/// raised to `MIN_POLL_INTERVAL_MS`. `None` when polling is off (0).
+#[cfg(feature = "ui")]
+fn poll_interval(poll_interval_ms: u64) -> Option<Duration> {
+    (poll_interval_ms > 0)
+        .then(|| Duration::from_millis(poll_interval_ms.max(MIN_POLL_INTERVAL_MS)))
+}
+
+/// Join `handle` if it finishes before `deadline`, otherwise detach it.
+/// Returns whether it finished.
+#[cfg(feature = "ui")]
+fn join_until(handle: thread::JoinHandle<()>, deadline: Instant) -> bool {
+    while !handle.is_finished() {
+        if Instant::now() >= deadline {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    let _ = handle.join();
+    true
+}
+
+/// Watch the config file for changes and send reload messages until
+/// `shutdown` is set
+#[cfg(feature = "ui")]
 fn watch_config_file(
     config_path: std::path::PathBuf,
     tx: mpsc::Sender<ConfigMessage>,
+    shutdown: &AtomicBool,
 ) -> Result<()> {
     let (watcher_tx, watcher_rx) = mpsc::channel::<Result<Event, notify::Error>>();
 
@@ -167,7 +469,13 @@ fn watch_config_file(
         tracing::info!("Watching config directory: {}", parent.display());
     }
 
-    for event in watcher_rx {
+    // Wake up regularly to check for shutdown
+    while !shutdown.load(Ordering::Relaxed) {
+        let event = match watcher_rx.recv_timeout(ipc::SHUTDOWN_CHECK_INTERVAL) {
+            Ok(event) => event,
+            Err(mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        };
         match event {
             Ok(event) => {
                 // Check if the event is for our config file
@@ -196,20 +504,31 @@ fn watch_config_file(
 }
 
 /// Apply a state update to the minimap
+#[cfg(feature = "ui")]
 fn apply_state_update(minimap: &MinimapWidget, update: StateUpdate) {
+    metrics::increment(&metrics::METRICS.state_updates);
     match update {
         StateUpdate::FullState(new_state) => {
+            metrics::increment(&metrics::METRICS.full_syncs);
+            // Merge in a single update so the swap causes exactly one
+            // redraw and never an intermediate empty frame. The widget's
+            // last-shown focus is untouched, so a resync doesn't look like a
+            // focus change.
             minimap.update_state(|state| {
-                *state = new_state;
+                state.merge_full_state(new_state);
             });
+            minimap.sync_sticky_workspace();
             tracing::debug!("Applied full state update");
         }
 
-        StateUpdate::WindowChanged {
-            window,
-            workspace_id,
-        } => {
+        StateUpdate::WindowChanged(window) => {
             let window_id = window.id;
+            if minimap.ignores_window(&window) {
+                // Dropped if it was tracked before its app id became ignored
+                minimap.update_state(|state| state.remove_window(window_id));
+                tracing::debug!("Window {} ignored ({:?})", window_id, window.app_id);
+                return;
+            }
             let is_focused = window.is_focused;
             let is_floating = window.is_floating;
             let mut is_new_window = false;
@@ -221,31 +540,21 @@ fn apply_state_update(minimap: &MinimapWidget, update: StateUpdate) {
                     state.set_focused_window(Some(window_id));
                 }
 
-                if let Some(ws_id) = workspace_id {
-                    is_on_active_workspace = state.active_workspace_id == Some(ws_id);
-
-                    // Remove from any other workspace (handles workspace moves)
-                    for (&id, workspace) in state.workspaces.iter_mut() {
-                        if id != ws_id {
-                            workspace.windows.remove(&window_id);
-                        }
-                    }
-
-                    // Insert into the correct workspace
-                    let workspace = state
-                        .workspaces
-                        .entry(ws_id)
-                        .or_insert_with(Default::default);
-                    is_new_window = !workspace.windows.contains_key(&window_id);
-                    workspace.windows.insert(window_id, window);
-                }
+                is_on_active_workspace = window.workspace_id.is_some()
+                    && state.active_workspace_id == window.workspace_id;
+                // Moves the window off any workspace it no longer belongs to
+                is_new_window = state.place_window(window);
             });
 
             // Only show the minimap for new windows on the active workspace.
             // Floating spawns are filtered by show_for_new_window when the
             // show_for_floating_windows opt-out is in effect.
+            if is_new_window {
+                minimap.animate_window_open(window_id);
+            }
+
             if is_on_active_workspace && is_new_window {
-                minimap.show_for_new_window(is_floating);
+                minimap.show_for_new_window(window_id, is_floating, is_focused);
                 tracing::debug!(
                     "New window {} opened (focused: {}, floating: {})",
                     window_id,
@@ -258,28 +567,28 @@ fn apply_state_update(minimap: &MinimapWidget, update: StateUpdate) {
         }
 
         StateUpdate::WindowClosed(window_id) => {
-            minimap.update_state(|state| {
-                state.remove_window(window_id);
-            });
+            // Removed right away, or after its fade-out with window_animations
+            minimap.close_window(window_id);
             tracing::debug!("Window {} closed", window_id);
         }
 
         StateUpdate::FocusChanged(window_id) => {
-            minimap.update_state(|state| {
-                state.set_focused_window(window_id);
-            });
-            // Show the minimap only if focus changed to a different window
-            minimap.show_on_focus_change(window_id);
+            // Held briefly with focus_hold_ms, so drag churn doesn't flicker
+            minimap.change_focus(window_id);
             tracing::debug!("Focus changed to {:?}", window_id);
         }
 
         StateUpdate::WorkspaceActivated { id, focused } => {
             if focused {
+                minimap.begin_workspace_transition(id);
                 minimap.update_state(|state| {
                     state.set_active_workspace(id);
                 });
-                // Show the minimap when workspace changes (will auto-hide if configured)
-                minimap.show();
+                minimap.follow_focus();
+                // Show the minimap when workspace changes (will auto-hide if
+                // configured), throttled while switches come in quickly
+                minimap.show_for_workspace_change();
+                minimap.sync_sticky_workspace();
                 tracing::debug!("Workspace {} activated", id);
             }
         }
@@ -307,34 +616,43 @@ fn apply_state_update(minimap: &MinimapWidget, update: StateUpdate) {
             );
         }
 
+        StateUpdate::WindowUrgencyChanged { id, urgent } => {
+            minimap.update_state(|state| {
+                state.set_window_urgent(id, urgent);
+            });
+            tracing::debug!("Window {} urgency -> {}", id, urgent);
+        }
+
+        StateUpdate::OverviewToggled(is_open) => {
+            minimap.set_overview_open(is_open);
+            tracing::debug!("Overview open -> {}", is_open);
+        }
+
         StateUpdate::LayoutsChanged(layouts) => {
+            let mut touches_active_workspace = false;
             minimap.update_state(|state| {
+                touches_active_workspace =
+                    state.any_on_active_workspace(layouts.iter().map(|(id, _)| *id));
                 for (window_id, layout) in layouts {
                     // Find and update the window's layout
                     for workspace in state.workspaces.values_mut() {
                         if let Some(window) = workspace.windows.get_mut(&window_id) {
-                            window.pos = layout.tile_pos_in_workspace_view;
-                            window.size = layout.tile_size;
-                            // Update floating status
-                            window.is_floating = layout.pos_in_scrolling_layout.is_none();
-                            if let Some((col, win_idx)) = layout.pos_in_scrolling_layout {
-                                let (column_index, window_index) =
-                                    ipc::validate_and_convert_indices(col, win_idx, window_id);
-                                window.column_index = column_index;
-                                window.window_index = window_index;
-                            }
+                            ipc::apply_niri_layout(window, &layout);
                         }
                     }
                 }
             });
-            // Show the minimap when layouts change (window resize, move, etc.)
-            minimap.show();
+            // Show the minimap when layouts change (window resize, move,
+            // etc.), but not for activity on other workspaces or outputs
+            if touches_active_workspace {
+                minimap.show_for(ShowTrigger::LayoutChange);
+            }
             tracing::debug!("Window layouts changed");
         }
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "ui"))]
 mod tests {
     use super::*;
 
@@ -344,6 +662,17 @@ mod tests {
         assert_eq!(CONFIG_RELOAD_DEBOUNCE_MS, 500);
     }
 
+    #[test]
+    fn test_poll_interval_is_clamped() {
+        assert_eq!(poll_interval(0), None);
+        // The poll is a safety net; it must stay far slower than the event stream
+        assert_eq!(
+            poll_interval(10),
+            Some(Duration::from_millis(MIN_POLL_INTERVAL_MS))
+        );
+        assert_eq!(poll_interval(5000), Some(Duration::from_millis(5000)));
+    }
+
     #[test]
     fn test_debounce_logic_simulation() {
         // Simulate debouncing logic similar to what happens in activate()
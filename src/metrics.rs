@@ -0,0 +1,115 @@
+//! Runtime counters, reported in Prometheus text format by the `metrics`
+//! control command (`behavior.metrics_enable`)
+
+#[cfg(feature = "ui")]
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Process-wide counters. Updated from the IPC threads and the UI thread.
+pub struct Metrics {
+    /// Niri events read from the event stream
+    pub events_received: AtomicU64,
+    /// State updates applied on the UI thread
+    pub state_updates: AtomicU64,
+    /// Full state snapshots applied (startup, polls, resyncs)
+    pub full_syncs: AtomicU64,
+    /// Frames drawn
+    pub redraws: AtomicU64,
+}
+
+pub static METRICS: Metrics = Metrics {
+    events_received: AtomicU64::new(0),
+    state_updates: AtomicU64::new(0),
+    full_syncs: AtomicU64::new(0),
+    redraws: AtomicU64::new(0),
+};
+
+/// Add one to `counter`
+pub fn increment(counter: &AtomicU64) {
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+#[cfg(feature = "ui")]
+impl Metrics {
+    /// Prometheus text exposition of the counters, plus the current window
+    /// and workspace counts
+    pub fn render(&self, windows: usize, workspaces: usize) -> String {
+        let counters = [
+            (
+                "nirimap_events_received_total",
+                "Niri events read from the event stream",
+                &self.events_received,
+            ),
+            (
+                "nirimap_state_updates_total",
+                "State updates applied",
+                &self.state_updates,
+            ),
+            (
+                "nirimap_full_syncs_total",
+                "Full state snapshots applied",
+                &self.full_syncs,
+            ),
+            ("nirimap_redraws_total", "Frames drawn", &self.redraws),
+        ];
+        let gauges = [
+            ("nirimap_windows", "Windows currently tracked", windows),
+            (
+                "nirimap_workspaces",
+                "Workspaces currently tracked",
+                workspaces,
+            ),
+        ];
+
+        let mut text = String::new();
+        for (name, help, counter) in counters {
+            let value = counter.load(Ordering::Relaxed);
+            let _ = writeln!(
+                text,
+                "# HELP {0} {1}\n# TYPE {0} counter\n{0} {2}",
+                name, help, value
+            );
+        }
+        for (name, help, value) in gauges {
+            let _ = writeln!(
+                text,
+                "# HELP {0} {1}\n# TYPE {0} gauge\n{0} {2}",
+                name, help, value
+            );
+        }
+        text
+    }
+}
+
+#[cfg(all(test, feature = "ui"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_prometheus_text() {
+        let metrics = Metrics {
+            events_received: AtomicU64::new(0),
+            state_updates: AtomicU64::new(0),
+            full_syncs: AtomicU64::new(0),
+            redraws: AtomicU64::new(0),
+        };
+        increment(&metrics.events_received);
+        increment(&metrics.events_received);
+        increment(&metrics.redraws);
+
+        let text = metrics.render(5, 2);
+        assert!(text.contains(
+            "# HELP nirimap_events_received_total Niri events read from the event stream\n\
+             # TYPE nirimap_events_received_total counter\n\
+             nirimap_events_received_total 2\n"
+        ));
+        assert!(text.contains("nirimap_state_updates_total 0\n"));
+        assert!(text.contains("nirimap_redraws_total 1\n"));
+        assert!(text.contains("# TYPE nirimap_windows gauge\nnirimap_windows 5\n"));
+        assert!(text.ends_with("nirimap_workspaces 2\n"));
+        // Every sample line is `name value`
+        for line in text.lines().filter(|l| !l.starts_with('#')) {
+            assert_eq!(line.split(' ').count(), 2, "{}", line);
+        }
+    }
+}
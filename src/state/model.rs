@@ -1,10 +1,21 @@
-use std::collections::HashMap;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 
 /// Represents a single window in the minimap
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Window {
     /// Unique window identifier from Niri
     pub id: u64,
+    /// Workspace the window is on, as reported by Niri
+    pub workspace_id: Option<u64>,
+    /// Application id (Wayland app_id), if the client set one
+    pub app_id: Option<String>,
+    /// Window title, if the client set one
+    pub title: Option<String>,
+    /// Output (connector name) of the window's workspace, if known. Kept in
+    /// sync with the workspace by `upsert_window` and
+    /// `replace_workspace_metadata`.
+    pub output: Option<String>,
     /// Position in workspace view coordinates (x, y), if known. Niri only
     /// populates `tile_pos_in_workspace_view` for windows whose tile is
     /// currently positioned in the viewport; off-viewport windows arrive as
@@ -20,15 +31,30 @@ pub struct Window {
     pub is_focused: bool,
     /// Whether this window is floating (not tiled)
     pub is_floating: bool,
+    /// Whether this window is requesting attention
+    pub is_urgent: bool,
+    /// Whether this window is present but not shown, e.g. an inactive tab.
+    /// Niri doesn't report this, so it's inferred by `infer_hidden_windows`;
+    /// windows are treated as visible when it can't be told.
+    pub is_hidden: bool,
+    /// Whether this is the active window of a multi-window column (the one
+    /// last focused there, or the shown tab). Inferred by
+    /// `infer_column_active_windows`; false when it can't be told.
+    pub is_column_active: bool,
+    /// Whether this window is fullscreen. Niri doesn't report this, so it's
+    /// inferred by `infer_fullscreen_windows`.
+    pub is_fullscreen: bool,
 }
 
 /// Represents a workspace containing windows
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct Workspace {
     /// Niri workspace id (stable across moves/reorder)
     pub id: u64,
     /// Index of the workspace on its monitor (display order)
     pub idx: u8,
+    /// Name given to the workspace in Niri's config, if any
+    pub name: Option<String>,
     /// Name of the output this workspace is on, if any
     pub output: Option<String>,
     /// Windows in this workspace, keyed by window ID
@@ -38,12 +64,20 @@ pub struct Workspace {
     /// The most-recently-focused window id on this workspace, if any. Niri
     /// tracks this per workspace and uses it for Overview-style alignment.
     pub active_window_id: Option<u64>,
+    /// Whether Niri has reported this workspace. False for entries created
+    /// on demand by an event that arrived before the workspace list.
+    pub reported: bool,
 }
 
-impl Workspace {}
+impl Workspace {
+    /// Whether `reference` names this workspace, by name or id
+    pub fn matches(&self, reference: &str) -> bool {
+        self.name.as_deref() == Some(reference) || reference == self.id.to_string()
+    }
+}
 
 /// Main state container for the minimap
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct MinimapState {
     /// All workspaces, keyed by workspace ID
     pub workspaces: HashMap<u64, Workspace>,
@@ -51,6 +85,23 @@ pub struct MinimapState {
     pub active_workspace_id: Option<u64>,
     /// Currently focused window ID
     pub focused_window_id: Option<u64>,
+    /// Output (connector name) the minimap is shown on, if known
+    pub output_name: Option<String>,
+    /// Niri's gap between tiles in workspace pixels, as last measured by
+    /// `infer_layout_gap`
+    pub layout_gap: Option<f64>,
+    /// Workspace the focused window was last found on. Only a hint so that
+    /// clearing the old focus doesn't need to scan every workspace; it is
+    /// re-checked (and falls back to a scan) when the window has moved.
+    #[serde(skip)]
+    focused_workspace_hint: Option<u64>,
+    /// Focus order of windows (window id -> sequence number, higher is more
+    /// recent), used to find each column's active window
+    #[serde(skip)]
+    focus_sequence: HashMap<u64, u64>,
+    /// Last sequence number handed out in `focus_sequence`
+    #[serde(skip)]
+    last_focus_sequence: u64,
 }
 
 impl MinimapState {
@@ -59,10 +110,63 @@ impl MinimapState {
         Self::default()
     }
 
-    /// Get the currently active workspace, if any
+    /// Get the currently active workspace, if any.
+    ///
+    /// Falls back to a workspace flagged `is_active` when
+    /// `active_workspace_id` is unset or points at a workspace that no
+    /// longer exists.
     pub fn active_workspace(&self) -> Option<&Workspace> {
         self.active_workspace_id
             .and_then(|id| self.workspaces.get(&id))
+            .or_else(|| self.flagged_active_workspace())
+    }
+
+    /// The first workspace (in display order) whose `is_active` flag is set
+    fn flagged_active_workspace(&self) -> Option<&Workspace> {
+        self.workspaces_sorted().into_iter().find(|ws| ws.is_active)
+    }
+
+    /// Re-point `active_workspace_id` at an `is_active` workspace when it is
+    /// unset, stale, or names a workspace that isn't active. Leaves it alone
+    /// when no workspace is flagged active.
+    pub fn reconcile_active_workspace(&mut self) {
+        let consistent = self
+            .active_workspace_id
+            .and_then(|id| self.workspaces.get(&id))
+            .is_some_and(|ws| ws.is_active);
+        if consistent {
+            return;
+        }
+
+        if let Some(id) = self.flagged_active_workspace().map(|ws| ws.id) {
+            if self.active_workspace_id.is_some() {
+                tracing::warn!(
+                    "Active workspace {:?} disagrees with is_active flags, using {}",
+                    self.active_workspace_id,
+                    id
+                );
+            }
+            self.active_workspace_id = Some(id);
+        }
+    }
+
+    /// Whether the active workspace has at least one tiled window.
+    pub fn active_workspace_has_tiled_windows(&self) -> bool {
+        self.active_workspace()
+            .is_some_and(|ws| ws.windows.values().any(|w| !w.is_floating))
+    }
+
+    /// Output of the focused window's workspace, or of the active workspace
+    /// when no window is focused.
+    pub fn focused_output(&self) -> Option<&str> {
+        self.focused_window_id
+            .and_then(|id| {
+                self.workspaces
+                    .values()
+                    .find(|ws| ws.windows.contains_key(&id))
+            })
+            .or_else(|| self.active_workspace())
+            .and_then(|ws| ws.output.as_deref())
     }
 
     /// Find a window by id across all workspaces.
@@ -70,6 +174,172 @@ impl MinimapState {
         self.workspaces.values().find_map(|ws| ws.windows.get(&id))
     }
 
+    /// Whether any tracked window is currently requesting attention.
+    pub fn has_urgent_windows(&self) -> bool {
+        self.workspaces
+            .values()
+            .any(|ws| ws.windows.values().any(|w| w.is_urgent))
+    }
+
+    /// Recompute `is_hidden` for every tiled window.
+    ///
+    /// Niri only reports a tile position for tiles it actually shows, and a
+    /// whole column scrolled out of view has no positions at all. So a tile
+    /// without a position in a column where another tile has one is hidden
+    /// (an inactive tab); everything else counts as visible.
+    pub fn infer_hidden_windows(&mut self) {
+        for workspace in self.workspaces.values_mut() {
+            let shown_columns: HashSet<usize> = workspace
+                .windows
+                .values()
+                .filter(|w| !w.is_floating && w.pos.is_some())
+                .map(|w| w.column_index)
+                .collect();
+            for window in workspace.windows.values_mut() {
+                window.is_hidden = !window.is_floating
+                    && window.pos.is_none()
+                    && shown_columns.contains(&window.column_index);
+            }
+        }
+    }
+
+    /// Recompute `is_fullscreen` for every window: a tiled window whose tile
+    /// fills its whole output. Niri doesn't report fullscreen, but a
+    /// fullscreen tile is exactly the output's logical size (no gaps, no
+    /// struts). `output_size` gives the logical size of an output, if known.
+    pub fn infer_fullscreen_windows(
+        &mut self,
+        output_size: impl Fn(Option<&str>) -> Option<(f64, f64)>,
+    ) {
+        for workspace in self.workspaces.values_mut() {
+            for window in workspace.windows.values_mut() {
+                let (width, height) = window.size;
+                window.is_fullscreen = !window.is_floating
+                    && output_size(window.output.as_deref()).is_some_and(|(out_w, out_h)| {
+                        (width - out_w).abs() < 1.0 && (height - out_h).abs() < 1.0
+                    });
+            }
+        }
+    }
+
+    /// Recompute `is_column_active` for every tiled window.
+    ///
+    /// Niri doesn't report a column's active window, so in each column with
+    /// more than one window it's the one focused most recently, or failing
+    /// that the only shown tab (see `infer_hidden_windows`, which must run
+    /// first). Columns where neither applies have no active window.
+    pub fn infer_column_active_windows(&mut self) {
+        let focus_sequence = &mut self.focus_sequence;
+        let mut live_ids = HashSet::new();
+
+        for workspace in self.workspaces.values_mut() {
+            let mut columns: HashMap<usize, Vec<&mut Window>> = HashMap::new();
+            for window in workspace.windows.values_mut() {
+                live_ids.insert(window.id);
+                window.is_column_active = false;
+                if !window.is_floating {
+                    columns.entry(window.column_index).or_default().push(window);
+                }
+            }
+
+            for windows in columns.values_mut() {
+                if windows.len() < 2 {
+                    continue;
+                }
+                let last_focused = windows
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, w)| focus_sequence.get(&w.id).map(|seq| (*seq, i)))
+                    .max()
+                    .map(|(_, i)| i);
+                let active = last_focused.or_else(|| {
+                    let shown: Vec<usize> = (0..windows.len())
+                        .filter(|&i| !windows[i].is_hidden)
+                        .collect();
+                    (shown.len() == 1).then(|| shown[0])
+                });
+                if let Some(i) = active {
+                    windows[i].is_column_active = true;
+                }
+            }
+        }
+
+        // Forget closed windows
+        focus_sequence.retain(|id, _| live_ids.contains(id));
+    }
+
+    /// Update `layout_gap` from the positions Niri reports for shown tiles:
+    /// the space between two stacked tiles of a column, or else between two
+    /// neighboring columns. Niri's gaps come from its config, so the last
+    /// measurement is kept while nothing on screen allows a new one.
+    pub fn infer_layout_gap(&mut self) {
+        if let Some(gap) = self.workspaces.values().find_map(measure_gap) {
+            self.layout_gap = Some(gap);
+        }
+    }
+
+    /// Tiled column (workspace id, column index) under the center of the
+    /// focused window, when that window is floating. Tiled and floating
+    /// windows both report viewport-relative positions, so only columns
+    /// currently in view can match.
+    pub fn floating_focus_column(&self) -> Option<(u64, usize)> {
+        let floating = self
+            .focused_window_id
+            .and_then(|id| self.find_window(id))
+            .filter(|w| w.is_floating)?;
+        let (x, _) = floating.pos?;
+        let center = x + floating.size.0 / 2.0;
+        let workspace = self.workspaces.get(&floating.workspace_id?)?;
+        workspace
+            .windows
+            .values()
+            .filter(|w| !w.is_floating && !w.is_hidden)
+            .find(|w| {
+                w.pos
+                    .is_some_and(|(x, _)| center >= x && center < x + w.size.0)
+            })
+            .map(|w| (workspace.id, w.column_index))
+    }
+
+    /// Whether any of `window_ids` is on the active workspace
+    pub fn any_on_active_workspace(&self, window_ids: impl IntoIterator<Item = u64>) -> bool {
+        self.active_workspace().is_some_and(|ws| {
+            window_ids
+                .into_iter()
+                .any(|id| ws.windows.contains_key(&id))
+        })
+    }
+
+    /// Number of windows across all workspaces
+    pub fn window_count(&self) -> usize {
+        self.workspaces.values().map(|ws| ws.windows.len()).sum()
+    }
+
+    /// Focus recency of every window focused since it opened: 0 for the
+    /// most recently focused, 1 for the one before, and so on.
+    pub fn focus_ranks(&self) -> HashMap<u64, usize> {
+        let mut by_recency: Vec<(u64, u64)> = self
+            .focus_sequence
+            .iter()
+            .map(|(&id, &seq)| (seq, id))
+            .collect();
+        by_recency.sort_unstable_by(|a, b| b.cmp(a));
+        by_recency
+            .into_iter()
+            .enumerate()
+            .map(|(rank, (_, id))| (id, rank))
+            .collect()
+    }
+
+    /// Set the urgency flag of a window, wherever it lives.
+    pub fn set_window_urgent(&mut self, window_id: u64, urgent: bool) {
+        for workspace in self.workspaces.values_mut() {
+            if let Some(window) = workspace.windows.get_mut(&window_id) {
+                window.is_urgent = urgent;
+            }
+        }
+    }
+
     /// Workspaces sorted for display (by output, then idx).
     ///
     /// Workspaces without an output sort last; within the same output they
@@ -87,6 +357,24 @@ impl MinimapState {
         out
     }
 
+    /// Niri's trailing placeholder workspace on each output: the last one
+    /// by index, if it has no windows. Named workspaces are declared in
+    /// Niri's config rather than created on demand, so they never count.
+    pub fn trailing_empty_workspaces(&self) -> HashSet<u64> {
+        let mut last_by_output: HashMap<Option<&str>, &Workspace> = HashMap::new();
+        for ws in self.workspaces.values() {
+            let last = last_by_output.entry(ws.output.as_deref()).or_insert(ws);
+            if (ws.idx, ws.id) > (last.idx, last.id) {
+                *last = ws;
+            }
+        }
+        last_by_output
+            .into_values()
+            .filter(|ws| ws.windows.is_empty() && ws.name.is_none())
+            .map(|ws| ws.id)
+            .collect()
+    }
+
     /// Replace workspace metadata from a fresh snapshot, preserving existing
     /// window data for workspaces that still exist.
     ///
@@ -97,8 +385,6 @@ impl MinimapState {
     ///   If none is focused, keeps the previous value when that workspace
     ///   still exists, otherwise leaves it as `None`.
     pub fn replace_workspace_metadata(&mut self, incoming: &[niri_ipc::Workspace]) {
-        use std::collections::HashSet;
-
         let incoming_ids: HashSet<u64> = incoming.iter().map(|w| w.id).collect();
         self.workspaces.retain(|id, _| incoming_ids.contains(id));
 
@@ -109,9 +395,14 @@ impl MinimapState {
             });
             entry.id = ws.id;
             entry.idx = ws.idx;
+            entry.name = ws.name.clone();
             entry.output = ws.output.clone();
+            for window in entry.windows.values_mut() {
+                window.output = ws.output.clone();
+            }
             entry.is_active = ws.is_active;
             entry.active_window_id = ws.active_window_id;
+            entry.reported = true;
         }
 
         // Track the globally focused workspace (there is at most one).
@@ -120,8 +411,66 @@ impl MinimapState {
             .filter(|id| incoming_ids.contains(id)));
     }
 
+    /// Merge a freshly fetched snapshot (e.g. after a reconnect) into this
+    /// state.
+    ///
+    /// Workspaces, windows and focus are taken from `incoming` wholesale, so
+    /// a resync clears a focus left behind by a missed focus event. Only the
+    /// active workspace falls back to the previous one when the snapshot
+    /// doesn't report one and the old workspace still exists. The focus
+    /// history is kept for windows still present.
+    pub fn merge_full_state(&mut self, incoming: MinimapState) {
+        let previous_active = self.active_workspace_id;
+        let output_name = self.output_name.take();
+        let layout_gap = self.layout_gap;
+        let mut focus_sequence = std::mem::take(&mut self.focus_sequence);
+        let last_focus_sequence = self.last_focus_sequence;
+        *self = incoming;
+        // Where the minimap is shown isn't part of Niri's snapshot
+        self.output_name = output_name;
+        self.layout_gap = self.layout_gap.or(layout_gap);
+
+        // Neither is the focus order, which recency fading and column-active
+        // windows depend on
+        let live_ids: HashSet<u64> = self
+            .workspaces
+            .values()
+            .flat_map(|workspace| workspace.windows.keys().copied())
+            .collect();
+        focus_sequence.retain(|id, _| live_ids.contains(id));
+        self.focus_sequence = focus_sequence;
+        self.last_focus_sequence = last_focus_sequence;
+        if let Some(id) = self.focused_window_id {
+            if self.focus_sequence.get(&id) != Some(&self.last_focus_sequence) {
+                self.last_focus_sequence += 1;
+                self.focus_sequence.insert(id, self.last_focus_sequence);
+            }
+        }
+
+        // Only restore the id: the snapshot's per-monitor `is_active` flags
+        // are authoritative.
+        if self.active_workspace_id.is_none() {
+            self.active_workspace_id =
+                previous_active.filter(|id| self.workspaces.contains_key(id));
+        }
+
+        // The restored id may be stale relative to the snapshot's flags
+        self.reconcile_active_workspace();
+    }
+
+    /// Drop workspaces created on demand that are left empty and aren't
+    /// active, so they don't pile up between workspace lists from Niri.
+    /// Workspaces Niri reported are kept even when empty: their metadata
+    /// (output, name) is still needed once they're activated.
+    pub fn prune_workspaces(&mut self) {
+        let active_id = self.active_workspace_id;
+        self.workspaces
+            .retain(|&id, ws| ws.reported || !ws.windows.is_empty() || Some(id) == active_id);
+    }
+
     /// Update or insert a window in the appropriate workspace
-    pub fn upsert_window(&mut self, workspace_id: u64, window: Window) {
+    pub fn upsert_window(&mut self, workspace_id: u64, mut window: Window) {
+        window.workspace_id = Some(workspace_id);
         let workspace = self
             .workspaces
             .entry(workspace_id)
@@ -129,9 +478,28 @@ impl MinimapState {
                 id: workspace_id,
                 ..Default::default()
             });
+        window.output = workspace.output.clone();
         workspace.windows.insert(window.id, window);
     }
 
+    /// File a window under the workspace it reports, removing it from any
+    /// other workspace it was on (workspace moves). A window without a
+    /// workspace is dropped. Returns true if the window wasn't tracked before.
+    pub fn place_window(&mut self, window: Window) -> bool {
+        let is_new = self.find_window(window.id).is_none();
+        let target = window.workspace_id;
+
+        for (&id, workspace) in self.workspaces.iter_mut() {
+            if Some(id) != target {
+                workspace.windows.remove(&window.id);
+            }
+        }
+        if let Some(workspace_id) = target {
+            self.upsert_window(workspace_id, window);
+        }
+        is_new
+    }
+
     /// Remove a window by ID from all workspaces
     pub fn remove_window(&mut self, window_id: u64) {
         for workspace in self.workspaces.values_mut() {
@@ -139,26 +507,62 @@ impl MinimapState {
         }
     }
 
+    /// Drop every window for which `ignored` returns true
+    pub fn remove_windows_where(&mut self, ignored: impl Fn(&Window) -> bool) {
+        for workspace in self.workspaces.values_mut() {
+            workspace.windows.retain(|_, window| !ignored(window));
+        }
+    }
+
     /// Set the focused window ID and update focus state
     pub fn set_focused_window(&mut self, window_id: Option<u64>) {
-        // Clear old focus
+        // Clear old focus, trying the workspace it was last seen on first
         if let Some(old_id) = self.focused_window_id {
-            for workspace in self.workspaces.values_mut() {
-                if let Some(window) = workspace.windows.get_mut(&old_id) {
-                    window.is_focused = false;
+            let hinted = self
+                .focused_workspace_hint
+                .and_then(|ws_id| self.workspaces.get_mut(&ws_id))
+                .and_then(|workspace| workspace.windows.get_mut(&old_id));
+            if let Some(window) = hinted {
+                window.is_focused = false;
+            } else {
+                for workspace in self.workspaces.values_mut() {
+                    if let Some(window) = workspace.windows.get_mut(&old_id) {
+                        window.is_focused = false;
+                    }
                 }
             }
         }
 
-        // Set new focus
+        // Set new focus. Focusing a window also clears its urgency; Niri
+        // follows up with a WindowUrgencyChanged, but don't keep blinking
+        // until it arrives.
         self.focused_window_id = window_id;
+        self.focused_workspace_hint = None;
         if let Some(new_id) = window_id {
-            for workspace in self.workspaces.values_mut() {
+            self.last_focus_sequence += 1;
+            self.focus_sequence.insert(new_id, self.last_focus_sequence);
+            for (&ws_id, workspace) in self.workspaces.iter_mut() {
                 if let Some(window) = workspace.windows.get_mut(&new_id) {
                     window.is_focused = true;
+                    window.is_urgent = false;
+                    self.focused_workspace_hint = Some(ws_id);
+                    break;
                 }
             }
         }
+
+        // Focus went to a window this state doesn't know, e.g. one on another
+        // output. Nothing here may stay highlighted, whatever `focused_window_id`
+        // was before (windows can arrive already flagged as focused).
+        if self.focused_workspace_hint.is_none() {
+            for window in self
+                .workspaces
+                .values_mut()
+                .flat_map(|workspace| workspace.windows.values_mut())
+            {
+                window.is_focused = false;
+            }
+        }
     }
 
     /// Set the active workspace
@@ -183,6 +587,42 @@ impl MinimapState {
     }
 }
 
+/// Measure the gap between shown tiles of `workspace`, see
+/// `MinimapState::infer_layout_gap`.
+fn measure_gap(workspace: &Workspace) -> Option<f64> {
+    let mut shown: Vec<(&Window, (f64, f64))> = workspace
+        .windows
+        .values()
+        .filter(|w| !w.is_floating)
+        .filter_map(|w| Some((w, w.pos?)))
+        .collect();
+    shown.sort_by_key(|(w, _)| (w.column_index, w.window_index));
+    let valid = |gap: f64| (gap.is_finite() && gap >= 0.0).then_some(gap);
+
+    // Two tiles stacked in the same column
+    let stacked = shown.windows(2).find_map(|pair| {
+        let ((a, a_pos), (b, b_pos)) = (pair[0], pair[1]);
+        (a.column_index == b.column_index && a.window_index + 1 == b.window_index)
+            .then(|| valid(b_pos.1 - (a_pos.1 + a.size.1)))
+            .flatten()
+    });
+
+    // The first tiles of two neighboring columns
+    stacked.or_else(|| {
+        let mut firsts = shown.iter().filter(|(w, _)| w.window_index == 0);
+        let mut prev = firsts.next()?;
+        for next in firsts {
+            if next.0.column_index == prev.0.column_index + 1 {
+                if let Some(gap) = valid(next.1 .0 - (prev.1 .0 + prev.0.size.0)) {
+                    return Some(gap);
+                }
+            }
+            prev = next;
+        }
+        None
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -190,12 +630,20 @@ mod tests {
     fn create_test_window(id: u64, x: f64, y: f64, width: f64, height: f64) -> Window {
         Window {
             id,
+            workspace_id: None,
+            app_id: None,
+            title: None,
+            output: None,
             pos: Some((x, y)),
             size: (width, height),
             column_index: 0,
             window_index: 0,
             is_focused: false,
             is_floating: false,
+            is_urgent: false,
+            is_hidden: false,
+            is_column_active: false,
+            is_fullscreen: false,
         }
     }
 
@@ -299,6 +747,520 @@ mod tests {
         assert!(!workspace.windows.get(&1).unwrap().is_focused);
     }
 
+    #[test]
+    fn test_set_focused_window_after_focused_window_moved() {
+        let mut state = MinimapState::new();
+        state.upsert_window(1, create_test_window(1, 0.0, 0.0, 100.0, 200.0));
+        state.upsert_window(1, create_test_window(2, 100.0, 0.0, 100.0, 200.0));
+        state.set_focused_window(Some(1));
+
+        // Window 1 moves to workspace 2, keeping its focus flag
+        let moved = state.workspaces.get_mut(&1).unwrap().windows.remove(&1);
+        state.upsert_window(2, moved.unwrap());
+
+        state.set_focused_window(Some(2));
+        assert!(!state.find_window(1).unwrap().is_focused);
+        assert!(state.find_window(2).unwrap().is_focused);
+    }
+
+    #[test]
+    fn test_set_focused_window_unknown_id_clears_focus() {
+        let mut state = MinimapState::new();
+        let mut window1 = create_test_window(1, 0.0, 0.0, 100.0, 200.0);
+        window1.is_focused = true;
+        state.upsert_window(1, window1);
+        state.upsert_window(1, create_test_window(2, 100.0, 0.0, 100.0, 200.0));
+        state.set_focused_window(Some(2));
+
+        // Focus moves to a window on another output
+        state.set_focused_window(Some(99));
+
+        assert_eq!(state.focused_window_id, Some(99));
+        assert!(state
+            .workspaces
+            .values()
+            .flat_map(|workspace| workspace.windows.values())
+            .all(|window| !window.is_focused));
+    }
+
+    #[test]
+    fn test_any_on_active_workspace() {
+        let mut state = MinimapState::new();
+        state.upsert_window(1, create_test_window(1, 0.0, 0.0, 100.0, 200.0));
+        state.upsert_window(2, create_test_window(2, 0.0, 0.0, 100.0, 200.0));
+        // No active workspace yet
+        assert!(!state.any_on_active_workspace([1]));
+
+        state.set_active_workspace(1);
+        // A layout change only on the inactive workspace
+        assert!(!state.any_on_active_workspace([2]));
+        assert!(state.any_on_active_workspace([2, 1]));
+        assert!(!state.any_on_active_workspace([]));
+    }
+
+    #[test]
+    fn test_window_count() {
+        let mut state = MinimapState::new();
+        assert_eq!(state.window_count(), 0);
+        state.upsert_window(1, create_test_window(1, 0.0, 0.0, 100.0, 200.0));
+        state.upsert_window(1, create_test_window(2, 0.0, 0.0, 100.0, 200.0));
+        state.upsert_window(2, create_test_window(3, 0.0, 0.0, 100.0, 200.0));
+        assert_eq!(state.window_count(), 3);
+    }
+
+    #[test]
+    fn test_focus_ranks() {
+        let mut state = MinimapState::new();
+        for id in 1..=3 {
+            state.upsert_window(1, create_test_window(id, 0.0, 0.0, 100.0, 200.0));
+        }
+        assert!(state.focus_ranks().is_empty());
+
+        state.set_focused_window(Some(1));
+        state.set_focused_window(Some(2));
+        state.set_focused_window(Some(3));
+        state.set_focused_window(Some(1));
+
+        let ranks = state.focus_ranks();
+        assert_eq!(ranks[&1], 0);
+        assert_eq!(ranks[&3], 1);
+        assert_eq!(ranks[&2], 2);
+    }
+
+    #[test]
+    fn test_workspace_matches() {
+        let named = Workspace {
+            id: 7,
+            name: Some("main".to_string()),
+            ..Default::default()
+        };
+        assert!(named.matches("main"));
+        assert!(named.matches("7"));
+        assert!(!named.matches("Main"));
+        assert!(!named.matches("1"));
+
+        let unnamed = Workspace {
+            id: 3,
+            ..Default::default()
+        };
+        assert!(unnamed.matches("3"));
+        assert!(!unnamed.matches(""));
+    }
+
+    #[test]
+    fn test_infer_layout_gap() {
+        let mut state = MinimapState::new();
+
+        // Nothing shown: unknown
+        let mut offscreen = create_test_window(1, 0.0, 0.0, 100.0, 200.0);
+        offscreen.pos = None;
+        state.upsert_window(1, offscreen);
+        state.infer_layout_gap();
+        assert_eq!(state.layout_gap, None);
+
+        // Two neighboring columns 16px apart
+        state.upsert_window(2, create_test_window(2, 0.0, 0.0, 100.0, 200.0));
+        let mut right = create_test_window(3, 116.0, 0.0, 100.0, 200.0);
+        right.column_index = 1;
+        state.upsert_window(2, right);
+        state.infer_layout_gap();
+        assert_eq!(state.layout_gap, Some(16.0));
+
+        // Stacked tiles take precedence
+        let mut below = create_test_window(4, 0.0, 210.0, 100.0, 200.0);
+        below.window_index = 1;
+        state.upsert_window(2, below);
+        state.infer_layout_gap();
+        assert_eq!(state.layout_gap, Some(10.0));
+
+        // Kept when nothing can be measured any more
+        state.workspaces.remove(&2);
+        state.infer_layout_gap();
+        assert_eq!(state.layout_gap, Some(10.0));
+    }
+
+    #[test]
+    fn test_infer_hidden_windows() {
+        let mut state = MinimapState::new();
+        // Column 0: a tabbed column where only window 1 is shown
+        state.upsert_window(1, create_test_window(1, 0.0, 0.0, 100.0, 200.0));
+        let mut tab = create_test_window(2, 0.0, 0.0, 100.0, 200.0);
+        tab.pos = None;
+        tab.window_index = 1;
+        state.upsert_window(1, tab);
+        // Column 1: scrolled out of view entirely
+        let mut offscreen = create_test_window(3, 0.0, 0.0, 100.0, 200.0);
+        offscreen.pos = None;
+        offscreen.column_index = 1;
+        state.upsert_window(1, offscreen);
+
+        state.infer_hidden_windows();
+        assert!(!state.find_window(1).unwrap().is_hidden);
+        assert!(state.find_window(2).unwrap().is_hidden);
+        assert!(!state.find_window(3).unwrap().is_hidden);
+    }
+
+    #[test]
+    fn test_infer_fullscreen_windows() {
+        let mut state = MinimapState::new();
+        state.replace_workspace_metadata(&[
+            ipc_workspace(1, 1, Some("DP-1"), true, true),
+            ipc_workspace(2, 1, Some("HDMI-A-1"), true, false),
+        ]);
+        state.upsert_window(1, create_test_window(1, 0.0, 0.0, 1920.0, 1080.0));
+        state.upsert_window(1, create_test_window(2, 0.0, 0.0, 944.0, 1048.0));
+        // Same size, but on a larger output
+        state.upsert_window(2, create_test_window(3, 0.0, 0.0, 1920.0, 1080.0));
+
+        let output_size = |output: Option<&str>| match output {
+            Some("DP-1") => Some((1920.0, 1080.0)),
+            Some("HDMI-A-1") => Some((2560.0, 1440.0)),
+            _ => None,
+        };
+        state.infer_fullscreen_windows(output_size);
+        assert!(state.find_window(1).unwrap().is_fullscreen);
+        assert!(!state.find_window(2).unwrap().is_fullscreen);
+        assert!(!state.find_window(3).unwrap().is_fullscreen);
+
+        // Leaving fullscreen shrinks the tile back
+        state.upsert_window(1, create_test_window(1, 0.0, 0.0, 944.0, 1048.0));
+        state.infer_fullscreen_windows(output_size);
+        assert!(!state.find_window(1).unwrap().is_fullscreen);
+    }
+
+    #[test]
+    fn test_place_window_moves_between_workspaces() {
+        let mut state = MinimapState::new();
+        state.set_active_workspace(1);
+        state.upsert_window(2, create_test_window(9, 0.0, 0.0, 100.0, 200.0));
+
+        let mut window = create_test_window(1, 0.0, 0.0, 100.0, 200.0);
+        window.workspace_id = Some(1);
+        assert!(state.place_window(window.clone()));
+
+        // Moving to a non-active workspace files it there and nowhere else
+        window.workspace_id = Some(2);
+        assert!(!state.place_window(window.clone()));
+        assert!(!state.workspaces[&1].windows.contains_key(&1));
+        assert!(state.workspaces[&2].windows.contains_key(&1));
+        assert!(state.workspaces[&2].windows.contains_key(&9));
+        assert_eq!(state.find_window(1).unwrap().workspace_id, Some(2));
+
+        // A window that leaves every workspace is dropped
+        window.workspace_id = None;
+        assert!(!state.place_window(window));
+        assert!(state.find_window(1).is_none());
+    }
+
+    #[test]
+    fn test_window_output_follows_workspace() {
+        let mut state = MinimapState::new();
+        state.output_name = Some("DP-1".to_string());
+        state.replace_workspace_metadata(&[ipc_workspace(1, 1, Some("DP-1"), true, true)]);
+        state.upsert_window(1, create_test_window(1, 0.0, 0.0, 100.0, 200.0));
+        assert_eq!(
+            state.find_window(1).unwrap().output.as_deref(),
+            Some("DP-1")
+        );
+
+        // The workspace moves to another monitor
+        state.replace_workspace_metadata(&[ipc_workspace(1, 1, Some("HDMI-A-1"), true, true)]);
+        assert_eq!(
+            state.find_window(1).unwrap().output.as_deref(),
+            Some("HDMI-A-1")
+        );
+
+        // A resync keeps track of where the minimap itself is shown
+        state.merge_full_state(MinimapState::new());
+        assert_eq!(state.output_name.as_deref(), Some("DP-1"));
+    }
+
+    #[test]
+    fn test_infer_column_active_windows() {
+        let mut state = MinimapState::new();
+        for (id, window_index) in [(1, 0), (2, 1), (3, 2)] {
+            let mut window = create_test_window(id, 0.0, 0.0, 100.0, 100.0);
+            window.window_index = window_index;
+            state.upsert_window(1, window);
+        }
+        // A single-window column never has a column-active window
+        let mut lone = create_test_window(4, 100.0, 0.0, 100.0, 300.0);
+        lone.column_index = 1;
+        state.upsert_window(1, lone);
+
+        // Nothing known yet: no highlight
+        state.infer_hidden_windows();
+        state.infer_column_active_windows();
+        assert!(!state.find_window(1).unwrap().is_column_active);
+
+        // The last window focused within the column stays active after
+        // focus moves to another column
+        state.set_focused_window(Some(2));
+        state.set_focused_window(Some(4));
+        state.infer_column_active_windows();
+        assert!(state.find_window(2).unwrap().is_column_active);
+        assert!(!state.find_window(1).unwrap().is_column_active);
+        assert!(!state.find_window(4).unwrap().is_column_active);
+
+        // Without focus history, the only shown tab is active
+        let mut state = MinimapState::new();
+        state.upsert_window(1, create_test_window(1, 0.0, 0.0, 100.0, 100.0));
+        let mut tab = create_test_window(2, 0.0, 0.0, 100.0, 100.0);
+        tab.pos = None;
+        tab.window_index = 1;
+        state.upsert_window(1, tab);
+        state.infer_hidden_windows();
+        state.infer_column_active_windows();
+        assert!(state.find_window(1).unwrap().is_column_active);
+        assert!(!state.find_window(2).unwrap().is_column_active);
+    }
+
+    #[test]
+    fn test_set_window_urgent_and_focus_clears_it() {
+        let mut state = MinimapState::new();
+        state.upsert_window(1, create_test_window(1, 0.0, 0.0, 100.0, 200.0));
+        state.upsert_window(2, create_test_window(2, 0.0, 0.0, 100.0, 200.0));
+        assert!(!state.has_urgent_windows());
+
+        state.set_window_urgent(2, true);
+        assert!(state.has_urgent_windows());
+        assert!(state.find_window(2).unwrap().is_urgent);
+
+        // Focusing the urgent window clears its urgency
+        state.set_focused_window(Some(2));
+        assert!(!state.find_window(2).unwrap().is_urgent);
+        assert!(!state.has_urgent_windows());
+    }
+
+    #[test]
+    fn test_merge_full_state_trusts_snapshot_focus() {
+        let mut state = MinimapState::new();
+        state.upsert_window(1, create_test_window(100, 0.0, 0.0, 100.0, 100.0));
+        state.set_active_workspace(1);
+        state.set_focused_window(Some(100));
+
+        // Nothing is focused any more, but the FocusChanged(None) was missed
+        let mut incoming = MinimapState::new();
+        incoming.upsert_window(1, create_test_window(100, 0.0, 0.0, 100.0, 100.0));
+        incoming.upsert_window(1, create_test_window(101, 100.0, 0.0, 100.0, 100.0));
+        state.merge_full_state(incoming);
+
+        assert_eq!(state.focused_window_id, None);
+        assert!(!state.find_window(100).unwrap().is_focused);
+        // The active workspace still falls back to the previous one
+        assert_eq!(state.active_workspace_id, Some(1));
+        assert_eq!(state.workspaces[&1].windows.len(), 2);
+    }
+
+    #[test]
+    fn test_merge_full_state_keeps_focus_history() {
+        let column_window = |id, window_index| {
+            let mut window = create_test_window(id, 0.0, 0.0, 100.0, 100.0);
+            window.window_index = window_index;
+            window
+        };
+        let mut lone = create_test_window(3, 100.0, 0.0, 100.0, 100.0);
+        lone.column_index = 1;
+
+        let mut state = MinimapState::new();
+        state.upsert_window(1, column_window(1, 0));
+        state.upsert_window(1, column_window(2, 1));
+        state.upsert_window(1, lone.clone());
+        state.set_focused_window(Some(2));
+        state.set_focused_window(Some(1));
+        state.set_focused_window(Some(3));
+        state.infer_column_active_windows();
+        let ranks = state.focus_ranks();
+
+        // A periodic poll reports the same windows, focus unchanged
+        let mut incoming = MinimapState::new();
+        incoming.upsert_window(1, column_window(1, 0));
+        incoming.upsert_window(1, column_window(2, 1));
+        incoming.upsert_window(1, lone);
+        incoming.set_focused_window(Some(3));
+        state.merge_full_state(incoming);
+        state.infer_column_active_windows();
+
+        assert_eq!(state.focus_ranks(), ranks);
+        // Column 0 keeps the window focused last within it
+        assert!(state.find_window(1).unwrap().is_column_active);
+        assert!(!state.find_window(2).unwrap().is_column_active);
+
+        // Closed windows are forgotten
+        let mut incoming = MinimapState::new();
+        incoming.upsert_window(1, column_window(2, 1));
+        incoming.set_focused_window(Some(2));
+        state.merge_full_state(incoming);
+        assert_eq!(state.focus_ranks(), HashMap::from([(2, 0)]));
+    }
+
+    #[test]
+    fn test_merge_full_state_then_focus_change() {
+        let mut state = MinimapState::new();
+        state.upsert_window(1, create_test_window(100, 0.0, 0.0, 100.0, 100.0));
+        state.set_focused_window(Some(100));
+
+        let mut incoming = MinimapState::new();
+        incoming.upsert_window(1, create_test_window(100, 0.0, 0.0, 100.0, 100.0));
+        incoming.upsert_window(1, create_test_window(101, 100.0, 0.0, 100.0, 100.0));
+        incoming.set_focused_window(Some(100));
+        state.merge_full_state(incoming);
+
+        // A FocusChanged arriving right after the resync wins
+        state.set_focused_window(Some(101));
+        assert_eq!(state.focused_window_id, Some(101));
+        assert!(!state.find_window(100).unwrap().is_focused);
+        assert!(state.find_window(101).unwrap().is_focused);
+    }
+
+    #[test]
+    fn test_merge_full_state_drops_stale_focus() {
+        let mut state = MinimapState::new();
+        state.upsert_window(1, create_test_window(100, 0.0, 0.0, 100.0, 100.0));
+        state.set_active_workspace(1);
+        state.set_focused_window(Some(100));
+
+        // Window and workspace are gone from the snapshot
+        let mut incoming = MinimapState::new();
+        incoming.upsert_window(2, create_test_window(200, 0.0, 0.0, 100.0, 100.0));
+        state.merge_full_state(incoming);
+
+        assert_eq!(state.focused_window_id, None);
+        assert_eq!(state.active_workspace_id, None);
+    }
+
+    #[test]
+    fn test_active_workspace_has_tiled_windows() {
+        let mut state = MinimapState::new();
+        assert!(!state.active_workspace_has_tiled_windows());
+
+        state.set_active_workspace(1);
+        assert!(!state.active_workspace_has_tiled_windows());
+
+        // Floating windows alone don't count
+        let mut floating = create_test_window(100, 0.0, 0.0, 100.0, 100.0);
+        floating.is_floating = true;
+        state.upsert_window(1, floating);
+        assert!(!state.active_workspace_has_tiled_windows());
+
+        state.upsert_window(1, create_test_window(101, 0.0, 0.0, 100.0, 100.0));
+        assert!(state.active_workspace_has_tiled_windows());
+
+        // Switching to an empty workspace
+        state.set_active_workspace(2);
+        assert!(!state.active_workspace_has_tiled_windows());
+    }
+
+    #[test]
+    fn test_floating_focus_column() {
+        let mut state = MinimapState::new();
+        state.upsert_window(1, create_test_window(1, 0.0, 0.0, 800.0, 1000.0));
+        let mut right = create_test_window(2, 800.0, 0.0, 800.0, 1000.0);
+        right.column_index = 1;
+        state.upsert_window(1, right);
+        let mut floating = create_test_window(3, 900.0, 100.0, 400.0, 300.0);
+        floating.is_floating = true;
+        state.upsert_window(1, floating);
+
+        // Focus on a tiled window needs no fallback
+        state.set_focused_window(Some(1));
+        assert_eq!(state.floating_focus_column(), None);
+
+        // Floating window centered at x = 1100, over column 1
+        state.set_focused_window(Some(3));
+        assert_eq!(state.floating_focus_column(), Some((1, 1)));
+
+        // Over no column in view
+        state
+            .workspaces
+            .get_mut(&1)
+            .unwrap()
+            .windows
+            .get_mut(&3)
+            .unwrap()
+            .pos = Some((2000.0, 100.0));
+        assert_eq!(state.floating_focus_column(), None);
+    }
+
+    #[test]
+    fn test_active_workspace_falls_back_to_is_active_flag() {
+        let mut state = MinimapState::new();
+        state.workspaces.insert(
+            2,
+            Workspace {
+                id: 2,
+                is_active: true,
+                ..Default::default()
+            },
+        );
+
+        // Unset id
+        assert_eq!(state.active_workspace().map(|ws| ws.id), Some(2));
+
+        // Id pointing at a workspace that no longer exists
+        state.active_workspace_id = Some(99);
+        assert_eq!(state.active_workspace().map(|ws| ws.id), Some(2));
+    }
+
+    #[test]
+    fn test_merge_full_state_prefers_is_active_over_stale_id() {
+        let mut state = MinimapState::new();
+        state.upsert_window(1, create_test_window(100, 0.0, 0.0, 100.0, 100.0));
+        state.set_active_workspace(1);
+
+        // The snapshot has no focused workspace, but flags workspace 2 active
+        let mut incoming = MinimapState::new();
+        incoming.upsert_window(1, create_test_window(100, 0.0, 0.0, 100.0, 100.0));
+        incoming.upsert_window(2, create_test_window(200, 0.0, 0.0, 100.0, 100.0));
+        incoming.workspaces.get_mut(&2).unwrap().is_active = true;
+        state.merge_full_state(incoming);
+
+        assert_eq!(state.active_workspace_id, Some(2));
+        assert_eq!(state.active_workspace().map(|ws| ws.id), Some(2));
+    }
+
+    #[test]
+    fn test_reconcile_active_workspace_keeps_consistent_id() {
+        let mut state = MinimapState::new();
+        state.set_active_workspace(1);
+        state.workspaces.insert(
+            2,
+            Workspace {
+                id: 2,
+                is_active: true,
+                ..Default::default()
+            },
+        );
+
+        // Both are flagged active (e.g. one per monitor); the id is valid
+        state.reconcile_active_workspace();
+        assert_eq!(state.active_workspace_id, Some(1));
+    }
+
+    #[test]
+    fn test_focused_output() {
+        let mut state = MinimapState::new();
+        assert_eq!(state.focused_output(), None);
+
+        for (id, output) in [(1, "DP-1"), (2, "HDMI-A-1")] {
+            state.workspaces.insert(
+                id,
+                Workspace {
+                    id,
+                    output: Some(output.to_string()),
+                    ..Default::default()
+                },
+            );
+        }
+        state.set_active_workspace(1);
+        assert_eq!(state.focused_output(), Some("DP-1"));
+
+        // The focused window's workspace wins over the active one
+        state.upsert_window(2, create_test_window(200, 0.0, 0.0, 100.0, 100.0));
+        state.set_focused_window(Some(200));
+        assert_eq!(state.focused_output(), Some("HDMI-A-1"));
+    }
+
     #[test]
     fn test_minimap_state_set_active_workspace() {
         let mut state = MinimapState::new();
@@ -370,6 +1332,54 @@ mod tests {
         assert_eq!(sorted_ids, vec![1, 3, 4, 5, 7]);
     }
 
+    #[test]
+    fn test_prune_workspaces() {
+        let mut state = MinimapState::new();
+        state.replace_workspace_metadata(&[ipc_workspace(1, 1, Some("DP-1"), true, true)]);
+
+        // Hop through transient workspaces, opening and closing a window on each
+        for id in 2..=6 {
+            state.set_active_workspace(id);
+            state.upsert_window(id, create_test_window(id * 10, 0.0, 0.0, 100.0, 200.0));
+            state.remove_window(id * 10);
+            state.prune_workspaces();
+        }
+        // Only the active one of them is left, plus the reported workspace
+        assert_eq!(state.workspaces.len(), 2);
+        assert!(state.workspaces.contains_key(&6));
+
+        state.set_active_workspace(1);
+        state.upsert_window(7, create_test_window(70, 0.0, 0.0, 100.0, 200.0));
+        state.prune_workspaces();
+        // Reported (even though empty) and non-empty workspaces stay
+        let mut ids: Vec<u64> = state.workspaces.keys().copied().collect();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![1, 7]);
+    }
+
+    #[test]
+    fn test_trailing_empty_workspaces() {
+        let mut state = MinimapState::new();
+        let mut named = ipc_workspace(6, 2, Some("HDMI-1"), false, false);
+        named.name = Some("chat".to_string());
+        state.replace_workspace_metadata(&[
+            ipc_workspace(1, 1, Some("DP-1"), true, true),
+            ipc_workspace(2, 2, Some("DP-1"), false, false),
+            ipc_workspace(3, 3, Some("DP-1"), false, false),
+            ipc_workspace(5, 1, Some("HDMI-1"), true, false),
+            named,
+        ]);
+        state.upsert_window(1, create_test_window(10, 0.0, 0.0, 100.0, 200.0));
+
+        // Only the last empty workspace on DP-1; workspace 2 is empty but not
+        // trailing, and HDMI-1's last one is named
+        assert_eq!(state.trailing_empty_workspaces(), HashSet::from([3]));
+
+        // A window on the trailing workspace means it's no longer a placeholder
+        state.upsert_window(3, create_test_window(11, 0.0, 0.0, 100.0, 200.0));
+        assert!(state.trailing_empty_workspaces().is_empty());
+    }
+
     #[test]
     fn test_replace_workspace_metadata_preserves_windows() {
         let mut state = MinimapState::new();
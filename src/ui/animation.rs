@@ -0,0 +1,165 @@
+use std::time::{Duration, Instant};
+
+use crate::state::Workspace;
+
+/// Duration of window open/close animations
+pub const WINDOW_ANIMATION_DURATION: Duration = Duration::from_millis(120);
+
+/// Redraw interval while animations are running (~60 fps)
+pub const ANIMATION_FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Fraction of its full size a window grows from when opening (and shrinks
+/// to when closing)
+pub const WINDOW_ANIMATION_MIN_SCALE: f64 = 0.6;
+
+/// Which way a window is animating
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationKind {
+    /// Newly opened: scale/fade in
+    Open,
+    /// Closed: fade out, then removed from the state
+    Close,
+}
+
+/// A running open/close animation for one window
+#[derive(Debug, Clone, Copy)]
+pub struct WindowAnimation {
+    pub kind: AnimationKind,
+    pub start: Instant,
+}
+
+impl WindowAnimation {
+    pub fn new(kind: AnimationKind, start: Instant) -> Self {
+        Self { kind, start }
+    }
+
+    /// Linear progress from 0.0 to 1.0
+    pub fn progress(&self, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.start);
+        (elapsed.as_secs_f64() / WINDOW_ANIMATION_DURATION.as_secs_f64()).min(1.0)
+    }
+
+    /// How much of the window is shown: 0.0 = invisible, 1.0 = fully drawn
+    pub fn visibility(&self, now: Instant) -> f64 {
+        match self.kind {
+            AnimationKind::Open => self.progress(now),
+            AnimationKind::Close => 1.0 - self.progress(now),
+        }
+    }
+
+    pub fn is_finished(&self, now: Instant) -> bool {
+        self.progress(now) >= 1.0
+    }
+}
+
+/// Scale applied to a window rect at the given visibility
+pub fn scale_for_visibility(visibility: f64) -> f64 {
+    WINDOW_ANIMATION_MIN_SCALE + (1.0 - WINDOW_ANIMATION_MIN_SCALE) * visibility.clamp(0.0, 1.0)
+}
+
+/// A running workspace switch in `current` mode: the previous workspace
+/// slides out while the new one slides in (`workspace_transition_ms`)
+#[derive(Debug, Clone)]
+pub struct WorkspaceSlide {
+    /// Snapshot of the workspace being left
+    pub from: Workspace,
+    pub start: Instant,
+    pub duration: Duration,
+    /// -1.0 slides the content left (to a higher workspace id), 1.0 right
+    pub direction: f64,
+}
+
+impl WorkspaceSlide {
+    pub fn new(from: Workspace, to_id: u64, start: Instant, duration: Duration) -> Self {
+        let direction = if to_id > from.id { -1.0 } else { 1.0 };
+        Self {
+            from,
+            start,
+            duration,
+            direction,
+        }
+    }
+
+    /// Eased (cubic ease-out) progress from 0.0 to 1.0
+    pub fn progress(&self, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.start).as_secs_f64();
+        let t = if self.duration.is_zero() {
+            1.0
+        } else {
+            (elapsed / self.duration.as_secs_f64()).min(1.0)
+        };
+        1.0 - (1.0 - t).powi(3)
+    }
+
+    /// Horizontal offsets of the old and the new workspace, as fractions of
+    /// the minimap width
+    pub fn offsets(&self, now: Instant) -> (f64, f64) {
+        let progress = self.progress(now);
+        (self.direction * progress, self.direction * (progress - 1.0))
+    }
+
+    pub fn is_finished(&self, now: Instant) -> bool {
+        self.progress(now) >= 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_animation_fades_in() {
+        let start = Instant::now();
+        let anim = WindowAnimation::new(AnimationKind::Open, start);
+
+        assert_eq!(anim.visibility(start), 0.0);
+        let halfway = anim.visibility(start + WINDOW_ANIMATION_DURATION / 2);
+        assert!((halfway - 0.5).abs() < 1e-6);
+        assert_eq!(anim.visibility(start + WINDOW_ANIMATION_DURATION), 1.0);
+        assert!(!anim.is_finished(start));
+        assert!(anim.is_finished(start + WINDOW_ANIMATION_DURATION));
+    }
+
+    #[test]
+    fn test_close_animation_fades_out() {
+        let start = Instant::now();
+        let anim = WindowAnimation::new(AnimationKind::Close, start);
+
+        assert_eq!(anim.visibility(start), 1.0);
+        assert_eq!(anim.visibility(start + WINDOW_ANIMATION_DURATION * 2), 0.0);
+        // A clock before the start counts as not started
+        assert_eq!(anim.progress(start - Duration::from_millis(10)), 0.0);
+    }
+
+    #[test]
+    fn test_workspace_slide_offsets() {
+        let start = Instant::now();
+        let duration = Duration::from_millis(200);
+        let from = Workspace {
+            id: 2,
+            ..Default::default()
+        };
+
+        // To a higher id: the old workspace leaves left, the new enters from the right
+        let forward = WorkspaceSlide::new(from.clone(), 3, start, duration);
+        assert_eq!(forward.offsets(start), (0.0, 1.0));
+        assert_eq!(forward.offsets(start + duration), (-1.0, 0.0));
+        let (old, new) = forward.offsets(start + duration / 2);
+        assert!(old < -0.5, "ease-out is past halfway at half time: {}", old);
+        assert!((new - old - 1.0).abs() < 1e-9);
+        assert!(!forward.is_finished(start + duration / 2));
+        assert!(forward.is_finished(start + duration));
+
+        // To a lower id the other way round
+        let back = WorkspaceSlide::new(from, 1, start, duration);
+        assert_eq!(back.offsets(start), (0.0, -1.0));
+        assert_eq!(back.offsets(start + duration * 2), (1.0, 0.0));
+    }
+
+    #[test]
+    fn test_scale_for_visibility() {
+        assert_eq!(scale_for_visibility(0.0), WINDOW_ANIMATION_MIN_SCALE);
+        assert_eq!(scale_for_visibility(1.0), 1.0);
+        assert_eq!(scale_for_visibility(2.0), 1.0);
+    }
+}
@@ -1,11 +1,23 @@
+use anyhow::Result;
 use gtk4::prelude::*;
 use gtk4::{Application, ApplicationWindow};
 use gtk4_layer_shell::{Edge, KeyboardMode, Layer, LayerShell};
 
-use crate::config::{Anchor, Config};
+use crate::config::{Anchor, Config, KeyboardInteractivity};
+
+/// Create and configure a layer-shell window for the minimap.
+///
+/// Fails if the compositor doesn't support the wlr-layer-shell protocol
+/// (or isn't a Wayland compositor at all): a plain toplevel would be tiled
+/// like any other window, which defeats the point of an overlay.
+pub fn create_layer_window(app: &Application, config: &Config) -> Result<ApplicationWindow> {
+    if !gtk4_layer_shell::is_supported() {
+        anyhow::bail!(
+            "The compositor doesn't support the wlr-layer-shell protocol, which nirimap \
+             needs to draw its overlay. nirimap is meant to run under Niri."
+        );
+    }
 
-/// Create and configure a layer-shell window for the minimap
-pub fn create_layer_window(app: &Application, config: &Config) -> ApplicationWindow {
     // Start with height from config; width will be set dynamically
     let window = ApplicationWindow::builder()
         .application(app)
@@ -23,8 +35,13 @@ pub fn create_layer_window(app: &Application, config: &Config) -> ApplicationWin
     // Initialize layer shell
     window.init_layer_shell();
 
-    // Set the namespace for layer rules
-    window.set_namespace(Some("nirimap"));
+    // Set the namespace for layer rules, defaulting to the app name
+    let namespace = if config.display.namespace.is_empty() {
+        crate::config::app_name()
+    } else {
+        config.display.namespace.as_str()
+    };
+    window.set_namespace(Some(namespace));
 
     // Set layer to overlay (above fullscreen windows)
     window.set_layer(Layer::Overlay);
@@ -32,20 +49,29 @@ pub fn create_layer_window(app: &Application, config: &Config) -> ApplicationWin
     // Don't reserve exclusive screen space
     window.set_exclusive_zone(0);
 
-    // No keyboard interactivity (read-only minimap)
-    window.set_keyboard_mode(KeyboardMode::None);
+    // No keyboard interactivity by default (read-only minimap)
+    window.set_keyboard_mode(match config.display.keyboard_interactivity {
+        KeyboardInteractivity::None => KeyboardMode::None,
+        KeyboardInteractivity::OnDemand => KeyboardMode::OnDemand,
+        KeyboardInteractivity::Exclusive => KeyboardMode::Exclusive,
+    });
 
     // Make window click-through (don't receive pointer events at GTK level)
-    window.set_can_target(false);
-
-    // Configure anchor based on config
-    configure_anchor(&window, config);
-
-    // Set margins
-    window.set_margin(Edge::Top, config.display.margin_y);
-    window.set_margin(Edge::Bottom, config.display.margin_y);
-    window.set_margin(Edge::Left, config.display.margin_x);
-    window.set_margin(Edge::Right, config.display.margin_x);
+    // unless interactive mode wants hover/click events.
+    let interactive = config.behavior.interactive;
+    window.set_can_target(interactive);
+
+    // Configure anchor and margins based on config
+    anchor_window(&window, config);
+
+    // Pin to the configured output, if any
+    let output = &config.display.output;
+    if !output.is_empty() && !move_to_output(&window, output) {
+        tracing::warn!(
+            "Output {:?} not found, using the compositor's default placement",
+            output
+        );
+    }
 
     // Set up CSS for transparency. GTK renders widget CSS backgrounds in a
     // separate render node beneath our Cairo content, so we must zero it out
@@ -77,16 +103,29 @@ pub fn create_layer_window(app: &Application, config: &Config) -> ApplicationWin
         gtk4::STYLE_PROVIDER_PRIORITY_USER,
     );
 
-    // Set up empty input region for true click-through at Wayland level
-    window.connect_realize(|window| {
-        if let Some(surface) = window.surface() {
-            // Create an empty region for input - this makes the surface click-through
-            let empty_region = gtk4::cairo::Region::create();
-            surface.set_input_region(Some(&empty_region));
-        }
-    });
+    // Set up empty input region for true click-through at Wayland level.
+    // Interactive mode keeps the default (whole-surface) input region.
+    if !interactive {
+        window.connect_realize(|window| {
+            if let Some(surface) = window.surface() {
+                // Create an empty region for input - this makes the surface click-through
+                let empty_region = gtk4::cairo::Region::create();
+                surface.set_input_region(Some(&empty_region));
+            }
+        });
+    }
+
+    Ok(window)
+}
 
-    window
+/// Apply `display.anchor` and the edge margins to the window. Also used to
+/// re-anchor the live window, e.g. for the `cycle-anchor` command.
+pub fn anchor_window(window: &ApplicationWindow, config: &Config) {
+    configure_anchor(window, config);
+    window.set_margin(Edge::Top, config.display.top_margin());
+    window.set_margin(Edge::Bottom, config.display.bottom_margin());
+    window.set_margin(Edge::Left, config.display.left_margin());
+    window.set_margin(Edge::Right, config.display.right_margin());
 }
 
 /// Configure the window anchor position based on config
@@ -128,3 +167,24 @@ fn configure_anchor(window: &ApplicationWindow, config: &Config) {
         }
     }
 }
+
+/// Find the GDK monitor for a Niri output name (its connector, e.g. "DP-1")
+pub fn monitor_for_output(name: &str) -> Option<gtk4::gdk::Monitor> {
+    let display = gtk4::gdk::Display::default()?;
+    let monitors = display.monitors();
+    (0..monitors.n_items())
+        .filter_map(|i| monitors.item(i)?.downcast::<gtk4::gdk::Monitor>().ok())
+        .find(|monitor| monitor.connector().as_deref() == Some(name))
+}
+
+/// Move the layer surface to the monitor showing `output`, keeping the
+/// configured anchor and margins. Returns false if the output is unknown.
+pub fn move_to_output(window: &ApplicationWindow, output: &str) -> bool {
+    match monitor_for_output(output) {
+        Some(monitor) => {
+            window.set_monitor(Some(&monitor));
+            true
+        }
+        None => false,
+    }
+}
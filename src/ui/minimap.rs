@@ -1,18 +1,117 @@
+use std::borrow::Cow;
 use std::cell::{Cell, RefCell};
-use std::collections::BTreeMap;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::Path;
 use std::rc::Rc;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 
-use gtk4::cairo::{Context, Operator};
-use gtk4::glib;
+use anyhow::Context as _;
+use gtk4::cairo::{
+    Antialias, Context, FontSlant, FontWeight, Format, ImageSurface, LinearGradient, Operator,
+};
+use gtk4::gdk_pixbuf::Pixbuf;
 use gtk4::prelude::*;
+use gtk4::{gdk, glib};
 use gtk4::{ApplicationWindow, DrawingArea};
 
-use crate::config::{AppearanceConfig, Color, Config, DisplayConfig, WorkspaceMode};
+use super::animation::{
+    scale_for_visibility, AnimationKind, WindowAnimation, WorkspaceSlide, ANIMATION_FRAME_INTERVAL,
+};
+use super::layer::{anchor_window, monitor_for_output, move_to_output};
+use crate::config::{
+    env_flag_set, Anchor, AppearanceConfig, BehaviorConfig, Color, Config, Corner, CornerStyle,
+    Density, DisplayConfig, FloatingIndicator, GapMode, Overflow, ShowTrigger, VerticalAlign,
+    WorkspaceMode,
+};
+use crate::ipc::NiriClient;
+use crate::metrics::{self, METRICS};
 use crate::state::{MinimapState, Window, Workspace};
 
 /// Outer padding around the minimap content, in minimap pixels.
 const PADDING: f64 = 4.0;
 
+/// Space between a focused window's edge and its focus ring, in minimap pixels.
+const FOCUS_RING_SPACING: f64 = 1.0;
+
+/// Columns narrower than this (in minimap pixels) don't get a window count.
+const MIN_COLUMN_COUNT_WIDTH: f64 = 12.0;
+
+/// Bounds for `scale_borders`, in minimap pixels, so borders stay visible on
+/// tiny minimaps and don't swallow windows on huge ones.
+const MIN_SCALED_BORDER_WIDTH: f64 = 0.5;
+const MAX_SCALED_BORDER_WIDTH: f64 = 4.0;
+const MAX_SCALED_BORDER_RADIUS: f64 = 16.0;
+
+/// Smallest window rectangle side, in minimap pixels, so tiny windows stay
+/// visible whatever the gap.
+const MIN_WINDOW_RECT_SIZE: f64 = 1.0;
+
+/// Environment variable that overlays layout diagnostics on the minimap
+const DEBUG_OVERLAY_ENV_VAR: &str = "NIRIMAP_DEBUG_OVERLAY";
+
+/// With `recency_fade`, each window focused since another one was costs it
+/// this much fill opacity, down to the floor (also used for windows never
+/// focused).
+const RECENCY_FADE_STEP: f64 = 0.15;
+const RECENCY_FADE_FLOOR: f64 = 0.3;
+
+const WHITE: Color = Color {
+    r: 1.0,
+    g: 1.0,
+    b: 1.0,
+    a: 1.0,
+};
+
+const BLACK: Color = Color {
+    r: 0.0,
+    g: 0.0,
+    b: 0.0,
+    a: 1.0,
+};
+
+/// Width of the fade at an edge where `overflow = "scroll"` cuts content off
+const SCROLL_FADE_WIDTH: f64 = 12.0;
+
+/// Opacity multiplier for windows that exist but aren't shown (inactive tabs).
+const HIDDEN_WINDOW_OPACITY: f64 = 0.4;
+
+/// With `floating_indicator = "column"`, the column under a focused floating
+/// window gets `focused_color` at this fraction of `focused_opacity`.
+const FLOATING_COLUMN_OPACITY: f64 = 0.5;
+
+/// With `overview_all_workspaces`, workspaces other than the active one are
+/// drawn at this opacity while Niri's overview is open.
+const OVERVIEW_INACTIVE_OPACITY: f64 = 0.4;
+
+/// Side of the `floating_indicator = "glyph"` icon, in minimap pixels.
+const FLOATING_GLYPH_SIZE: f64 = 8.0;
+
+/// Height of the `floating_strip` boxes, in minimap pixels. They are half
+/// again as wide.
+const FLOATING_STRIP_BOX_SIZE: f64 = 8.0;
+
+/// Font size of the `show_workspace_number` badge, in minimap pixels.
+const WORKSPACE_NUMBER_FONT_SIZE: f64 = 9.0;
+
+/// App icons (`show_icons`) are skipped in window boxes whose shorter side
+/// is below this, in minimap pixels; the icon wouldn't be recognizable.
+const MIN_ICON_BOX_SIZE: f64 = 14.0;
+
+/// Icon size relative to the shorter side of its window box, and its cap.
+const ICON_BOX_FRACTION: f64 = 0.6;
+const MAX_ICON_SIZE: f64 = 48.0;
+
+/// Size icons are loaded at; larger boxes would only show a blurry icon.
+const ICON_LOAD_SIZE: i32 = 48;
+
+/// Upper bound on the background fill opacity when `blur_behind` is enabled,
+/// so the blurred backdrop isn't hidden behind a near-opaque fill.
+const BLUR_BEHIND_MAX_BACKGROUND_OPACITY: f64 = 0.5;
+
 /// Wrapper around DrawingArea for the minimap
 #[derive(Clone)]
 pub struct MinimapWidget {
@@ -21,8 +120,54 @@ pub struct MinimapWidget {
     config: Rc<RefCell<Config>>,
     window: Rc<RefCell<Option<ApplicationWindow>>>,
     hide_timeout_id: Rc<Cell<Option<glib::SourceId>>>,
-    /// Track the last window ID that triggered a show via focus change
-    last_shown_focus_id: Rc<Cell<Option<u64>>>,
+    /// The focus the minimap was last shown for
+    shown_focus: Rc<Cell<Option<u64>>>,
+    /// Current urgent blink phase (true = urgent windows show `urgent_color`)
+    urgent_blink_on: Rc<Cell<bool>>,
+    /// Blink tick source, only running while urgent windows exist
+    urgent_tick_id: Rc<Cell<Option<glib::SourceId>>>,
+    /// Name of the preset applied on top of the config file, if any
+    active_preset: Rc<RefCell<Option<String>>>,
+    /// Runtime override of `display.workspace_mode` (control socket)
+    workspace_mode_override: Rc<Cell<Option<WorkspaceMode>>>,
+    /// Runtime override of `display.anchor` (`cycle-anchor`)
+    anchor_override: Rc<Cell<Option<Anchor>>>,
+    /// Whether the pointer is over the minimap (interactive mode only)
+    hovered: Rc<Cell<bool>>,
+    /// Whether the minimap is currently hidden by `hide_when_empty`
+    hidden_for_empty: Rc<Cell<bool>>,
+    /// Whether the layer surface is mapped, tracked by its map/unmap signals
+    mapped: Rc<Cell<bool>>,
+    /// Running open/close animations, keyed by window id
+    animations: Rc<RefCell<HashMap<u64, WindowAnimation>>>,
+    /// Animation frame tick, only running while animations are in flight
+    animation_tick_id: Rc<Cell<Option<glib::SourceId>>>,
+    /// Running `workspace_transition_ms` slide, if any
+    workspace_slide: Rc<RefCell<Option<WorkspaceSlide>>>,
+    /// Output the minimap was last moved to by `follow_focus`
+    followed_output: Rc<RefCell<Option<String>>>,
+    /// Whether state updates are being batched (see `batch_updates`)
+    batching: Rc<Cell<bool>>,
+    /// Whether a batched state update still needs a resize and redraw
+    batch_dirty: Rc<Cell<bool>>,
+    /// `draw_fingerprint` of the state after the last update, so updates
+    /// that change nothing visible don't resize or redraw
+    drawn_fingerprint: Rc<Cell<Option<u64>>>,
+    /// Where `get_monitor_caps` last found its monitor (`Some(None)`: the
+    /// 1920x1080 default), so a change of source is logged once
+    caps_source: Rc<Cell<Option<Option<MonitorSource>>>>,
+    /// Clickable regions from the last draw (interactive mode)
+    hit_regions: Rc<RefCell<Vec<HitRegion>>>,
+    /// App icons by app_id (`show_icons`); `None` caches a failed lookup
+    icon_cache: Rc<RefCell<HashMap<String, Option<Pixbuf>>>>,
+    /// Whether Niri's overview is open
+    overview_open: Rc<Cell<bool>>,
+    /// When the minimap was last shown for a workspace switch
+    last_workspace_show: Rc<Cell<Option<Instant>>>,
+    /// Focus changes held back by `focus_hold_ms`
+    focus_hold: Rc<Cell<FocusHold>>,
+    /// Timer applying the held focus change once it has stuck
+    focus_hold_id: Rc<Cell<Option<glib::SourceId>>>,
 }
 
 impl MinimapWidget {
@@ -44,70 +189,449 @@ impl MinimapWidget {
             config,
             window: Rc::new(RefCell::new(None)),
             hide_timeout_id: Rc::new(Cell::new(None)),
-            last_shown_focus_id: Rc::new(Cell::new(None)),
+            shown_focus: Rc::new(Cell::new(None)),
+            urgent_blink_on: Rc::new(Cell::new(true)),
+            urgent_tick_id: Rc::new(Cell::new(None)),
+            active_preset: Rc::new(RefCell::new(None)),
+            workspace_mode_override: Rc::new(Cell::new(None)),
+            anchor_override: Rc::new(Cell::new(None)),
+            hovered: Rc::new(Cell::new(false)),
+            hidden_for_empty: Rc::new(Cell::new(false)),
+            mapped: Rc::new(Cell::new(false)),
+            animations: Rc::new(RefCell::new(HashMap::new())),
+            animation_tick_id: Rc::new(Cell::new(None)),
+            workspace_slide: Rc::new(RefCell::new(None)),
+            followed_output: Rc::new(RefCell::new(None)),
+            batching: Rc::new(Cell::new(false)),
+            batch_dirty: Rc::new(Cell::new(false)),
+            drawn_fingerprint: Rc::new(Cell::new(None)),
+            caps_source: Rc::new(Cell::new(None)),
+            hit_regions: Rc::new(RefCell::new(Vec::new())),
+            icon_cache: Rc::new(RefCell::new(HashMap::new())),
+            overview_open: Rc::new(Cell::new(false)),
+            last_workspace_show: Rc::new(Cell::new(None)),
+            focus_hold: Rc::new(Cell::new(FocusHold::default())),
+            focus_hold_id: Rc::new(Cell::new(None)),
         };
 
         widget.setup_draw_handler();
+        widget.watch_monitors();
+        widget.sync_output_name();
+        if widget.config.borrow().behavior.interactive {
+            widget.setup_hover_tracking();
+            widget.setup_click_to_focus();
+        }
         widget
     }
 
+    /// Keep the minimap shown while the pointer is over it: entering cancels
+    /// the pending auto-hide, leaving re-schedules it. The window under the
+    /// pointer gets a tooltip with its title and app id.
+    ///
+    /// Only installed in interactive mode; click-through surfaces never
+    /// receive pointer events.
+    fn setup_hover_tracking(&self) {
+        let motion = gtk4::EventControllerMotion::new();
+
+        // Only touch the tooltip when the window under the pointer changes
+        let tooltip_window: Rc<Cell<Option<u64>>> = Rc::new(Cell::new(None));
+
+        let widget = self.clone();
+        let hovered_window = tooltip_window.clone();
+        motion.connect_motion(move |_, x, y| {
+            let window_id = match hit_test(&widget.hit_regions.borrow(), x, y) {
+                Some(HitTarget::Window(id)) => Some(id),
+                _ => None,
+            };
+            if hovered_window.replace(window_id) == window_id {
+                return;
+            }
+            let text = window_id.and_then(|id| {
+                widget
+                    .state
+                    .borrow()
+                    .find_window(id)
+                    .and_then(window_tooltip)
+            });
+            widget.drawing_area.set_tooltip_text(text.as_deref());
+        });
+
+        let widget = self.clone();
+        motion.connect_enter(move |_, _, _| {
+            widget.hovered.set(true);
+            widget.cancel_hide_timeout();
+        });
+
+        let widget = self.clone();
+        motion.connect_leave(move |_| {
+            widget.hovered.set(false);
+            tooltip_window.set(None);
+            widget.drawing_area.set_tooltip_text(None);
+            if widget.auto_hides() {
+                widget.schedule_hide();
+            }
+        });
+
+        self.drawing_area.add_controller(motion);
+    }
+
+    /// Focus the window (or, in `all` mode, the workspace row) under a
+    /// primary click. Only installed in interactive mode.
+    fn setup_click_to_focus(&self) {
+        let click = gtk4::GestureClick::new();
+        click.set_button(gtk4::gdk::BUTTON_PRIMARY);
+
+        let hit_regions = self.hit_regions.clone();
+        click.connect_released(move |_, _, x, y| {
+            let Some(target) = hit_test(&hit_regions.borrow(), x, y) else {
+                return;
+            };
+            // Niri IPC is a blocking round trip; keep it off the UI thread
+            std::thread::spawn(move || {
+                let result = NiriClient::connect().and_then(|mut client| match target {
+                    HitTarget::Window(id) => client.focus_window(id),
+                    HitTarget::Workspace(id) => client.focus_workspace(id),
+                });
+                if let Err(e) = result {
+                    tracing::warn!("Click-to-focus failed: {:#}", e);
+                }
+            });
+        });
+
+        self.drawing_area.add_controller(click);
+    }
+
+    /// Resize and redraw when a monitor is plugged in or out, or changes its
+    /// resolution or scale (e.g. docking a laptop), and when the minimap
+    /// moves to an output with another scale
+    fn watch_monitors(&self) {
+        let widget = self.clone();
+        self.drawing_area
+            .connect_scale_factor_notify(move |_| widget.on_monitors_changed());
+
+        let Some(display) = gdk::Display::default() else {
+            return;
+        };
+        let monitors = display.monitors();
+        for position in 0..monitors.n_items() {
+            self.watch_monitor(monitors.item(position));
+        }
+        let widget = self.clone();
+        monitors.connect_items_changed(move |monitors, position, _removed, added| {
+            for position in position..position + added {
+                widget.watch_monitor(monitors.item(position));
+            }
+            widget.on_monitors_changed();
+        });
+    }
+
+    /// Watch one monitor (an item of the display's monitor list) for
+    /// resolution and scale changes
+    fn watch_monitor(&self, monitor: Option<glib::Object>) {
+        let Some(monitor) = monitor.and_downcast::<gdk::Monitor>() else {
+            return;
+        };
+        let widget = self.clone();
+        monitor.connect_geometry_notify(move |_| widget.on_monitors_changed());
+        let widget = self.clone();
+        monitor.connect_scale_factor_notify(move |_| widget.on_monitors_changed());
+    }
+
+    /// Recompute everything derived from monitor sizes and redraw
+    fn on_monitors_changed(&self) {
+        tracing::debug!("Monitors changed, resizing the minimap");
+        // Fullscreen windows are recognized by their output's size
+        self.update_state(|_| {});
+        self.update_size();
+        self.drawing_area.queue_draw();
+    }
+
     /// Set the parent window (needed for dynamic resizing and visibility)
     pub fn set_window(&self, window: ApplicationWindow) {
         // Set initial visibility based on config
         if !self.config.borrow().behavior.always_visible {
             window.set_visible(false);
         }
+        let mapped = self.mapped.clone();
+        window.connect_map(move |_| mapped.set(true));
+        let mapped = self.mapped.clone();
+        window.connect_unmap(move |window| {
+            mapped.set(false);
+            if window.is_visible() {
+                tracing::debug!("Minimap surface unmapped while visible");
+            }
+        });
         *self.window.borrow_mut() = Some(window);
     }
 
     /// Show the minimap (with auto-hide timeout if configured)
     pub fn show(&self) {
+        if self.should_hide_for_empty() || self.hidden_for_overview() {
+            return;
+        }
+
         if let Some(window) = self.window.borrow().as_ref() {
-            window.set_visible(true);
+            if window.is_visible() && !self.mapped.get() {
+                // The compositor unmapped the surface behind our back, which
+                // set_visible(true) can't undo. Presenting a layer surface
+                // doesn't take focus: its keyboard mode is left as configured
+                // (`none` by default).
+                tracing::debug!("Minimap surface is unmapped, presenting it again");
+                window.present();
+            } else {
+                window.set_visible(true);
+            }
+            window.set_opacity(self.shown_opacity());
         }
 
-        // If not always visible, schedule hide after timeout
-        if !self.config.borrow().behavior.always_visible {
+        // If not always visible (or dimming when idle), schedule hide after
+        // timeout. Sticky workspaces keep it up.
+        if self.auto_hides() {
+            self.schedule_hide();
+        } else {
+            self.cancel_hide_timeout();
+        }
+    }
+
+    /// Opacity of the shown minimap: `overview_opacity` while the overview
+    /// is open, otherwise fully opaque
+    fn shown_opacity(&self) -> f64 {
+        if self.overview_open.get() {
+            self.config
+                .borrow()
+                .behavior
+                .overview_opacity
+                .clamp(0.0, 1.0)
+        } else {
+            1.0
+        }
+    }
+
+    /// Whether `show_on_overview = false` keeps the minimap hidden right now
+    fn hidden_for_overview(&self) -> bool {
+        self.overview_open.get() && !self.config.borrow().behavior.show_on_overview
+    }
+
+    /// Track Niri's overview: hide the minimap for it (`show_on_overview =
+    /// false`) or fade it to `overview_opacity`, and undo that on close.
+    pub fn set_overview_open(&self, is_open: bool) {
+        if self.overview_open.replace(is_open) == is_open {
+            return;
+        }
+        if self.hidden_for_overview() {
+            self.hide();
+            return;
+        }
+
+        let visible = self
+            .window
+            .borrow()
+            .as_ref()
+            .is_some_and(|window| window.is_visible());
+        if visible || self.config.borrow().behavior.always_visible {
+            self.show();
+        }
+        if self.config.borrow().behavior.overview_all_workspaces {
+            self.update_size();
+            self.drawing_area.queue_draw();
+        }
+    }
+
+    /// Opacity the idle timeout dims to, if `idle_dim_opacity` is enabled
+    fn idle_dim_opacity(&self) -> Option<f64> {
+        let opacity = self.config.borrow().behavior.idle_dim_opacity;
+        (opacity < 1.0).then(|| opacity.max(0.0))
+    }
+
+    /// Whether the hide timeout runs at all: to hide, or to dim when idle.
+    /// Never on a `sticky_workspaces` workspace.
+    fn auto_hides(&self) -> bool {
+        (!self.config.borrow().behavior.always_visible || self.idle_dim_opacity().is_some())
+            && !self.on_sticky_workspace()
+    }
+
+    /// Whether the active workspace is listed in `behavior.sticky_workspaces`
+    fn on_sticky_workspace(&self) -> bool {
+        let config = self.config.borrow();
+        let sticky = &config.behavior.sticky_workspaces;
+        !sticky.is_empty()
+            && self
+                .state
+                .borrow()
+                .active_workspace()
+                .is_some_and(|ws| sticky.iter().any(|reference| ws.matches(reference)))
+    }
+
+    /// Apply `sticky_workspaces` after the active workspace changed: bring
+    /// the minimap up on a sticky workspace, and restart the hide timeout
+    /// when leaving one.
+    pub fn sync_sticky_workspace(&self) {
+        if self.on_sticky_workspace() {
+            self.show();
+            return;
+        }
+        let shown =
+            self.window.borrow().as_ref().is_some_and(|window| {
+                window.is_visible() && window.opacity() >= self.shown_opacity()
+            });
+        if shown && self.auto_hides() {
             self.schedule_hide();
         }
     }
 
+    /// Move focus to `window_id`, following it and showing the minimap.
+    ///
+    /// Dragging a window makes Niri bounce focus between the dragged window
+    /// and its neighbours. A change arriving within `behavior.focus_hold_ms`
+    /// of the last one is held back and only applied if no other change
+    /// replaces it before the hold runs out.
+    pub fn change_focus(&self, window_id: Option<u64>) {
+        let hold = Duration::from_millis(self.config.borrow().behavior.focus_hold_ms as u64);
+        let mut focus_hold = self.focus_hold.get();
+        let applied = focus_hold.offer(window_id, Instant::now(), hold);
+        self.focus_hold.set(focus_hold);
+
+        match applied {
+            Some(window_id) => self.apply_focus_change(window_id),
+            None => {
+                tracing::debug!("Focus change to {:?} held", window_id);
+                self.schedule_focus_settle(hold);
+            }
+        }
+    }
+
+    /// Apply the held focus change after `hold`, unless already scheduled
+    fn schedule_focus_settle(&self, hold: Duration) {
+        if let Some(source_id) = self.focus_hold_id.take() {
+            self.focus_hold_id.set(Some(source_id));
+            return;
+        }
+        let widget = self.clone();
+        let source_id = glib::timeout_add_local_once(hold, move || {
+            widget.focus_hold_id.set(None);
+            let mut focus_hold = widget.focus_hold.get();
+            let settled = focus_hold.settle(Instant::now());
+            widget.focus_hold.set(focus_hold);
+            if let Some(window_id) = settled {
+                widget.apply_focus_change(window_id);
+            }
+        });
+        self.focus_hold_id.set(Some(source_id));
+    }
+
+    /// Focus `window_id` right away
+    fn apply_focus_change(&self, window_id: Option<u64>) {
+        self.update_state(|state| {
+            state.set_focused_window(window_id);
+        });
+        self.follow_focus();
+        // Show the minimap only if focus changed to a different window
+        self.show_on_focus_change(window_id);
+    }
+
     /// Show the minimap only if focus changed to a different window.
     /// Returns true if the minimap was shown.
     ///
     /// When `behavior.show_for_floating_windows` is false (the default), focus
     /// changes involving a floating window are suppressed *and* do not advance
-    /// `last_shown_focus_id`. This means returning focus from a popup back to
+    /// the last shown focus. This means returning focus from a popup back to
     /// the previously-focused tile won't re-trigger a show — the prior tile
     /// is still recorded as the last shown id.
     pub fn show_on_focus_change(&self, window_id: Option<u64>) -> bool {
-        let last_id = self.last_shown_focus_id.get();
-
-        if window_id == last_id {
+        let suppressed = !self.config.borrow().behavior.show_for_floating_windows
+            && window_id
+                .and_then(|id| self.state.borrow().find_window(id).map(|w| w.is_floating))
+                .unwrap_or(false);
+        let (show, shown_focus) = focus_show(
+            self.shown_focus.get(),
+            FocusShowEvent::FocusChange(window_id),
+            suppressed,
+        );
+        if !show || !self.show_for(ShowTrigger::FocusChange) {
             return false;
         }
+        self.shown_focus.set(shown_focus);
+        true
+    }
 
-        if !self.config.borrow().behavior.show_for_floating_windows {
-            let is_floating = window_id
-                .and_then(|id| self.state.borrow().find_window(id).map(|w| w.is_floating))
-                .unwrap_or(false);
-            if is_floating {
-                return false;
-            }
+    /// Show the minimap for a newly-spawned window, respecting the
+    /// `show_for_floating_windows` opt-out.
+    ///
+    /// A window that opens focused is followed by a focus change to it; that
+    /// is the same logical event and doesn't show the minimap again.
+    pub fn show_for_new_window(&self, window_id: u64, is_floating: bool, is_focused: bool) {
+        let suppressed = is_floating && !self.config.borrow().behavior.show_for_floating_windows;
+        let (show, shown_focus) = focus_show(
+            self.shown_focus.get(),
+            FocusShowEvent::NewWindow {
+                window_id,
+                is_focused,
+            },
+            suppressed,
+        );
+        if show && self.show_for(ShowTrigger::NewWindow) {
+            self.shown_focus.set(shown_focus);
         }
+    }
 
-        self.last_shown_focus_id.set(window_id);
+    /// Show the minimap for an event, if `behavior.show_triggers` includes
+    /// it. Every event-driven show goes through here. Returns true if the
+    /// minimap was shown.
+    pub fn show_for(&self, trigger: ShowTrigger) -> bool {
+        if !self
+            .config
+            .borrow()
+            .behavior
+            .show_triggers
+            .contains(&trigger)
+        {
+            return false;
+        }
         self.show();
         true
     }
 
-    /// Show the minimap for a newly-spawned window, respecting the
-    /// `show_for_floating_windows` opt-out.
-    pub fn show_for_new_window(&self, is_floating: bool) {
-        if is_floating && !self.config.borrow().behavior.show_for_floating_windows {
+    /// Show for a workspace switch, at most once per
+    /// `workspace_switch_cooldown_ms`. Holding a switch keybind fires many
+    /// activations; the state follows each one, but re-showing on every
+    /// event makes the minimap flicker.
+    pub fn show_for_workspace_change(&self) {
+        let cooldown = Duration::from_millis(
+            self.config.borrow().behavior.workspace_switch_cooldown_ms as u64,
+        );
+        let now = Instant::now();
+        if !cooldown_elapsed(self.last_workspace_show.get(), now, cooldown) {
+            tracing::debug!("Workspace switch show skipped (cooldown)");
             return;
         }
-        self.show();
+        if self.show_for(ShowTrigger::WorkspaceChange) {
+            self.last_workspace_show.set(Some(now));
+        }
+    }
+
+    /// With `display.follow_focus`, move the minimap to the output holding
+    /// the focused window (or the active workspace). The configured anchor
+    /// corner is kept on the new output.
+    pub fn follow_focus(&self) {
+        if !self.config.borrow().display.follow_focus {
+            return;
+        }
+        let Some(output) = self.state.borrow().focused_output().map(str::to_string) else {
+            return;
+        };
+        if self.followed_output.borrow().as_deref() == Some(output.as_str()) {
+            return;
+        }
+
+        if let Some(window) = self.window.borrow().as_ref() {
+            if move_to_output(window, &output) {
+                tracing::debug!("Following focus to output {}", output);
+                *self.followed_output.borrow_mut() = Some(output);
+                self.sync_output_name();
+            } else {
+                tracing::warn!("No monitor found for output {}", output);
+            }
+        }
     }
 
     /// Hide the minimap
@@ -120,12 +644,40 @@ impl MinimapWidget {
         }
     }
 
-    /// Schedule hiding the minimap after the configured timeout
+    /// Whether `hide_when_empty` currently requires the minimap to be hidden
+    fn should_hide_for_empty(&self) -> bool {
+        self.config.borrow().behavior.hide_when_empty
+            && !self.state.borrow().active_workspace_has_tiled_windows()
+    }
+
+    /// Hide the minimap when the active workspace becomes empty, and bring it
+    /// back when windows appear again. Without `always_visible` the next
+    /// event shows it as usual.
+    fn sync_empty_visibility(&self) {
+        if self.should_hide_for_empty() {
+            if !self.hidden_for_empty.replace(true) {
+                self.hide();
+            }
+        } else if self.hidden_for_empty.replace(false)
+            && self.config.borrow().behavior.always_visible
+        {
+            self.show();
+        }
+    }
+
+    /// Schedule hiding the minimap after the configured timeout, or dimming
+    /// it with `idle_dim_opacity`
     fn schedule_hide(&self) {
         // Cancel any existing timeout
         self.cancel_hide_timeout();
 
+        // Never hide from under the pointer; leaving re-schedules
+        if self.hovered.get() {
+            return;
+        }
+
         let timeout_ms = self.config.borrow().behavior.hide_timeout_ms;
+        let dim_opacity = self.idle_dim_opacity();
         let window = self.window.clone();
         let timeout_id_cell = self.hide_timeout_id.clone();
 
@@ -133,7 +685,10 @@ impl MinimapWidget {
             std::time::Duration::from_millis(timeout_ms as u64),
             move || {
                 if let Some(win) = window.borrow().as_ref() {
-                    win.set_visible(false);
+                    match dim_opacity {
+                        Some(opacity) => win.set_opacity(opacity),
+                        None => win.set_visible(false),
+                    }
                 }
                 timeout_id_cell.set(None);
             },
@@ -149,17 +704,23 @@ impl MinimapWidget {
         }
     }
 
-    /// Reload the configuration from disk
+    /// Reload the configuration from disk, re-applying the active preset
     pub fn reload_config(&self) {
         match Config::load() {
             Ok(new_config) => {
-                // Update the config
-                *self.config.borrow_mut() = new_config;
-
-                // Trigger resize and redraw
-                self.update_size();
-                self.drawing_area.queue_draw();
-
+                let active_preset = self.active_preset.borrow().clone();
+                let new_config = match active_preset {
+                    Some(name) => match new_config.with_preset(&name) {
+                        Ok(config) => config,
+                        Err(e) => {
+                            tracing::warn!("Dropping preset '{}': {:#}", name, e);
+                            *self.active_preset.borrow_mut() = None;
+                            new_config
+                        }
+                    },
+                    None => new_config,
+                };
+                self.apply_config(new_config);
                 tracing::info!("Configuration reloaded");
             }
             Err(e) => {
@@ -168,6 +729,104 @@ impl MinimapWidget {
         }
     }
 
+    /// Apply a named preset on top of the config file
+    pub fn apply_preset(&self, name: &str) -> anyhow::Result<()> {
+        let config = Config::load()?.with_preset(name)?;
+        self.apply_config(config);
+        *self.active_preset.borrow_mut() = Some(name.to_string());
+        tracing::info!("Applied preset '{}'", name);
+        Ok(())
+    }
+
+    /// Drop the active preset and return to the config file's settings
+    pub fn clear_preset(&self) -> anyhow::Result<()> {
+        let config = Config::load()?;
+        self.apply_config(config);
+        *self.active_preset.borrow_mut() = None;
+        tracing::info!("Cleared preset");
+        Ok(())
+    }
+
+    /// The workspace mode currently in effect
+    pub fn workspace_mode(&self) -> WorkspaceMode {
+        self.config.borrow().display.workspace_mode
+    }
+
+    /// Override `display.workspace_mode` at runtime. The override survives
+    /// config reloads and preset changes; `None` returns to the config's mode.
+    pub fn set_workspace_mode_override(&self, mode: Option<WorkspaceMode>) {
+        self.workspace_mode_override.set(mode);
+        match mode {
+            Some(_) => {
+                let config = self.config.borrow().clone();
+                self.apply_config(config);
+            }
+            None => self.reload_config(),
+        }
+    }
+
+    /// Move the minimap to the anchor after the current one. The change
+    /// survives config reloads; with `persist` it's written to the config
+    /// file instead. Returns the new anchor.
+    pub fn cycle_anchor(&self, persist: bool) -> anyhow::Result<Anchor> {
+        let anchor = self.config.borrow().display.anchor.next();
+        if persist {
+            Config::persist_anchor(anchor)?;
+            self.anchor_override.set(None);
+        } else {
+            self.anchor_override.set(Some(anchor));
+        }
+        let mut config = self.config.borrow().clone();
+        config.display.anchor = anchor;
+        self.apply_config(config);
+        Ok(anchor)
+    }
+
+    /// Swap in a new config and refresh everything derived from it
+    fn apply_config(&self, mut new_config: Config) {
+        if let Some(mode) = self.workspace_mode_override.get() {
+            new_config.display.workspace_mode = mode;
+        }
+        if let Some(anchor) = self.anchor_override.get() {
+            new_config.display.anchor = anchor;
+        }
+        *self.config.borrow_mut() = new_config;
+        self.sync_output_name();
+
+        // Anchor and margins may have changed
+        if let Some(window) = self.window.borrow().as_ref() {
+            anchor_window(window, &self.config.borrow());
+        }
+
+        // Don't leave the minimap dimmed once dimming is turned off
+        if self.idle_dim_opacity().is_none() {
+            if let Some(window) = self.window.borrow().as_ref() {
+                window.set_opacity(1.0);
+            }
+        }
+
+        // Restart the blink tick so a changed interval takes effect
+        if let Some(source_id) = self.urgent_tick_id.take() {
+            source_id.remove();
+        }
+        self.sync_urgent_blink();
+        self.sync_empty_visibility();
+
+        // Trigger resize and redraw
+        self.update_size();
+        self.drawing_area.queue_draw();
+    }
+
+    /// Record the output the minimap is on in the state: the one
+    /// `follow_focus` moved it to, else `display.output`.
+    fn sync_output_name(&self) {
+        let output = self.followed_output.borrow().clone().or_else(|| {
+            let output = &self.config.borrow().display.output;
+            (!output.is_empty()).then(|| output.clone())
+        });
+        self.state.borrow_mut().output_name = output;
+    }
+
     /// Get the underlying DrawingArea widget
     pub fn widget(&self) -> &DrawingArea {
         &self.drawing_area
@@ -178,96 +837,826 @@ impl MinimapWidget {
     where
         F: FnOnce(&mut MinimapState),
     {
-        f(&mut self.state.borrow_mut());
-        self.update_size();
-        self.drawing_area.queue_draw();
+        let fingerprint = {
+            let mut state = self.state.borrow_mut();
+            f(&mut state);
+            let behavior = &self.config.borrow().behavior;
+            if !behavior.ignore_app_ids.is_empty() {
+                state.remove_windows_where(|w| behavior.ignores_app_id(w.app_id.as_deref()));
+            }
+            state.prune_workspaces();
+            state.infer_hidden_windows();
+            state.infer_column_active_windows();
+            state.infer_layout_gap();
+            let own_monitor = self.monitor();
+            state.infer_fullscreen_windows(|output| {
+                let monitor = output
+                    .and_then(monitor_for_output)
+                    .or_else(|| own_monitor.clone())?;
+                let geometry = monitor.geometry();
+                Some((geometry.width() as f64, geometry.height() as f64))
+            });
+            draw_fingerprint(&state, &self.config.borrow().appearance)
+        };
+        // Nothing visible changed, e.g. only a title
+        if self.drawn_fingerprint.replace(Some(fingerprint)) == Some(fingerprint) {
+            return;
+        }
+        if self.batching.get() {
+            self.batch_dirty.set(true);
+            return;
+        }
+        self.refresh();
     }
 
-    /// Calculate and update the widget/window size based on current state
-    fn update_size(&self) {
-        let state = self.state.borrow();
-        let config = self.config.borrow();
+    /// Whether `window` is left out by `behavior.ignore_app_ids`
+    pub fn ignores_window(&self, window: &Window) -> bool {
+        self.config
+            .borrow()
+            .behavior
+            .ignores_app_id(window.app_id.as_deref())
+    }
 
-        let (max_width, max_height) = self.get_monitor_caps();
-        let viewport_width = monitor_logical_width();
-        let dims = compute_widget_dimensions(
-            &state,
-            &config.display,
-            config.appearance.workspace_gap,
-            max_width,
-            max_height,
-            viewport_width,
-        );
+    /// Read the current state
+    pub fn with_state<R>(&self, f: impl FnOnce(&MinimapState) -> R) -> R {
+        f(&self.state.borrow())
+    }
 
-        let final_width = dims.width.ceil() as i32;
-        let final_height = dims.height.ceil() as i32;
+    /// Apply several state updates as one: inside `f`, `update_state` only
+    /// mutates the state, and the resize and redraw happen once at the end.
+    pub fn batch_updates<F>(&self, f: F)
+    where
+        F: FnOnce(),
+    {
+        let was_batching = self.batching.replace(true);
+        f();
+        self.batching.set(was_batching);
 
-        self.drawing_area.set_content_width(final_width);
-        self.drawing_area.set_content_height(final_height);
-        if let Some(window) = self.window.borrow().as_ref() {
-            window.set_default_width(final_width);
-            window.set_default_height(final_height);
+        if !was_batching && self.batch_dirty.replace(false) {
+            self.refresh();
         }
     }
 
-    /// Get monitor-based caps for widget width and height.
-    fn get_monitor_caps(&self) -> (f64, f64) {
-        let display_cfg = &self.config.borrow().display;
-        let max_width_percent = display_cfg.max_width_percent;
-        let max_height_percent = display_cfg.max_height_percent;
-
-        if let Some(display) = gtk4::gdk::Display::default() {
-            if let Some(monitor) = display.monitors().item(0) {
-                if let Some(monitor) = monitor.downcast_ref::<gtk4::gdk::Monitor>() {
-                    let geometry = monitor.geometry();
-                    let w = geometry.width() as f64 * max_width_percent;
-                    let h = geometry.height() as f64 * max_height_percent;
-                    return (w, h);
-                }
+    /// Re-sync derived widget state after a state change and queue a redraw
+    fn refresh(&self) {
+        self.sync_urgent_blink();
+        self.sync_empty_visibility();
+        self.update_size();
+        self.drawing_area.queue_draw();
+    }
+
+    /// Start or stop the urgent blink tick to match the current state.
+    ///
+    /// The tick only runs while `urgent_blink` is enabled and at least one
+    /// window is urgent; it stops itself once urgency clears.
+    fn sync_urgent_blink(&self) {
+        let (enabled, interval_ms) = {
+            let cfg = self.config.borrow();
+            (cfg.appearance.urgent_blink, cfg.appearance.urgent_blink_ms)
+        };
+
+        if !enabled || !self.state.borrow().has_urgent_windows() {
+            if let Some(source_id) = self.urgent_tick_id.take() {
+                source_id.remove();
             }
+            self.urgent_blink_on.set(true);
+            return;
         }
 
-        // Fallback: use a reasonable default (1920x1080 baseline)
-        (1920.0 * max_width_percent, 1080.0 * max_height_percent)
-    }
+        // Already ticking
+        if let Some(source_id) = self.urgent_tick_id.take() {
+            self.urgent_tick_id.set(Some(source_id));
+            return;
+        }
 
-    /// Set up the draw handler
-    fn setup_draw_handler(&self) {
         let state = self.state.clone();
-        let config = self.config.clone();
+        let blink_on = self.urgent_blink_on.clone();
+        let tick_id = self.urgent_tick_id.clone();
+        let drawing_area = self.drawing_area.clone();
 
-        self.drawing_area
-            .set_draw_func(move |_area, cr, width, height| {
-                let cfg = config.borrow();
-                let viewport_width = monitor_logical_width();
-                draw_minimap(
-                    cr,
-                    width,
-                    height,
-                    &state.borrow(),
-                    &cfg.display,
-                    &cfg.appearance,
-                    viewport_width,
-                );
-            });
+        let source_id = glib::timeout_add_local(
+            std::time::Duration::from_millis(interval_ms.max(50) as u64),
+            move || {
+                if !state.borrow().has_urgent_windows() {
+                    blink_on.set(true);
+                    tick_id.set(None);
+                    drawing_area.queue_draw();
+                    return glib::ControlFlow::Break;
+                }
+                blink_on.set(!blink_on.get());
+                drawing_area.queue_draw();
+                glib::ControlFlow::Continue
+            },
+        );
+
+        self.urgent_tick_id.set(Some(source_id));
     }
-}
 
-/// Monitor's logical width — used as the workspace viewport width.
-///
-/// Niri's per-workspace viewport equals its output's logical width. We don't
-/// query niri-ipc for output info today, so we use the GTK display's monitor
-/// geometry, which matches for the single-output case (the only setup nirimap
-/// currently supports — see "Known limitations" in the README).
-fn monitor_logical_width() -> f64 {
-    if let Some(display) = gtk4::gdk::Display::default() {
-        if let Some(monitor) = display.monitors().item(0) {
-            if let Some(monitor) = monitor.downcast_ref::<gtk4::gdk::Monitor>() {
-                return monitor.geometry().width() as f64;
-            }
+    /// Scale/fade a newly opened window in. No-op unless `window_animations`
+    /// is enabled.
+    pub fn animate_window_open(&self, window_id: u64) {
+        if !self.config.borrow().appearance.window_animations {
+            return;
         }
+        self.animations.borrow_mut().insert(
+            window_id,
+            WindowAnimation::new(AnimationKind::Open, Instant::now()),
+        );
+        self.ensure_animation_tick();
+    }
+
+    /// Remove a closed window. With `window_animations` it fades out first
+    /// and is only removed from the state once the fade completes.
+    pub fn close_window(&self, window_id: u64) {
+        let animate = self.config.borrow().appearance.window_animations
+            && self.state.borrow().find_window(window_id).is_some();
+        if !animate {
+            self.animations.borrow_mut().remove(&window_id);
+            self.update_state(|state| state.remove_window(window_id));
+            return;
+        }
+
+        self.animations.borrow_mut().insert(
+            window_id,
+            WindowAnimation::new(AnimationKind::Close, Instant::now()),
+        );
+        self.ensure_animation_tick();
+    }
+
+    /// With `workspace_transition_ms`, slide from the active workspace to
+    /// `workspace_id`, which is about to be activated. Only `current` mode
+    /// animates; `all` mode already shows every workspace.
+    pub fn begin_workspace_transition(&self, workspace_id: u64) {
+        let duration_ms = self.config.borrow().appearance.workspace_transition_ms;
+        if duration_ms == 0 || self.workspace_mode() != WorkspaceMode::Current {
+            return;
+        }
+        let Some(from) = self
+            .state
+            .borrow()
+            .active_workspace()
+            .filter(|ws| ws.id != workspace_id)
+            .cloned()
+        else {
+            return;
+        };
+
+        *self.workspace_slide.borrow_mut() = Some(WorkspaceSlide::new(
+            from,
+            workspace_id,
+            Instant::now(),
+            Duration::from_millis(duration_ms as u64),
+        ));
+        self.ensure_animation_tick();
+    }
+
+    /// Start the animation frame tick if it isn't running. Each frame drops
+    /// finished animations (window fades and the workspace slide), removes
+    /// windows whose fade-out completed and redraws; the tick stops once
+    /// nothing is left to animate.
+    fn ensure_animation_tick(&self) {
+        // Already ticking
+        if let Some(source_id) = self.animation_tick_id.take() {
+            self.animation_tick_id.set(Some(source_id));
+            return;
+        }
+
+        let widget = self.clone();
+        let source_id = glib::timeout_add_local(ANIMATION_FRAME_INTERVAL, move || {
+            let now = Instant::now();
+            let closed: Vec<u64> = {
+                let mut animations = widget.animations.borrow_mut();
+                let closed = animations
+                    .iter()
+                    .filter(|(_, a)| a.kind == AnimationKind::Close && a.is_finished(now))
+                    .map(|(&id, _)| id)
+                    .collect();
+                animations.retain(|_, a| !a.is_finished(now));
+                closed
+            };
+            let slide_finished = widget
+                .workspace_slide
+                .borrow()
+                .as_ref()
+                .is_some_and(|slide| slide.is_finished(now));
+            if slide_finished {
+                *widget.workspace_slide.borrow_mut() = None;
+            }
+
+            if closed.is_empty() {
+                widget.drawing_area.queue_draw();
+            } else {
+                widget.update_state(|state| {
+                    for id in closed {
+                        state.remove_window(id);
+                    }
+                });
+            }
+
+            if widget.animations.borrow().is_empty() && widget.workspace_slide.borrow().is_none() {
+                widget.animation_tick_id.set(None);
+                return glib::ControlFlow::Break;
+            }
+            glib::ControlFlow::Continue
+        });
+
+        self.animation_tick_id.set(Some(source_id));
+    }
+
+    /// Calculate and update the widget/window size based on current state
+    fn update_size(&self) {
+        let state = self.state.borrow();
+        let config = self.config.borrow();
+        let config = overview_config(&config, self.overview_open.get());
+
+        let (max_width, max_height) = self.get_monitor_caps();
+        let viewport_width = monitor_logical_width(self.monitor().as_ref());
+        let mut dims = compute_widget_dimensions(
+            &state,
+            &config.display,
+            &config.appearance,
+            &config.behavior,
+            max_width,
+            max_height,
+            viewport_width,
+        );
+        if config.display.match_monitor_aspect {
+            dims = match_aspect(dims, monitor_aspect(self.monitor().as_ref()), max_width);
+        }
+
+        let (final_width, final_height) =
+            widget_pixel_size(&dims, config.display.height as f64, max_width);
+
+        self.drawing_area.set_content_width(final_width);
+        self.drawing_area.set_content_height(final_height);
+        if let Some(window) = self.window.borrow().as_ref() {
+            window.set_default_width(final_width);
+            window.set_default_height(final_height);
+        }
+    }
+
+    /// The monitor the minimap is shown on, see `minimap_monitor`
+    fn monitor(&self) -> Option<gtk4::gdk::Monitor> {
+        self.locate_monitor().map(|(monitor, _)| monitor)
+    }
+
+    /// The monitor the minimap is shown on, and how it was found
+    fn locate_monitor(&self) -> Option<(gtk4::gdk::Monitor, MonitorSource)> {
+        let surface = self
+            .window
+            .borrow()
+            .as_ref()
+            .and_then(|window| window.surface());
+        minimap_monitor(
+            &self.config.borrow().display,
+            self.followed_output.borrow().as_deref(),
+            surface.as_ref(),
+        )
+    }
+
+    /// Get monitor-based caps for widget width and height.
+    fn get_monitor_caps(&self) -> (f64, f64) {
+        let (max_width_percent, max_height_percent) = {
+            let display_cfg = &self.config.borrow().display;
+            (
+                display_cfg.max_width_percent,
+                display_cfg.max_height_percent,
+            )
+        };
+
+        let located = self.locate_monitor();
+        let source = located.as_ref().map(|(_, source)| *source);
+        // Log only when the source changes, not on every resize
+        if self.caps_source.replace(Some(source)) != Some(source) {
+            match source {
+                Some(source) => {
+                    tracing::debug!("Sizing the minimap by the {}", source.description())
+                }
+                None => tracing::debug!("No monitor found, sizing the minimap for 1920x1080"),
+            }
+        }
+        if let Some((monitor, _)) = located {
+            let geometry = monitor.geometry();
+            let w = geometry.width() as f64 * max_width_percent;
+            let h = geometry.height() as f64 * max_height_percent;
+            return (w, h);
+        }
+
+        // Last resort: use a reasonable default (1920x1080 baseline)
+        (1920.0 * max_width_percent, 1080.0 * max_height_percent)
+    }
+
+    /// Set up the draw handler
+    fn setup_draw_handler(&self) {
+        let widget = self.clone();
+        self.drawing_area
+            .set_draw_func(move |_area, cr, width, height| {
+                metrics::increment(&METRICS.redraws);
+                *widget.hit_regions.borrow_mut() = widget.render(cr, width, height);
+            });
+    }
+
+    /// Draw the current state into `cr` at `width` x `height`, returning
+    /// the clickable regions drawn
+    fn render(&self, cr: &Context, width: i32, height: i32) -> Vec<HitRegion> {
+        let cfg = self.config.borrow();
+        let cfg = overview_config(&cfg, self.overview_open.get());
+        let state = self.state.borrow();
+        let viewport_width = monitor_logical_width(self.monitor().as_ref());
+        let now = Instant::now();
+        let effects = DrawEffects {
+            urgent_blink_on: self.urgent_blink_on.get(),
+            window_visibility: self
+                .animations
+                .borrow()
+                .iter()
+                .map(|(&id, animation)| (id, animation.visibility(now)))
+                .collect(),
+            hit_regions: RefCell::new(Vec::new()),
+            icons: if cfg.appearance.show_icons {
+                window_icons(&state, &mut self.icon_cache.borrow_mut())
+            } else {
+                HashMap::new()
+            },
+            focus_ranks: cfg.appearance.recency_fade.then(|| state.focus_ranks()),
+            floating_column: (cfg.appearance.floating_indicator == FloatingIndicator::Column)
+                .then(|| state.floating_focus_column())
+                .flatten(),
+            workspace_slide: self
+                .workspace_slide
+                .borrow()
+                .as_ref()
+                .map(|slide| (slide.from.clone(), slide.offsets(now))),
+            dim_inactive_workspaces: self.overview_open.get()
+                && cfg.behavior.overview_all_workspaces,
+            contrast_background: (cfg.appearance.enforce_contrast
+                && cfg.appearance.background_opacity > 0.0)
+                .then(|| {
+                    Color::from_hex(workspace_background(
+                        &cfg.appearance,
+                        state.active_workspace(),
+                    ))
+                })
+                .flatten(),
+        };
+        draw_minimap(cr, width, height, &state, &cfg, viewport_width, &effects);
+        let floating_focused = state
+            .focused_window_id
+            .and_then(|id| state.find_window(id))
+            .is_some_and(|w| w.is_floating);
+        if floating_focused && cfg.appearance.floating_indicator == FloatingIndicator::Glyph {
+            draw_floating_glyph(cr, width as f64, &cfg.appearance);
+        }
+        if cfg.appearance.floating_strip {
+            draw_floating_strip(
+                cr,
+                width as f64,
+                height as f64,
+                &floating_strip_windows(&state),
+                &cfg.appearance,
+                &effects,
+            );
+        }
+        if cfg.appearance.show_workspace_number {
+            if let Some(workspace) = state.active_workspace() {
+                draw_workspace_number(cr, width as f64, height as f64, workspace, &cfg.appearance);
+            }
+        }
+        effects.hit_regions.into_inner()
+    }
+
+    /// Runtime counters in Prometheus text format, if `metrics_enable` is set
+    pub fn metrics(&self) -> anyhow::Result<String> {
+        if !self.config.borrow().behavior.metrics_enable {
+            anyhow::bail!("metrics are disabled (set behavior.metrics_enable = true)");
+        }
+        let state = self.state.borrow();
+        Ok(METRICS.render(state.window_count(), state.workspaces.len()))
+    }
+
+    /// Render the minimap at its current size into a PNG file, for bug
+    /// reports and documentation
+    pub fn snapshot(&self, path: &Path) -> anyhow::Result<()> {
+        let width = self.drawing_area.content_width().max(1);
+        let height = self.drawing_area.content_height().max(1);
+        let mut surface = ImageSurface::create(Format::ARgb32, width, height)?;
+        {
+            let cr = Context::new(&surface)?;
+            self.render(&cr, width, height);
+        }
+        surface.flush();
+
+        // Cairo's ARGB32 is premultiplied BGRA in memory on little-endian
+        let stride = surface.stride() as usize;
+        let data = surface.data()?.to_vec();
+        let texture = gdk::MemoryTexture::new(
+            width,
+            height,
+            gdk::MemoryFormat::B8g8r8a8Premultiplied,
+            &glib::Bytes::from_owned(data),
+            stride,
+        );
+        texture
+            .save_to_png(path)
+            .with_context(|| format!("Failed to write snapshot: {}", path.display()))?;
+        tracing::info!("Wrote {}x{} snapshot to {}", width, height, path.display());
+        Ok(())
+    }
+}
+
+/// An event that may show the minimap for the focused window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FocusShowEvent {
+    /// A window opened, possibly focused
+    NewWindow { window_id: u64, is_focused: bool },
+    /// Focus moved to a window, or to none
+    FocusChange(Option<u64>),
+}
+
+/// Whether `event` shows the minimap, and the focus it is then shown for,
+/// given the focus it was last shown for. One logical focus change reported
+/// by several events (a window opening focused, then the focus change to it)
+/// shows it only once. `suppressed` events (floating windows, unless
+/// `show_for_floating_windows`) neither show it nor move the shown focus.
+fn focus_show(
+    shown_focus: Option<u64>,
+    event: FocusShowEvent,
+    suppressed: bool,
+) -> (bool, Option<u64>) {
+    if suppressed {
+        return (false, shown_focus);
+    }
+    match event {
+        FocusShowEvent::NewWindow {
+            window_id,
+            is_focused,
+        } => (
+            true,
+            if is_focused {
+                Some(window_id)
+            } else {
+                shown_focus
+            },
+        ),
+        FocusShowEvent::FocusChange(window_id) => (window_id != shown_focus, window_id),
+    }
+}
+
+/// Gate for `focus_hold_ms`: a focus change soon after the last applied
+/// one is parked until the hold runs out, and later changes replace it, so
+/// only the focus that stuck is applied.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct FocusHold {
+    /// When a focus change was last applied
+    last_applied: Option<Instant>,
+    /// The latest held focus change, if any
+    pending: Option<Option<u64>>,
+}
+
+impl FocusHold {
+    /// Offer a focus change at `now`. Returns the focus to apply right away,
+    /// or `None` if the change is held.
+    fn offer(
+        &mut self,
+        window_id: Option<u64>,
+        now: Instant,
+        hold: Duration,
+    ) -> Option<Option<u64>> {
+        if self.pending.is_none() && cooldown_elapsed(self.last_applied, now, hold) {
+            self.last_applied = Some(now);
+            Some(window_id)
+        } else {
+            self.pending = Some(window_id);
+            None
+        }
+    }
+
+    /// Take the held focus change once the hold has run out
+    fn settle(&mut self, now: Instant) -> Option<Option<u64>> {
+        let pending = self.pending.take();
+        if pending.is_some() {
+            self.last_applied = Some(now);
+        }
+        pending
+    }
+}
+
+/// Whether at least `cooldown` has passed since `last` (always true the
+/// first time).
+fn cooldown_elapsed(last: Option<Instant>, now: Instant, cooldown: Duration) -> bool {
+    last.is_none_or(|last| now.duration_since(last) >= cooldown)
+}
+
+/// Time-driven render state owned by the widget rather than `MinimapState`.
+#[derive(Debug, Clone, Default)]
+struct DrawEffects {
+    /// Whether urgent windows currently show `urgent_color` (blink phase).
+    urgent_blink_on: bool,
+    /// Visibility (0.0-1.0) of windows mid open/close animation; windows not
+    /// listed are fully visible.
+    window_visibility: HashMap<u64, f64>,
+    /// Clickable regions recorded while drawing, in draw order.
+    hit_regions: RefCell<Vec<HitRegion>>,
+    /// App icons by app_id; empty unless `show_icons` is enabled.
+    icons: HashMap<String, Pixbuf>,
+    /// Focus recency of windows (0 = most recent), with `recency_fade`.
+    focus_ranks: Option<HashMap<u64, usize>>,
+    /// Tiled column (workspace id, column index) under the focused floating
+    /// window, with `floating_indicator = "column"`.
+    floating_column: Option<(u64, usize)>,
+    /// With `workspace_transition_ms`: the workspace sliding out, and the x
+    /// offsets of it and of the active workspace as fractions of the width.
+    workspace_slide: Option<(Workspace, (f64, f64))>,
+    /// With `overview_all_workspaces` while the overview is open: draw the
+    /// rows of inactive workspaces at `OVERVIEW_INACTIVE_OPACITY`.
+    dim_inactive_workspaces: bool,
+    /// With `enforce_contrast`: the painted background window fills must
+    /// stand out from. `None` when no background is painted.
+    contrast_background: Option<Color>,
+}
+
+impl DrawEffects {
+    fn visibility(&self, window_id: u64) -> f64 {
+        self.window_visibility
+            .get(&window_id)
+            .copied()
+            .unwrap_or(1.0)
+    }
+
+    /// Fill alpha multiplier for `window_id` from `recency_fade`.
+    fn recency(&self, window_id: u64) -> f64 {
+        self.focus_ranks
+            .as_ref()
+            .map_or(1.0, |ranks| recency_alpha(ranks.get(&window_id).copied()))
+    }
+
+    fn record_hit(&self, target: HitTarget, x: f64, y: f64, w: f64, h: f64) {
+        self.hit_regions
+            .borrow_mut()
+            .push(HitRegion { target, x, y, w, h });
+    }
+}
+
+/// What a click on the minimap refers to (interactive mode).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HitTarget {
+    Window(u64),
+    Workspace(u64),
+}
+
+/// A drawn rectangle in widget coordinates and what it represents.
+#[derive(Debug, Clone, Copy)]
+struct HitRegion {
+    target: HitTarget,
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+}
+
+/// Find what's under widget point (`x`, `y`). Later regions were drawn on
+/// top, so they win.
+fn hit_test(regions: &[HitRegion], x: f64, y: f64) -> Option<HitTarget> {
+    regions
+        .iter()
+        .rev()
+        .find(|r| x >= r.x && x < r.x + r.w && y >= r.y && y < r.y + r.h)
+        .map(|r| r.target)
+}
+
+/// Hover tooltip for a window: its title over its app id, whichever are set
+fn window_tooltip(window: &Window) -> Option<String> {
+    let lines: Vec<&str> = [window.title.as_deref(), window.app_id.as_deref()]
+        .into_iter()
+        .flatten()
+        .filter(|line| !line.is_empty())
+        .collect();
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// Window colors resolved from the appearance config, with fallbacks for
+/// unparseable hex values.
+struct Palette {
+    window: Color,
+    focused: Color,
+    border: Color,
+    urgent: Color,
+    /// Active window of a multi-window column, if highlighted
+    column_active: Option<Color>,
+    /// Focused fullscreen window, if it gets its own color
+    fullscreen: Option<Color>,
+}
+
+impl Palette {
+    fn from_appearance(appearance: &AppearanceConfig) -> Self {
+        Self {
+            window: Color::from_hex(&appearance.window_color).unwrap_or(Color {
+                r: 0.27,
+                g: 0.28,
+                b: 0.35,
+                a: 1.0,
+            }),
+            focused: Color::from_hex(&appearance.focused_color).unwrap_or(Color {
+                r: 0.54,
+                g: 0.71,
+                b: 0.98,
+                a: 1.0,
+            }),
+            border: Color::from_hex(&appearance.border_color).unwrap_or(Color {
+                r: 0.42,
+                g: 0.44,
+                b: 0.53,
+                a: 1.0,
+            }),
+            urgent: Color::from_hex(&appearance.urgent_color).unwrap_or(Color {
+                r: 0.95,
+                g: 0.55,
+                b: 0.66,
+                a: 1.0,
+            }),
+            column_active: Color::from_hex(&appearance.column_active_color),
+            fullscreen: Color::from_hex(&appearance.fullscreen_color),
+        }
+    }
+
+    /// Fill color and alpha for a window. Focus wins over urgency; urgent
+    /// windows alternate with the normal window color while blinking. The
+    /// column under a focused floating window gets a faded focus color.
+    fn fill_for(
+        &self,
+        window: &Window,
+        appearance: &AppearanceConfig,
+        effects: &DrawEffects,
+    ) -> (&Color, f64) {
+        let window_opacity = appearance.window_opacity * effects.recency(window.id);
+        if window.is_focused {
+            let color = self
+                .fullscreen
+                .as_ref()
+                .filter(|_| window.is_fullscreen)
+                .unwrap_or(&self.focused);
+            (color, appearance.focused_opacity)
+        } else if effects
+            .floating_column
+            .is_some_and(|(workspace_id, column)| {
+                window.workspace_id == Some(workspace_id) && window.column_index == column
+            })
+        {
+            (
+                &self.focused,
+                appearance.focused_opacity * FLOATING_COLUMN_OPACITY,
+            )
+        } else if window.is_urgent && effects.urgent_blink_on {
+            (&self.urgent, window_opacity)
+        } else if let Some(color) = self
+            .column_active
+            .as_ref()
+            .filter(|_| window.is_column_active)
+        {
+            (color, window_opacity)
+        } else {
+            (&self.window, window_opacity)
+        }
+    }
+}
+
+/// Monitor's logical width — used as the workspace viewport width.
+///
+/// Niri's per-workspace viewport equals its output's logical width. We don't
+/// query niri-ipc for output info today, so we use the GDK geometry of the
+/// monitor the minimap is on (see `minimap_monitor`), falling back to 1920.
+fn monitor_logical_width(monitor: Option<&gtk4::gdk::Monitor>) -> f64 {
+    monitor.map_or(1920.0, |monitor| monitor.geometry().width() as f64)
+}
+
+/// Monitor's width / height, for `match_monitor_aspect`. Falls back to 16:9.
+fn monitor_aspect(monitor: Option<&gtk4::gdk::Monitor>) -> f64 {
+    monitor
+        .map(|monitor| monitor.geometry())
+        .filter(|geometry| geometry.width() > 0 && geometry.height() > 0)
+        .map_or(16.0 / 9.0, |geometry| {
+            geometry.width() as f64 / geometry.height() as f64
+        })
+}
+
+/// How `minimap_monitor` found the monitor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MonitorSource {
+    /// The followed or configured output
+    Output,
+    /// The monitor the minimap's surface is on
+    Surface,
+    /// The first monitor GDK lists
+    FirstMonitor,
+}
+
+impl MonitorSource {
+    fn description(self) -> &'static str {
+        match self {
+            MonitorSource::Output => "configured output",
+            MonitorSource::Surface => "monitor the minimap is on",
+            MonitorSource::FirstMonitor => "first monitor",
+        }
+    }
+}
+
+/// The monitor the minimap is shown on: the output `follow_focus` last moved
+/// it to, else `display.output`, else the monitor holding `surface` (the
+/// minimap's own, once mapped), else the first monitor.
+fn minimap_monitor(
+    display: &DisplayConfig,
+    followed_output: Option<&str>,
+    surface: Option<&gtk4::gdk::Surface>,
+) -> Option<(gtk4::gdk::Monitor, MonitorSource)> {
+    let output =
+        followed_output.or((!display.output.is_empty()).then_some(display.output.as_str()));
+    if let Some(monitor) = output.and_then(monitor_for_output) {
+        return Some((monitor, MonitorSource::Output));
+    }
+    let gdk_display = gtk4::gdk::Display::default()?;
+    if let Some(monitor) = surface.and_then(|surface| gdk_display.monitor_at_surface(surface)) {
+        return Some((monitor, MonitorSource::Surface));
+    }
+    gdk_display
+        .monitors()
+        .item(0)?
+        .downcast::<gtk4::gdk::Monitor>()
+        .ok()
+        .map(|monitor| (monitor, MonitorSource::FirstMonitor))
+}
+
+/// Hash of everything in `state` that the minimap draws or sizes itself by.
+/// Titles are left out: they only show in the hover tooltip, which reads the
+/// state when the pointer moves. App ids only matter with `show_icons`.
+fn draw_fingerprint(state: &MinimapState, appearance: &AppearanceConfig) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    state.active_workspace_id.hash(&mut hasher);
+    state.focused_window_id.hash(&mut hasher);
+    state.output_name.hash(&mut hasher);
+    state.layout_gap.map(f64::to_bits).hash(&mut hasher);
+    for workspace in state.workspaces_sorted() {
+        workspace.id.hash(&mut hasher);
+        workspace.idx.hash(&mut hasher);
+        workspace.name.hash(&mut hasher);
+        workspace.output.hash(&mut hasher);
+        workspace.is_active.hash(&mut hasher);
+        workspace.active_window_id.hash(&mut hasher);
+
+        let mut windows: Vec<&Window> = workspace.windows.values().collect();
+        windows.sort_unstable_by_key(|w| w.id);
+        for window in windows {
+            window.id.hash(&mut hasher);
+            if appearance.show_icons {
+                window.app_id.hash(&mut hasher);
+            }
+            window
+                .pos
+                .map(|(x, y)| (x.to_bits(), y.to_bits()))
+                .hash(&mut hasher);
+            (window.size.0.to_bits(), window.size.1.to_bits()).hash(&mut hasher);
+            window.column_index.hash(&mut hasher);
+            window.window_index.hash(&mut hasher);
+            (
+                window.is_focused,
+                window.is_floating,
+                window.is_urgent,
+                window.is_hidden,
+                window.is_column_active,
+                window.is_fullscreen,
+            )
+                .hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+/// The config a frame is laid out and drawn with. With
+/// `overview_all_workspaces`, every workspace is shown while Niri's overview
+/// is open, overriding `workspace_mode` and `focus_mode`.
+fn overview_config(config: &Config, overview_open: bool) -> Cow<'_, Config> {
+    if !(overview_open && config.behavior.overview_all_workspaces) {
+        return Cow::Borrowed(config);
     }
-    1920.0
+    let mut config = config.clone();
+    config.display.workspace_mode = WorkspaceMode::All;
+    config.display.focus_mode = false;
+    Cow::Owned(config)
+}
+
+/// Background color (hex) to draw while `workspace` is active: its entry in
+/// `workspace_colors`, else the global `background`.
+fn workspace_background<'a>(
+    appearance: &'a AppearanceConfig,
+    workspace: Option<&Workspace>,
+) -> &'a str {
+    workspace
+        .and_then(|ws| {
+            appearance
+                .workspace_colors
+                .iter()
+                .find(|(reference, _)| ws.matches(reference))
+        })
+        .map_or(&appearance.background, |(_, color)| color)
 }
 
 /// Per-workspace geometry computed from its tiled windows.
@@ -293,58 +1682,172 @@ struct WorkspaceLayout<'a> {
     anchored_right: f64,
     /// Whether this workspace has any tiled windows.
     has_tiled: bool,
+    /// Columns left out on the left/right by `display.max_columns`.
+    hidden_columns_left: usize,
+    hidden_columns_right: usize,
+    /// Draw one bar per column instead of one rect per window
+    /// (`display.density`).
+    compact: bool,
 }
 
 /// Select the workspaces that should appear in `all` mode:
 /// any workspace that has at least one window, plus the focused one even if empty.
 /// This filters out Niri's trailing placeholder workspace (the always-present empty
-/// workspace users can scroll into to create a new one) unless the user is on it.
-fn all_mode_rows(state: &MinimapState, viewport_width: f64) -> Vec<WorkspaceLayout<'_>> {
+/// workspace users can scroll into to create a new one) unless the user is on it,
+/// or always with `hide_trailing_empty`.
+fn all_mode_rows<'a>(
+    state: &'a MinimapState,
+    display: &DisplayConfig,
+    hide_trailing_empty: bool,
+    viewport_width: f64,
+) -> Vec<WorkspaceLayout<'a>> {
     let active_id = state.active_workspace_id;
+    let trailing_empty = if hide_trailing_empty {
+        state.trailing_empty_workspaces()
+    } else {
+        HashSet::new()
+    };
     state
         .workspaces_sorted()
         .into_iter()
         .filter(|ws| !ws.windows.is_empty() || Some(ws.id) == active_id)
-        .map(|ws| build_workspace_layout(ws, viewport_width))
+        .filter(|ws| !trailing_empty.contains(&ws.id))
+        .map(|ws| build_workspace_layout(ws, display, viewport_width))
         .collect()
 }
 
 /// Build the layout for a single workspace (tiled windows only).
-fn build_workspace_layout(workspace: &Workspace, viewport_width: f64) -> WorkspaceLayout<'_> {
+///
+/// Every column is rendered as a vertical stack of its windows. Niri's IPC
+/// doesn't report a column's display mode, so tabbed columns can't be told
+/// apart from normal ones and fall back to the same stacked rendering.
+///
+/// Windows with non-finite sizes are skipped so they can't poison the scale
+/// math (and from there, the widget size).
+///
+/// With `display.max_columns`, only that many columns are kept, centered on
+/// the focused column (or the workspace's last-focused one); the number of
+/// columns left out on each side is recorded for the overflow indicators.
+fn build_workspace_layout<'a>(
+    workspace: &'a Workspace,
+    display: &DisplayConfig,
+    viewport_width: f64,
+) -> WorkspaceLayout<'a> {
+    let max_columns = display.max_columns as usize;
+    if max_columns == 0 {
+        return build_workspace_layout_filtered(workspace, viewport_width, |_| true);
+    }
+
+    let mut column_ids: Vec<usize> = workspace
+        .windows
+        .values()
+        .filter(|w| !w.is_floating)
+        .map(|w| w.column_index)
+        .collect();
+    column_ids.sort_unstable();
+    column_ids.dedup();
+
+    if column_ids.len() <= max_columns {
+        return build_workspace_layout_filtered(workspace, viewport_width, |_| true);
+    }
+
+    let center_column = workspace
+        .windows
+        .values()
+        .find(|w| w.is_focused && !w.is_floating)
+        .or_else(|| {
+            workspace
+                .active_window_id
+                .and_then(|id| workspace.windows.get(&id))
+        })
+        .map(|w| w.column_index)
+        .unwrap_or(0);
+    let center = column_ids.partition_point(|&c| c < center_column);
+    let start = center
+        .saturating_sub(max_columns / 2)
+        .min(column_ids.len() - max_columns);
+    let kept = &column_ids[start..start + max_columns];
+    let (first, last) = (kept[0], kept[max_columns - 1]);
+
+    let mut layout = build_workspace_layout_filtered(workspace, viewport_width, |col| {
+        (first..=last).contains(&col)
+    });
+    layout.hidden_columns_left = start;
+    layout.hidden_columns_right = column_ids.len() - start - max_columns;
+    layout
+}
+
+/// In `focus_mode`, the layout of the active workspace restricted to the
+/// focused column plus `focus_neighbors` columns on either side.
+///
+/// Returns `None` when focus mode is off or no tiled window on the active
+/// workspace is focused, in which case the whole workspace is drawn.
+fn focus_layout<'a>(
+    state: &'a MinimapState,
+    display: &DisplayConfig,
+    viewport_width: f64,
+) -> Option<WorkspaceLayout<'a>> {
+    if !display.focus_mode {
+        return None;
+    }
+    let workspace = state.active_workspace()?;
+    let focused = state
+        .focused_window_id
+        .and_then(|id| workspace.windows.get(&id))
+        .filter(|w| !w.is_floating)?;
+
+    let neighbors = display.focus_neighbors as usize;
+    let first = focused.column_index.saturating_sub(neighbors);
+    let last = focused.column_index.saturating_add(neighbors);
+    Some(build_workspace_layout_filtered(
+        workspace,
+        viewport_width,
+        |col| (first..=last).contains(&col),
+    ))
+}
+
+/// `build_workspace_layout`, keeping only columns for which `keep_column`
+/// returns true. Dropped columns take no horizontal space.
+fn build_workspace_layout_filtered(
+    workspace: &Workspace,
+    viewport_width: f64,
+    keep_column: impl Fn(usize) -> bool,
+) -> WorkspaceLayout<'_> {
     let mut columns: BTreeMap<usize, Vec<&Window>> = BTreeMap::new();
     for window in workspace.windows.values() {
-        if !window.is_floating {
+        let finite = window.size.0.is_finite() && window.size.1.is_finite();
+        if !window.is_floating && finite && keep_column(window.column_index) {
             columns.entry(window.column_index).or_default().push(window);
         }
     }
     for windows in columns.values_mut() {
-        windows.sort_by_key(|w| w.window_index);
-    }
-
-    let mut column_widths: Vec<f64> = Vec::new();
-    let mut column_heights: Vec<f64> = Vec::new();
-    if let Some(&max_col) = columns.keys().max() {
-        for col_idx in 0..=max_col {
-            if let Some(windows) = columns.get(&col_idx) {
-                let w = windows.iter().map(|w| w.size.0).fold(0.0_f64, f64::max);
-                let h: f64 = windows.iter().map(|w| w.size.1).sum();
-                column_widths.push(w);
-                column_heights.push(h);
-            } else {
-                column_widths.push(0.0);
-                column_heights.push(0.0);
-            }
-        }
+        // Break window_index ties (e.g. several windows saturated to 0 by
+        // incomplete layout data) by vertical position, then id, so the
+        // order stays stable between frames.
+        windows.sort_by(|a, b| {
+            a.window_index
+                .cmp(&b.window_index)
+                .then_with(|| {
+                    let a_y = a.pos.map(|(_, y)| y);
+                    let b_y = b.pos.map(|(_, y)| y);
+                    a_y.partial_cmp(&b_y).unwrap_or(Ordering::Equal)
+                })
+                .then_with(|| a.id.cmp(&b.id))
+        });
     }
 
-    let mut column_x_positions = Vec::with_capacity(column_widths.len());
-    let mut x = 0.0_f64;
-    for &w in &column_widths {
-        column_x_positions.push(x);
-        x += w;
+    // Missing column indices take no width
+    let column_count = columns.keys().next_back().map_or(0, |&max_col| max_col + 1);
+    let mut column_x_positions = Vec::with_capacity(column_count);
+    let mut total_width = 0.0_f64;
+    let mut max_height = 0.0_f64;
+    for col_idx in 0..column_count {
+        column_x_positions.push(total_width);
+        if let Some(windows) = columns.get(&col_idx) {
+            total_width += windows.iter().map(|w| w.size.0).fold(0.0_f64, f64::max);
+            max_height = max_height.max(windows.iter().map(|w| w.size.1).sum());
+        }
     }
-    let total_width: f64 = column_widths.iter().sum();
-    let max_height = column_heights.iter().fold(0.0_f64, |a, &b| a.max(b));
 
     // Derive the viewport offset (`align_x`) — the workspace-x of the
     // viewport's left edge.
@@ -360,15 +1863,11 @@ fn build_workspace_layout(workspace: &Workspace, viewport_width: f64) -> Workspa
     // niri pins it at 0; otherwise we approximate using the last-focused
     // window's column position, clamped to `[0, total_width - viewport_width]`
     // so right-edge content stays right-aligned.
-    let pos_offset = workspace
-        .windows
-        .values()
-        .filter(|w| !w.is_floating)
-        .find_map(|w| {
-            let (px, _) = w.pos?;
-            let col_x = column_x_positions.get(w.column_index).copied()?;
-            Some(col_x - px)
-        });
+    let pos_offset = columns.values().flatten().find_map(|w| {
+        let (px, _) = w.pos?;
+        let col_x = column_x_positions.get(w.column_index).copied()?;
+        Some(col_x - px)
+    });
     let align_x = if let Some(offset) = pos_offset {
         offset
     } else if total_width <= viewport_width {
@@ -378,7 +1877,7 @@ fn build_workspace_layout(workspace: &Workspace, viewport_width: f64) -> Workspa
         workspace
             .active_window_id
             .and_then(|id| workspace.windows.get(&id))
-            .filter(|w| !w.is_floating)
+            .filter(|w| !w.is_floating && w.size.0.is_finite())
             .and_then(|w| column_x_positions.get(w.column_index).copied())
             .unwrap_or(0.0)
             .clamp(0.0, max_offset)
@@ -398,50 +1897,204 @@ fn build_workspace_layout(workspace: &Workspace, viewport_width: f64) -> Workspa
         anchored_left,
         anchored_right,
         has_tiled,
+        hidden_columns_left: 0,
+        hidden_columns_right: 0,
+        compact: false,
     }
 }
 
-/// Resolved widget dimensions.
-struct WidgetDimensions {
-    width: f64,
-    height: f64,
+/// The workspace layouts a frame shows, shared by sizing and drawing so
+/// both always agree on what's on screen.
+enum FrameLayout<'a> {
+    /// A single row: the active workspace (`current` mode), or the focused
+    /// columns when `zoomed` (focus mode).
+    Current {
+        layout: Option<WorkspaceLayout<'a>>,
+        zoomed: bool,
+    },
+    /// One row per workspace (`all` mode).
+    All(Vec<WorkspaceLayout<'a>>),
 }
 
-/// Geometry shared across all workspace rows in `all` mode.
+/// Build the layouts for the current state and display mode. Focus mode
+/// takes precedence over `workspace_mode`.
 ///
-/// All rows use the same scale so viewports align visually. Each row is drawn
-/// from its own `row_x_origin` (the screen x where its workspace-coord 0 sits),
-/// which is computed from the shared `viewport_anchor_x` and the workspace's
-/// `viewport_offset`.
-struct AllModeGeometry {
-    widget_width: f64,
-    widget_height: f64,
-    row_height: f64,
-    scale: f64,
-    /// Screen x where the anchored frame's origin (viewport left edge) lives.
-    viewport_anchor_x: f64,
-}
-
-/// Compute shared all-mode geometry from the workspace rows and config caps.
-fn compute_all_mode_geometry(
-    rows: &[WorkspaceLayout<'_>],
+/// Nothing is cached: sizing builds the layouts on every state or config
+/// change and drawing on every frame, plus one more for a workspace sliding
+/// out. The `frame_layout` benchmark measures what that costs.
+fn frame_layout<'a>(
+    state: &'a MinimapState,
     display: &DisplayConfig,
-    workspace_gap: f64,
-    max_width: f64,
-    max_height: f64,
+    appearance: &AppearanceConfig,
+    behavior: &BehaviorConfig,
     viewport_width: f64,
-) -> AllModeGeometry {
-    let row_height_cfg = display.height as f64;
-    let min_widget_width = row_height_cfg;
-
-    let n = rows.len().max(1) as f64;
-    let total_gap = (n - 1.0).max(0.0) * workspace_gap;
+) -> FrameLayout<'a> {
+    let mut frame = if let Some(layout) = focus_layout(state, display, viewport_width) {
+        FrameLayout::Current {
+            layout: Some(layout),
+            zoomed: true,
+        }
+    } else {
+        match display.workspace_mode {
+            WorkspaceMode::Current => FrameLayout::Current {
+                layout: state
+                    .active_workspace()
+                    .map(|ws| build_workspace_layout(ws, display, viewport_width)),
+                zoomed: false,
+            },
+            WorkspaceMode::All => FrameLayout::All(all_mode_rows(
+                state,
+                display,
+                behavior.hide_empty_trailing_workspace,
+                viewport_width,
+            )),
+        }
+    };
 
-    let ideal_height = n * row_height_cfg + total_gap + PADDING * 2.0;
-    let min_height = row_height_cfg + PADDING * 2.0;
+    // A focused fullscreen window hides the rest of its workspace, so its
+    // row shows just that window; leaving fullscreen brings the others back
+    let collapse_fullscreen = |layout: &mut WorkspaceLayout<'a>| {
+        if let Some(fullscreen) = fullscreen_layout(layout.workspace) {
+            *layout = fullscreen;
+        }
+    };
+    match &mut frame {
+        FrameLayout::Current { layout, .. } => layout.iter_mut().for_each(collapse_fullscreen),
+        FrameLayout::All(rows) => rows.iter_mut().for_each(collapse_fullscreen),
+    }
+
+    if appearance.preserve_column_spacing {
+        match &mut frame {
+            FrameLayout::Current { layout, .. } => layout.iter_mut().for_each(respace_columns),
+            FrameLayout::All(rows) => rows.iter_mut().for_each(respace_columns),
+        }
+    }
+    let set_density =
+        |layout: &mut WorkspaceLayout<'_>| layout.compact = is_compact(layout, display);
+    match &mut frame {
+        FrameLayout::Current { layout, .. } => layout.iter_mut().for_each(set_density),
+        FrameLayout::All(rows) => rows.iter_mut().for_each(set_density),
+    }
+    frame
+}
+
+/// Layout holding only `workspace`'s focused window, when it's a fullscreen
+/// tiled window. It fills the whole row, like the window fills its output.
+fn fullscreen_layout(workspace: &Workspace) -> Option<WorkspaceLayout<'_>> {
+    let window = workspace
+        .windows
+        .values()
+        .find(|w| w.is_focused && w.is_fullscreen && !w.is_floating)?;
+    let (total_width, max_height) = window.size;
+    Some(WorkspaceLayout {
+        workspace,
+        columns: BTreeMap::from([(window.column_index, vec![window])]),
+        // Earlier columns are hidden, so they take no width
+        column_x_positions: vec![0.0; window.column_index + 1],
+        total_width,
+        max_height,
+        align_x: 0.0,
+        anchored_left: 0.0,
+        anchored_right: total_width,
+        has_tiled: true,
+        hidden_columns_left: 0,
+        hidden_columns_right: 0,
+        compact: false,
+    })
+}
+
+/// Whether a workspace is drawn with compact density: always with
+/// `density = "compact"`, or once it holds `compact_threshold` tiled windows.
+fn is_compact(layout: &WorkspaceLayout<'_>, display: &DisplayConfig) -> bool {
+    let tiled: usize = layout.columns.values().map(Vec::len).sum();
+    display.density == Density::Compact
+        || (display.compact_threshold > 0 && tiled >= display.compact_threshold as usize)
+}
+
+/// With `preserve_column_spacing`, place the columns Niri reports positions
+/// for at their real distance from each other (Niri's gaps included) rather
+/// than edge to edge. Columns without positions (scrolled out of view, or
+/// every column of a background workspace) stay packed.
+fn respace_columns(layout: &mut WorkspaceLayout<'_>) {
+    // Leftmost reported x of each column
+    let reported_x: BTreeMap<usize, f64> = layout
+        .columns
+        .iter()
+        .filter_map(|(&col, windows)| {
+            let x = windows
+                .iter()
+                .filter_map(|w| w.pos.map(|(x, _)| x))
+                .fold(f64::INFINITY, f64::min);
+            x.is_finite().then_some((col, x))
+        })
+        .collect();
+    let Some((&anchor_col, &anchor_reported)) = reported_x.iter().next() else {
+        return;
+    };
+    // The first positioned column keeps its place, so the viewport offset
+    // (`align_x`) derived from it stays valid
+    let anchor_x = layout.column_x_positions[anchor_col];
+
+    let mut next_x = 0.0_f64;
+    let mut total_width = 0.0_f64;
+    for col in 0..layout.column_x_positions.len() {
+        let col_x = reported_x
+            .get(&col)
+            .map_or(next_x, |x| anchor_x + (x - anchor_reported));
+        let width = layout.columns.get(&col).map_or(0.0, |windows| {
+            windows.iter().map(|w| w.size.0).fold(0.0, f64::max)
+        });
+        layout.column_x_positions[col] = col_x;
+        next_x = col_x + width;
+        total_width = total_width.max(next_x);
+    }
+    layout.total_width = total_width;
+    layout.anchored_right = total_width - layout.align_x;
+}
+
+/// Resolved widget dimensions.
+struct WidgetDimensions {
+    width: f64,
+    height: f64,
+}
+
+/// Geometry shared across all workspace rows in `all` mode.
+///
+/// All rows use the same scale so viewports align visually. Each row is drawn
+/// from its own `row_x_origin` (the screen x where its workspace-coord 0 sits),
+/// which is computed from the shared `viewport_anchor_x` and the workspace's
+/// `viewport_offset`.
+struct AllModeGeometry {
+    widget_width: f64,
+    widget_height: f64,
+    row_height: f64,
+    scale: f64,
+    /// Screen x where the anchored frame's origin (viewport left edge) lives.
+    viewport_anchor_x: f64,
+}
+
+/// Compute shared all-mode geometry from the workspace rows and config caps.
+fn compute_all_mode_geometry(
+    rows: &[WorkspaceLayout<'_>],
+    display: &DisplayConfig,
+    appearance: &AppearanceConfig,
+    max_width: f64,
+    max_height: f64,
+    viewport_width: f64,
+) -> AllModeGeometry {
+    let padding = content_padding(appearance);
+    let workspace_gap = appearance.workspace_gap;
+    let row_height_cfg = display.height as f64;
+    let min_widget_width = row_height_cfg;
+
+    let n = rows.len().max(1) as f64;
+    let total_gap = (n - 1.0).max(0.0) * workspace_gap;
+
+    let ideal_height = n * row_height_cfg + total_gap + padding * 2.0;
+    let min_height = row_height_cfg + padding * 2.0;
     let widget_height = ideal_height.min(max_height.max(min_height)).max(min_height);
 
-    let available = widget_height - PADDING * 2.0 - total_gap;
+    let available = widget_height - padding * 2.0 - total_gap;
     let row_height = (available / n).max(1.0);
 
     // Shared scale: fit the tallest workspace's column height into row_height.
@@ -472,29 +2125,29 @@ fn compute_all_mode_geometry(
 
     let (scaled_content_width, ideal_anchor) = if has_content {
         let w = (combined_right - combined_left) * scale;
-        // Place the leftmost anchored content at x = PADDING; then anchored x=0
+        // Place the leftmost anchored content at x = padding; then anchored x=0
         // (each workspace's viewport left edge) lives at:
-        let anchor = PADDING - combined_left * scale;
+        let anchor = padding - combined_left * scale;
         (w, anchor)
     } else {
-        (0.0, PADDING)
+        (0.0, padding)
     };
 
-    let ideal_width = scaled_content_width + PADDING * 2.0;
+    let ideal_width = scaled_content_width + padding * 2.0;
     let widget_width = ideal_width.min(max_width).max(min_widget_width);
 
     // If content fits, keep the leftmost-anchored layout. If we got clamped
     // narrower, shifting `viewport_anchor_x` keeps the leftmost extent at
-    // PADDING but pushes content past the right edge — and a workspace with a
+    // the padding but pushes content past the right edge — and a workspace with a
     // large left-side off-viewport context (large `align_x`) can drag every
     // workspace's viewport off the visible widget. Re-center on the viewport
     // (anchored x in [0, viewport_width]) instead so it's always visible.
-    let inner_width = (widget_width - PADDING * 2.0).max(0.0);
+    let inner_width = (widget_width - padding * 2.0).max(0.0);
     let viewport_anchor_x = if !has_content || scaled_content_width <= inner_width {
         ideal_anchor
     } else {
         let viewport_scaled = viewport_width * scale;
-        PADDING + (inner_width - viewport_scaled) / 2.0
+        padding + (inner_width - viewport_scaled) / 2.0
     };
 
     AllModeGeometry {
@@ -510,29 +2163,27 @@ fn compute_all_mode_geometry(
 fn compute_widget_dimensions(
     state: &MinimapState,
     display: &DisplayConfig,
-    workspace_gap: f64,
+    appearance: &AppearanceConfig,
+    behavior: &BehaviorConfig,
     max_width: f64,
     max_height: f64,
     viewport_width: f64,
 ) -> WidgetDimensions {
+    let padding = content_padding(appearance);
     let row_height_cfg = display.height as f64;
     let min_widget_width = row_height_cfg;
+    let strip_height = floating_strip_height(state, appearance);
 
-    match display.workspace_mode {
-        WorkspaceMode::Current => {
-            let widget_height = row_height_cfg;
-            let row_height = (widget_height - PADDING * 2.0).max(0.0);
-            let scaled_w = state
-                .active_workspace()
-                .map(|ws| {
-                    row_scaled_width_centered(
-                        &build_workspace_layout(ws, viewport_width),
-                        row_height,
-                    )
-                })
+    // Focus mode is sized like `current` mode
+    match frame_layout(state, display, appearance, behavior, viewport_width) {
+        FrameLayout::Current { layout, .. } => {
+            let widget_height = row_height_cfg + strip_height;
+            let row_height = (row_height_cfg - padding * 2.0).max(0.0);
+            let scaled_w = layout
+                .map(|layout| row_scaled_width_centered(&layout, row_height))
                 .unwrap_or(0.0);
 
-            let ideal_width = scaled_w + PADDING * 2.0;
+            let ideal_width = scaled_w + padding * 2.0;
             let width = ideal_width.min(max_width).max(min_widget_width);
 
             WidgetDimensions {
@@ -540,25 +2191,103 @@ fn compute_widget_dimensions(
                 height: widget_height,
             }
         }
-        WorkspaceMode::All => {
-            let rows = all_mode_rows(state, viewport_width);
+        FrameLayout::All(rows) => {
             let geom = compute_all_mode_geometry(
                 &rows,
                 display,
-                workspace_gap,
+                appearance,
                 max_width,
-                max_height,
+                (max_height - strip_height).max(0.0),
                 viewport_width,
             );
 
             WidgetDimensions {
                 width: geom.widget_width,
-                height: geom.widget_height,
+                height: geom.widget_height + strip_height,
             }
         }
     }
 }
 
+/// Size of the minimap for `state`, computed as `update_size` does but
+/// without a widget or monitor
+pub fn frame_size(
+    state: &MinimapState,
+    config: &Config,
+    max_width: f64,
+    max_height: f64,
+    viewport_width: f64,
+) -> (f64, f64) {
+    let dims = compute_widget_dimensions(
+        state,
+        &config.display,
+        &config.appearance,
+        &config.behavior,
+        max_width,
+        max_height,
+        viewport_width,
+    );
+    (dims.width, dims.height)
+}
+
+/// Draw `state` like the widget does, but without animations, icons or
+/// urgency blinking
+pub fn draw_frame(
+    cr: &Context,
+    width: i32,
+    height: i32,
+    state: &MinimapState,
+    config: &Config,
+    viewport_width: f64,
+) {
+    draw_minimap(
+        cr,
+        width,
+        height,
+        state,
+        config,
+        viewport_width,
+        &DrawEffects::default(),
+    );
+}
+
+/// With `match_monitor_aspect`: give `dims` the width that makes it
+/// `aspect` (width / height) wide, up to `max_width`. The height stays.
+fn match_aspect(dims: WidgetDimensions, aspect: f64, max_width: f64) -> WidgetDimensions {
+    WidgetDimensions {
+        width: (dims.height * aspect).min(max_width),
+        height: dims.height,
+    }
+}
+
+/// Turn computed dimensions into the whole-pixel size handed to GTK, which
+/// must never see a non-finite or absurd size. Non-finite values fall back to
+/// `min_width` (with a warning) and the width is clamped to
+/// `[min_width, max_width]`.
+fn widget_pixel_size(dims: &WidgetDimensions, min_width: f64, max_width: f64) -> (i32, i32) {
+    let finite_or = |value: f64, what: &str, fallback: f64| {
+        if value.is_finite() {
+            value
+        } else {
+            tracing::warn!("Non-finite minimap {}: {}, using {}", what, value, fallback);
+            fallback
+        }
+    };
+    let min_width = finite_or(min_width, "minimum width", 1.0).max(1.0);
+    let max_width = finite_or(max_width, "maximum width", min_width).max(min_width);
+    let width = finite_or(dims.width, "width", min_width);
+    let height = finite_or(dims.height, "height", min_width).max(1.0);
+    if width > max_width {
+        tracing::warn!("Minimap width {} exceeds the cap {}", width, max_width);
+    }
+
+    let to_pixels = |value: f64| value.ceil().min(i32::MAX as f64) as i32;
+    (
+        to_pixels(width.clamp(min_width, max_width)),
+        to_pixels(height),
+    )
+}
+
 /// Compute the scaled width a workspace row would occupy at the given inner row height
 /// when rendered with column-centered layout ("current" mode).
 fn row_scaled_width_centered(layout: &WorkspaceLayout<'_>, row_inner_height: f64) -> f64 {
@@ -575,59 +2304,150 @@ fn draw_minimap(
     width: i32,
     height: i32,
     state: &MinimapState,
-    display: &DisplayConfig,
-    appearance: &AppearanceConfig,
+    config: &Config,
     viewport_width: f64,
+    effects: &DrawEffects,
 ) {
+    let display = &config.display;
+    let appearance = &config.appearance;
     let width = width as f64;
     let height = height as f64;
 
-    // Clear with transparency first
+    // Width of the monitor's visible slice to outline, if enabled
+    let viewport_outline = config.behavior.show_viewport.then_some(viewport_width);
+
+    // Clear with transparency first. `Clear` zeroes every channel, which is
+    // fully transparent in Cairo's premultiplied ARGB, so anything we don't
+    // paint below lets the compositor (and any blur it applies) show through.
     cr.set_operator(Operator::Clear);
     cr.paint().ok();
     cr.set_operator(Operator::Over);
+    cr.set_antialias(if appearance.antialias {
+        Antialias::Default
+    } else {
+        Antialias::None
+    });
+
+    // Themed per active workspace with `workspace_colors`
+    let background = workspace_background(appearance, state.active_workspace());
 
     // Optional background fill — applied in both modes; transparent by default.
-    if appearance.background_opacity > 0.0 {
-        if let Some(bg_color) = Color::from_hex(&appearance.background) {
-            cr.set_source_rgba(
-                bg_color.r,
-                bg_color.g,
-                bg_color.b,
-                appearance.background_opacity,
+    // With `blur_behind` the fill is capped so compositor blur stays visible.
+    let background_opacity = if appearance.blur_behind {
+        appearance
+            .background_opacity
+            .min(BLUR_BEHIND_MAX_BACKGROUND_OPACITY)
+    } else {
+        appearance.background_opacity
+    };
+    if background_opacity > 0.0 {
+        if let Some(bg_color) = Color::from_hex(background) {
+            set_source_color(cr, bg_color.with_alpha(background_opacity));
+            rounded_rectangle(
+                cr,
+                0.0,
+                0.0,
+                width,
+                height,
+                appearance.border_radius * 2.0,
+                appearance.corner_style,
             );
-            rounded_rectangle(cr, 0.0, 0.0, width, height, appearance.border_radius * 2.0);
             cr.fill().ok();
         }
     }
 
-    let inner_width = (width - PADDING * 2.0).max(0.0);
+    // Outline around the whole minimap, contrasting the configured background
+    if appearance.auto_contrast_border {
+        let background = Color::from_hex(background).unwrap_or(Color {
+            r: 0.12,
+            g: 0.12,
+            b: 0.18,
+            a: 1.0,
+        });
+        let outline = background.contrasting();
+        cr.set_source_rgba(outline.r, outline.g, outline.b, outline.a);
+        cr.set_line_width(1.0);
+        rounded_rectangle(
+            cr,
+            0.5,
+            0.5,
+            (width - 1.0).max(0.0),
+            (height - 1.0).max(0.0),
+            appearance.border_radius * 2.0,
+            appearance.corner_style,
+        );
+        cr.stroke().ok();
+    }
+
+    // The band below the layout holds the `floating_strip`, drawn by `render`
+    let height = (height - floating_strip_height(state, appearance)).max(0.0);
+    let padding = content_padding(appearance);
+    let inner_width = (width - padding * 2.0).max(0.0);
 
-    match display.workspace_mode {
-        WorkspaceMode::Current => {
-            let Some(workspace) = state.active_workspace() else {
-                return;
+    // Niri's own gap, in workspace pixels, when asked for and known
+    let niri_gap = match appearance.gap_mode {
+        GapMode::Fixed => None,
+        GapMode::FromNiri => state.layout_gap,
+    };
+
+    // Focus mode draws its zoomed layout like `current` mode. The viewport
+    // outline doesn't apply to a subset of columns.
+    match frame_layout(state, display, appearance, &config.behavior, viewport_width) {
+        FrameLayout::Current { layout, zoomed } => {
+            let viewport_outline = if zoomed { None } else { viewport_outline };
+            let row_inner_height = (height - padding * 2.0).max(0.0);
+            let draw_row = |layout: &WorkspaceLayout<'_>, offset: f64| {
+                if layout.total_width <= 0.0 || layout.max_height <= 0.0 {
+                    return;
+                }
+                cr.save().ok();
+                cr.translate(offset * width, 0.0);
+                draw_workspace_row_centered(
+                    cr,
+                    layout,
+                    padding,
+                    padding,
+                    inner_width,
+                    row_inner_height,
+                    padding,
+                    viewport_outline,
+                    display.overflow,
+                    appearance,
+                    niri_gap,
+                    effects,
+                );
+                cr.restore().ok();
             };
-            let layout = build_workspace_layout(workspace, viewport_width);
-            if layout.total_width <= 0.0 || layout.max_height <= 0.0 {
-                return;
+
+            // Mid workspace switch, the workspace being left slides out
+            // while the active one slides in
+            match effects.workspace_slide.as_ref().filter(|_| !zoomed) {
+                Some((from, (from_offset, to_offset))) => {
+                    let mut from_layout = build_workspace_layout(from, display, viewport_width);
+                    if appearance.preserve_column_spacing {
+                        respace_columns(&mut from_layout);
+                    }
+                    from_layout.compact = is_compact(&from_layout, display);
+                    draw_row(&from_layout, *from_offset);
+                    if let Some(layout) = &layout {
+                        draw_row(layout, *to_offset);
+                    }
+                }
+                None => {
+                    if let Some(layout) = &layout {
+                        draw_row(layout, 0.0);
+                    }
+                }
             }
-            let row_inner_height = (height - PADDING * 2.0).max(0.0);
-            draw_workspace_row_centered(
-                cr,
-                &layout,
-                PADDING,
-                PADDING,
-                inner_width,
-                row_inner_height,
-                appearance,
-            );
         }
-        WorkspaceMode::All => {
-            let rows = all_mode_rows(state, viewport_width);
+        FrameLayout::All(mut rows) => {
             if rows.is_empty() {
                 return;
             }
+            // First workspace at the bottom
+            if appearance.flip_vertical {
+                rows.reverse();
+            }
 
             // Recompute the shared geometry using this draw call's actual widget size.
             // max_height is effectively the current height — we use the drawing area's
@@ -636,7 +2456,7 @@ fn draw_minimap(
             let geom = compute_all_mode_geometry(
                 &rows,
                 display,
-                appearance.workspace_gap,
+                appearance,
                 width,
                 height,
                 viewport_width,
@@ -650,8 +2470,16 @@ fn draw_minimap(
                     a: 1.0,
                 });
 
-            let mut y = PADDING;
+            let mut y = padding;
             for layout in &rows {
+                effects.record_hit(
+                    HitTarget::Workspace(layout.workspace.id),
+                    padding,
+                    y,
+                    inner_width,
+                    geom.row_height,
+                );
+
                 // Active workspace highlight: border around the row rectangle.
                 if layout.workspace.is_active && appearance.active_workspace_border_width > 0.0 {
                     cr.set_source_rgba(
@@ -664,28 +2492,39 @@ fn draw_minimap(
                     let inset = appearance.active_workspace_border_width / 2.0;
                     rounded_rectangle(
                         cr,
-                        PADDING + inset,
+                        padding + inset,
                         y + inset,
                         (inner_width - inset * 2.0).max(0.0),
                         (geom.row_height - inset * 2.0).max(0.0),
                         appearance.border_radius,
+                        appearance.corner_style,
                     );
                     cr.stroke().ok();
                 }
 
+                let dimmed = effects.dim_inactive_workspaces && !layout.workspace.is_active;
+                if dimmed {
+                    cr.push_group();
+                }
                 if layout.has_tiled && geom.scale > 0.0 {
                     draw_workspace_row_viewport(
                         cr,
                         layout,
-                        PADDING,
+                        padding,
                         y,
                         inner_width,
                         geom.row_height,
                         geom.scale,
                         geom.viewport_anchor_x,
+                        viewport_outline,
                         appearance,
+                        niri_gap,
+                        effects,
                     );
                 }
+                if dimmed && cr.pop_group_to_source().is_ok() {
+                    cr.paint_with_alpha(OVERVIEW_INACTIVE_OPACITY).ok();
+                }
 
                 y += geom.row_height + appearance.workspace_gap;
             }
@@ -693,51 +2532,40 @@ fn draw_minimap(
     }
 }
 
-/// Draw all tiled windows of one workspace into the rectangle
-/// `(offset_x, offset_y, row_width, row_height)` using column-based centered layout.
-///
-/// Used for `current` mode: windows are grouped by column and laid out as a single
-/// scrolling-layout image, horizontally centered in the row.
-fn draw_workspace_row_centered(
-    cr: &Context,
-    layout: &WorkspaceLayout<'_>,
-    offset_x: f64,
-    offset_y: f64,
-    row_width: f64,
-    row_height: f64,
-    appearance: &AppearanceConfig,
-) {
-    if layout.total_width <= 0.0 || layout.max_height <= 0.0 || row_height <= 0.0 {
-        return;
-    }
-
-    // Scale to fit height; horizontally center within the row.
-    let scale = row_height / layout.max_height;
-    let scaled_width = layout.total_width * scale;
-    let x_origin = offset_x + (row_width - scaled_width).max(0.0) / 2.0;
-    let y_origin = offset_y;
+/// A tiled window's rectangle in widget coordinates, after gap insets.
+struct WindowRect<'a> {
+    window: &'a Window,
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+}
 
-    let window_color = Color::from_hex(&appearance.window_color).unwrap_or(Color {
-        r: 0.27,
-        g: 0.28,
-        b: 0.35,
-        a: 1.0,
-    });
-    let focused_color = Color::from_hex(&appearance.focused_color).unwrap_or(Color {
-        r: 0.54,
-        g: 0.71,
-        b: 0.98,
-        a: 1.0,
-    });
-    let border_color = Color::from_hex(&appearance.border_color).unwrap_or(Color {
-        r: 0.42,
-        g: 0.44,
-        b: 0.53,
-        a: 1.0,
-    });
+impl WindowRect<'_> {
+    /// Whether two rects share an edge (within `tolerance`), i.e. they sit
+    /// side by side or stacked with only the gap between them.
+    fn is_adjacent_to(&self, other: &WindowRect<'_>, tolerance: f64) -> bool {
+        let overlaps_x = self.x < other.x + other.w && other.x < self.x + self.w;
+        let overlaps_y = self.y < other.y + other.h && other.y < self.y + self.h;
+        let touches_x = (self.x + self.w - other.x).abs() <= tolerance
+            || (other.x + other.w - self.x).abs() <= tolerance;
+        let touches_y = (self.y + self.h - other.y).abs() <= tolerance
+            || (other.y + other.h - self.y).abs() <= tolerance;
+        (touches_x && overlaps_y) || (touches_y && overlaps_x)
+    }
+}
 
-    let gap = appearance.gap;
-    let half_gap = gap / 2.0;
+/// Compute the rectangles of a workspace's tiled windows, with workspace-x 0
+/// placed at `x_origin` and the top of each column at `y_origin`. Compact
+/// layouts get one bar per column, standing in for its active window.
+fn window_rects<'a>(
+    layout: &WorkspaceLayout<'a>,
+    x_origin: f64,
+    y_origin: f64,
+    scale: f64,
+    gap: f64,
+) -> Vec<WindowRect<'a>> {
+    let mut rects = Vec::new();
 
     for (&col_idx, windows) in &layout.columns {
         let col_x = layout
@@ -745,10 +2573,27 @@ fn draw_workspace_row_centered(
             .get(col_idx)
             .copied()
             .unwrap_or(0.0);
-        let mut y_pos = 0.0;
+        let x = x_origin + col_x * scale;
 
-        for window in windows {
-            let x = x_origin + col_x * scale;
+        if layout.compact {
+            let Some(&window) = windows
+                .iter()
+                .find(|w| w.is_focused)
+                .or_else(|| windows.iter().find(|w| w.is_column_active))
+                .or_else(|| windows.first())
+            else {
+                continue;
+            };
+            let width = windows.iter().map(|w| w.size.0).fold(0.0, f64::max);
+            let height: f64 = windows.iter().map(|w| w.size.1).sum();
+            let (x, w) = inset_cell(x, width * scale, gap);
+            let (y, h) = inset_cell(y_origin, height * scale, gap);
+            rects.push(WindowRect { window, x, y, w, h });
+            continue;
+        }
+
+        let mut y_pos = 0.0;
+        for &window in windows {
             let y = y_origin + y_pos * scale;
             let w = window.size.0 * scale;
             let h = window.size.1 * scale;
@@ -756,163 +2601,1266 @@ fn draw_workspace_row_centered(
             y_pos += window.size.1;
 
             // Apply gap
-            let x = x + half_gap;
-            let y = y + half_gap;
-            let w = (w - gap).max(1.0);
-            let h = (h - gap).max(1.0);
-
-            if w < 1.0 || h < 1.0 {
-                continue;
-            }
-
-            let (fill_color, fill_alpha) = if window.is_focused {
-                (&focused_color, appearance.focused_opacity)
-            } else {
-                (&window_color, appearance.window_opacity)
-            };
-
-            if fill_alpha > 0.0 {
-                cr.set_source_rgba(fill_color.r, fill_color.g, fill_color.b, fill_alpha);
-                rounded_rectangle(cr, x, y, w, h, appearance.border_radius);
-                cr.fill().ok();
-            }
+            let (x, w) = inset_cell(x, w, gap);
+            let (y, h) = inset_cell(y, h, gap);
 
-            if appearance.border_width > 0.0 {
-                cr.set_source_rgba(
-                    border_color.r,
-                    border_color.g,
-                    border_color.b,
-                    border_color.a,
-                );
-                cr.set_line_width(appearance.border_width);
-                rounded_rectangle(cr, x, y, w, h, appearance.border_radius);
-                cr.stroke().ok();
-            }
+            rects.push(WindowRect { window, x, y, w, h });
         }
     }
 
-    // Floating windows intentionally not drawn here: see comment in git history
-    // and issue #6 — viewport offset is not exposed by Niri IPC, so floating
-    // window placement on the minimap is unreliable.
+    rects
 }
 
-/// Draw all tiled windows of one workspace using viewport-anchored column layout.
-///
-/// Used for `all` mode. Columns are placed in scrolling-layout coordinates,
-/// then shifted by the workspace's own `viewport_offset` so that the workspace
-/// viewport (workspace-x = viewport_offset) lands at the shared
-/// `viewport_anchor_x`. Different workspaces with different viewport offsets
-/// therefore shift horizontally relative to each other (Overview-style).
-/// The drawing is clipped to the row's bounds so content outside the row
-/// doesn't leak into neighbouring rows.
-#[allow(clippy::too_many_arguments)]
-fn draw_workspace_row_viewport(
-    cr: &Context,
+/// Move each column's rects down within a `row_height`-tall row, per
+/// `vertical_align`. `window_rects` stacks every column from the top.
+fn align_columns(
+    rects: &mut [WindowRect<'_>],
     layout: &WorkspaceLayout<'_>,
-    offset_x: f64,
-    offset_y: f64,
-    row_width: f64,
-    row_height: f64,
     scale: f64,
-    viewport_anchor_x: f64,
-    appearance: &AppearanceConfig,
+    row_height: f64,
+    align: VerticalAlign,
 ) {
-    if !layout.has_tiled || scale <= 0.0 || row_width <= 0.0 || row_height <= 0.0 {
+    if align == VerticalAlign::Top {
         return;
     }
+    for rect in rects {
+        let column_height: f64 = layout
+            .columns
+            .get(&rect.window.column_index)
+            .map_or(0.0, |windows| windows.iter().map(|w| w.size.1).sum());
+        rect.y += (row_height - column_height * scale).max(0.0) * align.factor();
+    }
+}
 
-    let window_color = Color::from_hex(&appearance.window_color).unwrap_or(Color {
-        r: 0.27,
-        g: 0.28,
-        b: 0.35,
-        a: 1.0,
-    });
-    let focused_color = Color::from_hex(&appearance.focused_color).unwrap_or(Color {
-        r: 0.54,
-        g: 0.71,
-        b: 0.98,
-        a: 1.0,
-    });
-    let border_color = Color::from_hex(&appearance.border_color).unwrap_or(Color {
-        r: 0.42,
-        g: 0.44,
-        b: 0.53,
-        a: 1.0,
-    });
+/// Shrink a window's cell (`start`, `size`) along one axis by `gap`, keeping
+/// it centered. Cells too small for the gap still get a minimal box, centered
+/// rather than pushed off by half the gap.
+fn inset_cell(start: f64, size: f64, gap: f64) -> (f64, f64) {
+    let inner = (size - gap).max(MIN_WINDOW_RECT_SIZE);
+    (start + (size - inner) / 2.0, inner)
+}
 
-    let gap = appearance.gap;
-    let half_gap = gap / 2.0;
+/// Mirror rects horizontally within `[left, right]`, so column 0 ends up on
+/// the right (`reverse_columns`). Every row is mirrored around the same
+/// bounds, which keeps `all`-mode viewport alignment intact.
+fn mirror_rects_x(rects: &mut [WindowRect<'_>], left: f64, right: f64) {
+    for rect in rects {
+        rect.x = left + right - (rect.x + rect.w);
+    }
+}
 
-    // Screen x where this workspace's column at workspace-x = 0 sits.
-    // Equivalent to `viewport_anchor_x + anchored_left * scale`.
-    let row_x_origin = viewport_anchor_x - layout.align_x * scale;
-    let y_origin = offset_y;
+/// Mirror rects vertically within `[top, bottom]`, so columns stack up from
+/// the bottom of the row (`flip_vertical`).
+fn mirror_rects_y(rects: &mut [WindowRect<'_>], top: f64, bottom: f64) {
+    for rect in rects {
+        rect.y = top + bottom - (rect.y + rect.h);
+    }
+}
 
-    // Clip to the row rect so off-viewport content doesn't leak into
-    // adjacent workspace rows or outside the widget.
-    cr.save().ok();
-    cr.rectangle(offset_x, offset_y, row_width, row_height);
-    cr.clip();
+/// Round a rect's edges (in device pixels) to whole pixels plus `offset`.
+/// Edges rather than x/w are rounded so neighbouring rects stay aligned.
+fn snap_rect(x: f64, y: f64, w: f64, h: f64, offset: f64) -> (f64, f64, f64, f64) {
+    let snap = |v: f64| (v - offset).round() + offset;
+    let (left, top) = (snap(x), snap(y));
+    let (right, bottom) = (snap(x + w), snap(y + h));
+    (left, top, (right - left).max(1.0), (bottom - top).max(1.0))
+}
 
-    for (&col_idx, windows) in &layout.columns {
-        let col_x = layout
-            .column_x_positions
-            .get(col_idx)
-            .copied()
-            .unwrap_or(0.0);
-        let mut y_pos = 0.0;
+/// Snap window rects to the device pixel grid (`pixel_snap`). Odd stroke
+/// widths need edges on pixel centers, or a 1px border blurs over two rows.
+fn snap_rects(cr: &Context, rects: &mut [WindowRect<'_>], border_width: f64) {
+    let Ok((device_scale, _)) = cr.user_to_device_distance(1.0, 0.0) else {
+        return;
+    };
+    if device_scale <= 0.0 {
+        return;
+    }
+    let stroke_pixels = (border_width * device_scale).round() as i64;
+    let offset = if stroke_pixels % 2 == 1 { 0.5 } else { 0.0 };
 
-        for window in windows {
-            let x = row_x_origin + col_x * scale;
-            let y = y_origin + y_pos * scale;
-            let w = window.size.0 * scale;
-            let h = window.size.1 * scale;
+    for rect in rects {
+        let (x, y) = cr.user_to_device(rect.x, rect.y);
+        let (x, y, w, h) = snap_rect(x, y, rect.w * device_scale, rect.h * device_scale, offset);
+        let Ok((x, y)) = cr.device_to_user(x, y) else {
+            continue;
+        };
+        rect.x = x;
+        rect.y = y;
+        rect.w = w / device_scale;
+        rect.h = h / device_scale;
+    }
+}
 
-            y_pos += window.size.1;
+/// Minimum luminance difference between adjacent window fills when
+/// `enforce_contrast` is enabled.
+const MIN_ADJACENT_LUMINANCE_DELTA: f64 = 0.05;
+
+/// Minimum luminance difference between window fills and the background
+/// when `enforce_contrast` is enabled.
+const MIN_BACKGROUND_LUMINANCE_DELTA: f64 = 0.15;
 
-            let x = x + half_gap;
-            let y = y + half_gap;
-            let w = (w - gap).max(1.0);
-            let h = (h - gap).max(1.0);
+/// Lighten fills too close to a dark `background`, or darken them against a
+/// light one, just enough to reach `MIN_BACKGROUND_LUMINANCE_DELTA`.
+fn enforce_background_contrast(fills: &mut [(Color, f64)], background: Color) {
+    let background_lum = background.luminance();
+    for (fill, _) in fills.iter_mut() {
+        let lum = fill.luminance();
+        if (lum - background_lum).abs() >= MIN_BACKGROUND_LUMINANCE_DELTA {
+            continue;
+        }
+        // Luminance is linear in the blend, so solve for the mix directly
+        *fill = if background_lum < 0.5 {
+            let target = background_lum + MIN_BACKGROUND_LUMINANCE_DELTA;
+            let t = (target - lum) / (1.0 - lum).max(f64::EPSILON);
+            fill.blend(Color { a: fill.a, ..WHITE }, t)
+        } else {
+            let target = background_lum - MIN_BACKGROUND_LUMINANCE_DELTA;
+            let t = 1.0 - target / lum.max(f64::EPSILON);
+            fill.blend(Color { a: fill.a, ..BLACK }, t)
+        };
+    }
+}
 
-            if w < 1.0 || h < 1.0 {
+/// Nudge the brightness of fills that are too close to an adjacent,
+/// already-resolved fill. Rects are visited in draw order, so a run of
+/// same-colored windows ends up alternating between base and nudged shades.
+fn enforce_adjacent_contrast(rects: &[WindowRect<'_>], fills: &mut [(Color, f64)], gap: f64) {
+    let tolerance = gap + 1.0;
+    for (i, rect) in rects.iter().enumerate() {
+        for (j, other) in rects[..i].iter().enumerate() {
+            if !rect.is_adjacent_to(other, tolerance) {
                 continue;
             }
-
-            let (fill_color, fill_alpha) = if window.is_focused {
-                (&focused_color, appearance.focused_opacity)
-            } else {
-                (&window_color, appearance.window_opacity)
+            let lum_i = fills[i].0.luminance();
+            let lum_j = fills[j].0.luminance();
+            if (lum_i - lum_j).abs() >= MIN_ADJACENT_LUMINANCE_DELTA {
+                continue;
+            }
+            // Move away from the neighbour; brighten dark colors and darken
+            // light ones when they're identical.
+            let brighten = if lum_i == lum_j {
+                lum_i < 0.5
+            } else {
+                lum_i > lum_j
+            };
+            // Brighten by mixing in white rather than scaling, which
+            // would leave black unchanged
+            let fill = fills[i].0;
+            fills[i].0 = if brighten {
+                fill.blend(Color { a: fill.a, ..WHITE }, 0.25)
+            } else {
+                fill.scale_brightness(0.75)
             };
+        }
+    }
+}
+
+/// Bounding boxes (x, y, w, h) of each column's window rects, grown by a
+/// quarter of the gap so half the gap still separates neighbouring columns.
+fn column_backdrop_rects(rects: &[WindowRect<'_>], gap: f64) -> Vec<(f64, f64, f64, f64)> {
+    let mut bounds: BTreeMap<usize, (f64, f64, f64, f64)> = BTreeMap::new();
+    for rect in rects {
+        bounds
+            .entry(rect.window.column_index)
+            .and_modify(|(left, top, right, bottom)| {
+                *left = left.min(rect.x);
+                *top = top.min(rect.y);
+                *right = right.max(rect.x + rect.w);
+                *bottom = bottom.max(rect.y + rect.h);
+            })
+            .or_insert((rect.x, rect.y, rect.x + rect.w, rect.y + rect.h));
+    }
+
+    let pad = gap / 4.0;
+    bounds
+        .into_values()
+        .map(|(left, top, right, bottom)| {
+            (
+                left - pad,
+                top - pad,
+                right - left + pad * 2.0,
+                bottom - top + pad * 2.0,
+            )
+        })
+        .collect()
+}
+
+/// Paint a rounded panel behind each column, beneath the window rects.
+fn draw_column_backdrops(cr: &Context, rects: &[WindowRect<'_>], appearance: &AppearanceConfig) {
+    let color = Color::from_hex(&appearance.column_backdrop_color).unwrap_or(Color {
+        r: 0.19,
+        g: 0.2,
+        b: 0.27,
+        a: 1.0,
+    });
+    cr.set_source_rgba(color.r, color.g, color.b, color.a);
+    for (x, y, w, h) in column_backdrop_rects(rects, appearance.gap) {
+        rounded_rectangle(
+            cr,
+            x,
+            y,
+            w,
+            h,
+            appearance.border_radius,
+            appearance.corner_style,
+        );
+        cr.fill().ok();
+    }
+}
+
+/// X positions of the separators between neighbouring columns: the middle
+/// of the gap between one column's right edge and the next one's left edge,
+/// in screen order (so `reverse_columns` is accounted for).
+fn column_separator_xs(rects: &[WindowRect<'_>]) -> Vec<f64> {
+    let mut spans: BTreeMap<usize, (f64, f64)> = BTreeMap::new();
+    for rect in rects {
+        spans
+            .entry(rect.window.column_index)
+            .and_modify(|(left, right)| {
+                *left = left.min(rect.x);
+                *right = right.max(rect.x + rect.w);
+            })
+            .or_insert((rect.x, rect.x + rect.w));
+    }
+
+    let mut spans: Vec<(f64, f64)> = spans.into_values().collect();
+    spans.sort_by(|a, b| a.0.total_cmp(&b.0));
+    spans
+        .windows(2)
+        .map(|pair| (pair[0].1 + pair[1].0) / 2.0)
+        .collect()
+}
+
+/// Draw a vertical line between neighbouring columns, spanning the row's
+/// `(y, height)`.
+fn draw_column_separators(
+    cr: &Context,
+    rects: &[WindowRect<'_>],
+    (y, height): (f64, f64),
+    appearance: &AppearanceConfig,
+) {
+    let color = Color::from_hex(&appearance.column_separator_color).unwrap_or(Color {
+        r: 0.27,
+        g: 0.28,
+        b: 0.35,
+        a: 1.0,
+    });
+    cr.set_source_rgba(color.r, color.g, color.b, color.a);
+    cr.set_line_width(appearance.column_separator_width);
+    for x in column_separator_xs(rects) {
+        cr.move_to(x, y);
+        cr.line_to(x, y + height);
+    }
+    cr.stroke().ok();
+}
+
+/// Corner radius of a window, `stacked` if its column holds other windows
+fn window_radius(appearance: &AppearanceConfig, stacked: bool) -> f64 {
+    if stacked && appearance.stacked_border_radius >= 0.0 {
+        appearance.stacked_border_radius
+    } else {
+        appearance.border_radius
+    }
+}
+
+fn set_source_color(cr: &Context, color: Color) {
+    cr.set_source_rgba(color.r, color.g, color.b, color.a);
+}
+
+/// Fill and stroke a set of window rectangles.
+fn draw_window_rects(
+    cr: &Context,
+    rects: &[WindowRect<'_>],
+    palette: &Palette,
+    appearance: &AppearanceConfig,
+    effects: &DrawEffects,
+) {
+    let mut fills: Vec<(Color, f64)> = rects
+        .iter()
+        .map(|rect| {
+            let (color, alpha) = palette.fill_for(rect.window, appearance, effects);
+            (*color, alpha)
+        })
+        .collect();
+    if appearance.enforce_contrast {
+        if let Some(background) = effects.contrast_background {
+            enforce_background_contrast(&mut fills, background);
+        }
+        enforce_adjacent_contrast(rects, &mut fills, appearance.gap);
+    }
+
+    let mut column_sizes: HashMap<usize, usize> = HashMap::new();
+    for rect in rects {
+        *column_sizes.entry(rect.window.column_index).or_default() += 1;
+    }
+
+    for (rect, (fill_color, fill_alpha)) in rects.iter().zip(fills) {
+        let (mut x, mut y, mut w, mut h) = (rect.x, rect.y, rect.w, rect.h);
+        let radius = window_radius(appearance, column_sizes[&rect.window.column_index] > 1);
+
+        // Opening/closing windows shrink around their center and fade
+        let visibility = effects.visibility(rect.window.id);
+        if visibility <= 0.0 {
+            continue;
+        }
+        // Windows mid-animation may already be gone; don't make them clickable
+        if visibility >= 1.0 {
+            effects.record_hit(HitTarget::Window(rect.window.id), x, y, w, h);
+        }
+        // Hidden windows (inactive tabs) reuse the normal colors, dimmed
+        let dim = if rect.window.is_hidden {
+            HIDDEN_WINDOW_OPACITY
+        } else {
+            1.0
+        };
+        if visibility < 1.0 {
+            let scale = scale_for_visibility(visibility);
+            x += w * (1.0 - scale) / 2.0;
+            y += h * (1.0 - scale) / 2.0;
+            w *= scale;
+            h *= scale;
+        }
+        let fill_alpha = fill_alpha * visibility * dim;
+
+        if fill_alpha > 0.0 {
+            set_source_color(cr, fill_color.with_alpha(fill_alpha));
+            rounded_rectangle(cr, x, y, w, h, radius, appearance.corner_style);
+            cr.fill().ok();
+        }
+
+        let icon = rect
+            .window
+            .app_id
+            .as_ref()
+            .and_then(|id| effects.icons.get(id));
+        if let (Some(icon), Some(size)) = (icon, icon_size(w, h)) {
+            draw_icon(cr, icon, x + w / 2.0, y + h / 2.0, size, visibility * dim);
+        }
+
+        if appearance.border_width > 0.0 {
+            set_source_color(
+                cr,
+                palette
+                    .border
+                    .with_alpha(palette.border.a * visibility * dim),
+            );
+            cr.set_line_width(appearance.border_width);
+            if rect.window.is_hidden {
+                let dash = (appearance.border_width * 2.0).max(2.0);
+                cr.set_dash(&[dash, dash], 0.0);
+            }
+            rounded_rectangle(cr, x, y, w, h, radius, appearance.corner_style);
+            cr.stroke().ok();
+            cr.set_dash(&[], 0.0);
+        }
+    }
+}
+
+/// Fill alpha multiplier for a window with focus recency `rank` (0 = most
+/// recently focused, `None` = never focused).
+fn recency_alpha(rank: Option<usize>) -> f64 {
+    rank.map_or(RECENCY_FADE_FLOOR, |rank| {
+        (1.0 - rank as f64 * RECENCY_FADE_STEP).max(RECENCY_FADE_FLOOR)
+    })
+}
+
+/// Side length of the app icon drawn in a `w` x `h` window box, or `None`
+/// if the box is too small for an icon.
+fn icon_size(w: f64, h: f64) -> Option<f64> {
+    let side = w.min(h);
+    (side >= MIN_ICON_BOX_SIZE).then(|| (side * ICON_BOX_FRACTION).min(MAX_ICON_SIZE))
+}
+
+/// Paint `icon` scaled to `size` and centered on (`cx`, `cy`).
+fn draw_icon(cr: &Context, icon: &Pixbuf, cx: f64, cy: f64, size: f64, alpha: f64) {
+    let scale = size / f64::from(icon.width().max(icon.height()).max(1));
+    cr.save().ok();
+    cr.translate(
+        cx - f64::from(icon.width()) * scale / 2.0,
+        cy - f64::from(icon.height()) * scale / 2.0,
+    );
+    cr.scale(scale, scale);
+    cr.set_source_pixbuf(icon, 0.0, 0.0);
+    cr.paint_with_alpha(alpha).ok();
+    cr.restore().ok();
+}
+
+/// Icons for the app_ids of all windows in `state`, loading any not yet in
+/// `cache`.
+fn window_icons(
+    state: &MinimapState,
+    cache: &mut HashMap<String, Option<Pixbuf>>,
+) -> HashMap<String, Pixbuf> {
+    let app_ids = state
+        .workspaces
+        .values()
+        .flat_map(|ws| ws.windows.values())
+        .filter_map(|window| window.app_id.as_deref());
+
+    let mut icons = HashMap::new();
+    for app_id in app_ids {
+        if icons.contains_key(app_id) {
+            continue;
+        }
+        let icon = cache
+            .entry(app_id.to_string())
+            .or_insert_with(|| load_icon(app_id));
+        if let Some(icon) = icon {
+            icons.insert(app_id.to_string(), icon.clone());
+        }
+    }
+    icons
+}
+
+/// Look up the icon for `app_id` in the display's icon theme. Apps usually
+/// name their icon after their app_id, sometimes in lowercase.
+fn load_icon(app_id: &str) -> Option<Pixbuf> {
+    let display = gdk::Display::default()?;
+    let theme = gtk4::IconTheme::for_display(&display);
+    let lowercase = app_id.to_lowercase();
+    let name = [app_id, lowercase.as_str()]
+        .into_iter()
+        .find(|name| theme.has_icon(name))?;
+
+    let paintable = theme.lookup_icon(
+        name,
+        &[],
+        ICON_LOAD_SIZE,
+        1,
+        gtk4::TextDirection::None,
+        gtk4::IconLookupFlags::empty(),
+    );
+    let path = paintable.file()?.path()?;
+    match Pixbuf::from_file_at_scale(&path, ICON_LOAD_SIZE, ICON_LOAD_SIZE, true) {
+        Ok(icon) => Some(icon),
+        Err(err) => {
+            tracing::debug!("Failed to load icon {:?} for {}: {}", path, app_id, err);
+            None
+        }
+    }
+}
+
+/// The appearance for a row drawn at `scale`.
+///
+/// With `scale_borders`, `border_width`/`border_radius` are converted from
+/// workspace pixels to minimap pixels, clamped to sane bounds. A zero border
+/// width stays disabled. `niri_gap` (workspace pixels, see `gap_mode`)
+/// replaces `gap` the same way.
+fn scaled_appearance(
+    appearance: &AppearanceConfig,
+    scale: f64,
+    niri_gap: Option<f64>,
+) -> Cow<'_, AppearanceConfig> {
+    if !appearance.scale_borders && niri_gap.is_none() {
+        return Cow::Borrowed(appearance);
+    }
+
+    let mut scaled = appearance.clone();
+    if appearance.scale_borders {
+        if appearance.border_width > 0.0 {
+            scaled.border_width = (appearance.border_width * scale)
+                .clamp(MIN_SCALED_BORDER_WIDTH, MAX_SCALED_BORDER_WIDTH);
+        }
+        scaled.border_radius =
+            (appearance.border_radius * scale).clamp(0.0, MAX_SCALED_BORDER_RADIUS);
+        if appearance.stacked_border_radius >= 0.0 {
+            scaled.stacked_border_radius =
+                (appearance.stacked_border_radius * scale).clamp(0.0, MAX_SCALED_BORDER_RADIUS);
+        }
+    }
+    if let Some(gap) = niri_gap {
+        scaled.gap = gap * scale;
+    }
+    Cow::Owned(scaled)
+}
+
+/// Outer padding needed so the focus ring of an edge window stays inside
+/// the widget.
+fn content_padding(appearance: &AppearanceConfig) -> f64 {
+    if appearance.focus_ring_width > 0.0 {
+        PADDING.max(FOCUS_RING_SPACING + appearance.focus_ring_width)
+    } else {
+        PADDING
+    }
+}
+
+/// Stroke an emphasis ring just outside the focused window, drawn on top of
+/// the window fills. The ring is inset where needed to stay within `bounds`
+/// (x, y, width, height).
+fn draw_focus_ring(
+    cr: &Context,
+    rects: &[WindowRect<'_>],
+    bounds: (f64, f64, f64, f64),
+    appearance: &AppearanceConfig,
+) {
+    let ring_width = appearance.focus_ring_width;
+    if ring_width <= 0.0 {
+        return;
+    }
+    let Some(rect) = rects.iter().find(|r| r.window.is_focused) else {
+        return;
+    };
+
+    // Cairo strokes are centered on the path; keep the whole stroke within
+    // bounds.
+    let offset = FOCUS_RING_SPACING + ring_width / 2.0;
+    let half = ring_width / 2.0;
+    let (bx, by, bw, bh) = bounds;
+    let left = (rect.x - offset).max(bx + half);
+    let top = (rect.y - offset).max(by + half);
+    let right = (rect.x + rect.w + offset).min(bx + bw - half);
+    let bottom = (rect.y + rect.h + offset).min(by + bh - half);
+    if right <= left || bottom <= top {
+        return;
+    }
+
+    let color = Color::from_hex(&appearance.focus_ring_color).unwrap_or(Color {
+        r: 0.98,
+        g: 0.89,
+        b: 0.69,
+        a: 1.0,
+    });
+    cr.set_source_rgba(color.r, color.g, color.b, color.a);
+    cr.set_line_width(ring_width);
+    rounded_rectangle(
+        cr,
+        left,
+        top,
+        right - left,
+        bottom - top,
+        appearance.border_radius + offset,
+        appearance.corner_style,
+    );
+    cr.stroke().ok();
+}
+
+/// Draw `floating_indicator = "glyph"`: two overlapping squares in the
+/// top-right corner, the front one filled, in `focused_color`.
+fn draw_floating_glyph(cr: &Context, width: f64, appearance: &AppearanceConfig) {
+    let color = Color::from_hex(&appearance.focused_color).unwrap_or(Color {
+        r: 0.54,
+        g: 0.71,
+        b: 0.98,
+        a: 1.0,
+    });
+    let size = FLOATING_GLYPH_SIZE;
+    let square = size * 0.7;
+    let x = width - content_padding(appearance) - size;
+    let y = content_padding(appearance);
+
+    set_source_color(cr, color);
+    cr.set_line_width(1.0);
+    cr.rectangle(x + size - square + 0.5, y + 0.5, square - 1.0, square - 1.0);
+    cr.stroke().ok();
+    cr.rectangle(x, y + size - square, square, square);
+    cr.fill().ok();
+}
+
+/// Floating windows listed by `floating_strip`: those on the active
+/// workspace, in id (opening) order.
+fn floating_strip_windows(state: &MinimapState) -> Vec<&Window> {
+    let mut windows: Vec<&Window> = state
+        .active_workspace()
+        .into_iter()
+        .flat_map(|ws| ws.windows.values())
+        .filter(|w| w.is_floating)
+        .collect();
+    windows.sort_by_key(|w| w.id);
+    windows
+}
+
+/// Height reserved below the layout for `floating_strip`: a row of boxes
+/// plus `element_spacing`, or nothing while the active workspace has no
+/// floating windows.
+fn floating_strip_height(state: &MinimapState, appearance: &AppearanceConfig) -> f64 {
+    if appearance.floating_strip && !floating_strip_windows(state).is_empty() {
+        FLOATING_STRIP_BOX_SIZE + appearance.element_spacing
+    } else {
+        0.0
+    }
+}
+
+/// Draw `floating_strip`: one box per floating window along the bottom of
+/// the minimap, filled like tiled windows (so the focused one is
+/// highlighted). Boxes that don't fit the width are left out.
+fn draw_floating_strip(
+    cr: &Context,
+    width: f64,
+    height: f64,
+    windows: &[&Window],
+    appearance: &AppearanceConfig,
+    effects: &DrawEffects,
+) {
+    let palette = Palette::from_appearance(appearance);
+    let padding = content_padding(appearance);
+    let box_h = FLOATING_STRIP_BOX_SIZE;
+    let box_w = box_h * 1.5;
+    let y = height - padding - box_h;
+
+    for (i, window) in windows.iter().enumerate() {
+        let x = padding + i as f64 * (box_w + appearance.gap);
+        if x + box_w > width - padding {
+            break;
+        }
+        let (fill, alpha) = palette.fill_for(window, appearance, effects);
+        set_source_color(cr, fill.with_alpha(alpha));
+        rounded_rectangle(
+            cr,
+            x,
+            y,
+            box_w,
+            box_h,
+            appearance.border_radius,
+            appearance.corner_style,
+        );
+        cr.fill_preserve().ok();
+        if appearance.border_width > 0.0 {
+            set_source_color(cr, palette.border);
+            cr.set_line_width(appearance.border_width);
+            cr.stroke().ok();
+        } else {
+            cr.new_path();
+        }
+        effects.record_hit(HitTarget::Window(window.id), x, y, box_w, box_h);
+    }
+}
+
+/// Text of the `show_workspace_number` badge: the workspace's name if it has
+/// one, else its index.
+fn workspace_number_text(workspace: &Workspace) -> String {
+    workspace
+        .name
+        .clone()
+        .unwrap_or_else(|| workspace.idx.to_string())
+}
+
+/// Top-left of a `badge` (width, height) placed in `corner` of a `width` x
+/// `height` minimap, `inset` pixels in from both edges.
+fn corner_origin(
+    corner: Corner,
+    width: f64,
+    height: f64,
+    badge: (f64, f64),
+    inset: f64,
+) -> (f64, f64) {
+    let (badge_w, badge_h) = badge;
+    let left = inset;
+    let right = width - inset - badge_w;
+    let top = inset;
+    let bottom = height - inset - badge_h;
+    match corner {
+        Corner::TopLeft => (left, top),
+        Corner::TopRight => (right, top),
+        Corner::BottomLeft => (left, bottom),
+        Corner::BottomRight => (right, bottom),
+    }
+}
+
+/// Draw the active workspace's name or index as a badge in
+/// `workspace_number_corner`, over a translucent `background` plate.
+fn draw_workspace_number(
+    cr: &Context,
+    width: f64,
+    height: f64,
+    workspace: &Workspace,
+    appearance: &AppearanceConfig,
+) {
+    let text = workspace_number_text(workspace);
+    let font_size = WORKSPACE_NUMBER_FONT_SIZE;
+    let color = Color::from_hex(&appearance.workspace_number_color).unwrap_or(Color {
+        r: 0.8,
+        g: 0.84,
+        b: 0.96,
+        a: 1.0,
+    });
+    let plate_color =
+        Color::from_hex(workspace_background(appearance, Some(workspace))).unwrap_or(Color {
+            r: 0.12,
+            g: 0.12,
+            b: 0.18,
+            a: 1.0,
+        });
+
+    cr.select_font_face("Sans", FontSlant::Normal, FontWeight::Bold);
+    cr.set_font_size(font_size);
+    let Ok(extents) = cr.text_extents(&text) else {
+        return;
+    };
+    let badge_w = extents.width() + 4.0;
+    let badge_h = font_size + 4.0;
+    let (x, y) = corner_origin(
+        appearance.workspace_number_corner,
+        width,
+        height,
+        (badge_w, badge_h),
+        content_padding(appearance),
+    );
+
+    set_source_color(cr, plate_color.with_alpha(0.8));
+    rounded_rectangle(cr, x, y, badge_w, badge_h, 2.0, appearance.corner_style);
+    cr.fill().ok();
+    draw_text_centered(
+        cr,
+        &text,
+        x + badge_w / 2.0,
+        y + badge_h / 2.0,
+        font_size,
+        &color,
+    );
+}
+
+/// Label each column holding more than one window with its window count,
+/// centered at the top of the column's first window. Columns too narrow to
+/// fit a digit are skipped.
+fn draw_column_counts(
+    cr: &Context,
+    layout: &WorkspaceLayout<'_>,
+    rects: &[WindowRect<'_>],
+    appearance: &AppearanceConfig,
+) {
+    let color = Color::from_hex(&appearance.label_color).unwrap_or(Color {
+        r: 0.8,
+        g: 0.84,
+        b: 0.96,
+        a: 1.0,
+    });
+
+    for (col_idx, windows) in &layout.columns {
+        if windows.len() < 2 {
+            continue;
+        }
+        // Rects are emitted top-down per column, so the first match is the
+        // column's top window.
+        let Some(top) = rects.iter().find(|r| r.window.column_index == *col_idx) else {
+            continue;
+        };
+        if top.w < MIN_COLUMN_COUNT_WIDTH {
+            continue;
+        }
+
+        let font_size = (top.w * 0.5).clamp(6.0, 10.0);
+        if top.h < font_size + 2.0 {
+            continue;
+        }
+        draw_text_centered(
+            cr,
+            &windows.len().to_string(),
+            top.x + top.w / 2.0,
+            top.y + 1.0 + font_size / 2.0,
+            font_size,
+            &color,
+        );
+    }
+}
+
+/// Draw "+N" badges at the row edges for columns left out by
+/// `display.max_columns`. `row` is (x, y, width, height).
+fn draw_overflow_indicators(
+    cr: &Context,
+    layout: &WorkspaceLayout<'_>,
+    row: (f64, f64, f64, f64),
+    appearance: &AppearanceConfig,
+) {
+    let (row_x, row_y, row_width, row_height) = row;
+    let (mut left, mut right) = (layout.hidden_columns_left, layout.hidden_columns_right);
+    if appearance.reverse_columns {
+        std::mem::swap(&mut left, &mut right);
+    }
+
+    let font_size = (row_height * 0.3).clamp(7.0, 11.0);
+    let label_color = Color::from_hex(&appearance.label_color).unwrap_or(Color {
+        r: 0.8,
+        g: 0.84,
+        b: 0.96,
+        a: 1.0,
+    });
+    let badge_color = Color::from_hex(&appearance.background).unwrap_or(Color {
+        r: 0.12,
+        g: 0.12,
+        b: 0.18,
+        a: 1.0,
+    });
+
+    for (count, at_left) in [(left, true), (right, false)] {
+        if count == 0 {
+            continue;
+        }
+        let text = format!("+{}", count);
+        cr.select_font_face("Sans", FontSlant::Normal, FontWeight::Bold);
+        cr.set_font_size(font_size);
+        let Ok(extents) = cr.text_extents(&text) else {
+            continue;
+        };
+
+        let badge_w = extents.width() + 4.0;
+        let badge_h = font_size + 4.0;
+        let badge_x = if at_left {
+            row_x
+        } else {
+            row_x + row_width - badge_w
+        };
+        let badge_y = row_y + (row_height - badge_h) / 2.0;
+
+        set_source_color(cr, badge_color.with_alpha(0.8));
+        rounded_rectangle(
+            cr,
+            badge_x,
+            badge_y,
+            badge_w,
+            badge_h,
+            2.0,
+            appearance.corner_style,
+        );
+        cr.fill().ok();
+        draw_text_centered(
+            cr,
+            &text,
+            badge_x + badge_w / 2.0,
+            badge_y + badge_h / 2.0,
+            font_size,
+            &label_color,
+        );
+    }
+}
+
+/// Draw `text` centered on `(cx, cy)` with Cairo's toy text API.
+fn draw_text_centered(cr: &Context, text: &str, cx: f64, cy: f64, font_size: f64, color: &Color) {
+    cr.select_font_face("Sans", FontSlant::Normal, FontWeight::Bold);
+    cr.set_font_size(font_size);
+    let Ok(extents) = cr.text_extents(text) else {
+        return;
+    };
+
+    cr.set_source_rgba(color.r, color.g, color.b, color.a);
+    cr.move_to(
+        cx - extents.width() / 2.0 - extents.x_bearing(),
+        cy - extents.height() / 2.0 - extents.y_bearing(),
+    );
+    cr.show_text(text).ok();
+}
+
+/// Whether `NIRIMAP_DEBUG_OVERLAY` is set. Read once, so the check is free
+/// on every later frame.
+fn debug_overlay_enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| env_flag_set(std::env::var_os(DEBUG_OVERLAY_ENV_VAR)))
+}
+
+/// Overlay the layout math of a row for bug reports: its scale, workspace
+/// size, column count and origin (the widget position of workspace-x 0),
+/// plus each window's id and palette fill color (`fill_of`) at its top-left
+/// corner.
+fn draw_debug_overlay(
+    cr: &Context,
+    layout: &WorkspaceLayout<'_>,
+    rects: &[WindowRect<'_>],
+    scale: f64,
+    origin: (f64, f64),
+    fill_of: impl Fn(&Window) -> Color,
+) {
+    const FONT_SIZE: f64 = 8.0;
+    cr.save().ok();
+    cr.select_font_face("Monospace", FontSlant::Normal, FontWeight::Normal);
+    cr.set_font_size(FONT_SIZE);
+
+    let summary = format!(
+        "ws {} scale {:.4} size {:.0}x{:.0} cols {} origin {:.1},{:.1}",
+        layout.workspace.id,
+        scale,
+        layout.total_width,
+        layout.max_height,
+        layout.columns.len(),
+        origin.0,
+        origin.1,
+    );
+    let labels = rects
+        .iter()
+        .map(|rect| {
+            let label = format!("#{} {}", rect.window.id, fill_of(rect.window).to_hex());
+            (label, rect.x + 1.0, rect.y + 1.0)
+        })
+        .chain(std::iter::once((summary, origin.0.max(0.0), origin.1)));
+    for (text, x, y) in labels {
+        let Ok(extents) = cr.text_extents(&text) else {
+            continue;
+        };
+        cr.set_source_rgba(0.0, 0.0, 0.0, 0.7);
+        cr.rectangle(x, y, extents.x_advance() + 2.0, FONT_SIZE + 2.0);
+        cr.fill().ok();
+        cr.set_source_rgba(1.0, 1.0, 0.0, 1.0);
+        cr.move_to(x + 1.0, y + FONT_SIZE);
+        cr.show_text(&text).ok();
+    }
+    cr.restore().ok();
+}
+
+/// Draw all tiled windows of one workspace into the rectangle
+/// `(offset_x, offset_y, row_width, row_height)` using column-based centered layout.
+///
+/// Used for `current` mode: windows are grouped by column and laid out as a single
+/// scrolling-layout image, horizontally centered in the row. The focus ring
+/// may extend `bleed` pixels past the row into the surrounding padding.
+#[allow(clippy::too_many_arguments)]
+fn draw_workspace_row_centered(
+    cr: &Context,
+    layout: &WorkspaceLayout<'_>,
+    offset_x: f64,
+    offset_y: f64,
+    row_width: f64,
+    row_height: f64,
+    bleed: f64,
+    viewport_outline: Option<f64>,
+    overflow: Overflow,
+    appearance: &AppearanceConfig,
+    niri_gap: Option<f64>,
+    effects: &DrawEffects,
+) {
+    if layout.total_width <= 0.0 || layout.max_height <= 0.0 || row_height <= 0.0 {
+        return;
+    }
+
+    let (scale, x_origin) = row_placement(layout, offset_x, row_width, row_height, overflow);
+    let y_origin = offset_y;
+
+    // Edges where a scrolled workspace is cut off, in screen order
+    let scaled_width = layout.total_width * scale;
+    let cut_left = x_origin < offset_x - 0.5;
+    let cut_right = x_origin + scaled_width > offset_x + row_width + 0.5;
+    let (cut_left, cut_right) = if appearance.reverse_columns {
+        (cut_right, cut_left)
+    } else {
+        (cut_left, cut_right)
+    };
+    let scrolled = cut_left || cut_right;
+    let clip = (
+        offset_x,
+        offset_y - bleed,
+        row_width,
+        row_height + bleed * 2.0,
+    );
+    if scrolled {
+        // Drawn into a group so the edge fades only affect the row
+        cr.save().ok();
+        cr.rectangle(clip.0, clip.1, clip.2, clip.3);
+        cr.clip();
+        cr.push_group();
+    }
+
+    let appearance = scaled_appearance(appearance, scale, niri_gap);
+    let appearance = appearance.as_ref();
+    let palette = Palette::from_appearance(appearance);
+    let mut rects = window_rects(layout, x_origin, y_origin, scale, appearance.gap);
+    align_columns(
+        &mut rects,
+        layout,
+        scale,
+        row_height,
+        appearance.vertical_align,
+    );
+    if appearance.reverse_columns {
+        mirror_rects_x(&mut rects, offset_x, offset_x + row_width);
+    }
+    if appearance.flip_vertical {
+        mirror_rects_y(&mut rects, offset_y, offset_y + row_height);
+    }
+    if appearance.pixel_snap {
+        snap_rects(cr, &mut rects, appearance.border_width);
+    }
+    if appearance.column_backdrop {
+        draw_column_backdrops(cr, &rects, appearance);
+    }
+    if appearance.column_separator_width > 0.0 {
+        draw_column_separators(cr, &rects, (offset_y, row_height), appearance);
+    }
+    draw_window_rects(cr, &rects, &palette, appearance, effects);
+    draw_focus_ring(
+        cr,
+        &rects,
+        (
+            offset_x - bleed,
+            offset_y - bleed,
+            row_width + bleed * 2.0,
+            row_height + bleed * 2.0,
+        ),
+        appearance,
+    );
+    if appearance.show_column_counts {
+        draw_column_counts(cr, layout, &rects, appearance);
+    }
+    draw_overflow_indicators(
+        cr,
+        layout,
+        (offset_x, offset_y, row_width, row_height),
+        appearance,
+    );
+    if debug_overlay_enabled() {
+        draw_debug_overlay(cr, layout, &rects, scale, (x_origin, y_origin), |w| {
+            *palette.fill_for(w, appearance, effects).0
+        });
+    }
+
+    if let Some(viewport_width) = viewport_outline {
+        draw_viewport_rect(
+            cr,
+            x_origin + layout.align_x * scale,
+            viewport_width * scale,
+            (offset_x, offset_y, row_width, row_height),
+            appearance,
+        );
+    }
+
+    if scrolled {
+        draw_scroll_fades(cr, clip, cut_left, cut_right);
+        cr.pop_group_to_source().ok();
+        cr.paint().ok();
+        cr.restore().ok();
+    }
+
+    // Floating windows intentionally not drawn here: see comment in git history
+    // and issue #6 — viewport offset is not exposed by Niri IPC, so floating
+    // window placement on the minimap is unreliable.
+}
+
+/// Scale and screen x of workspace-x 0 for a `current`-mode row.
+///
+/// The row's height sets the scale. A workspace too wide for the row is
+/// either shrunk to fit (`compress`) or kept at that scale and scrolled so
+/// the focused column is centered, without scrolling past either end
+/// (`scroll`). Workspaces that fit are centered.
+fn row_placement(
+    layout: &WorkspaceLayout<'_>,
+    offset_x: f64,
+    row_width: f64,
+    row_height: f64,
+    overflow: Overflow,
+) -> (f64, f64) {
+    let mut scale = row_height / layout.max_height;
+    if layout.total_width * scale > row_width && overflow == Overflow::Compress {
+        scale = row_width / layout.total_width;
+    }
+
+    let scaled_width = layout.total_width * scale;
+    if scaled_width <= row_width {
+        return (scale, offset_x + (row_width - scaled_width) / 2.0);
+    }
+
+    let x_origin = match focused_column_center(layout) {
+        Some(center) => (offset_x + row_width / 2.0 - center * scale)
+            .clamp(offset_x + row_width - scaled_width, offset_x),
+        None => offset_x,
+    };
+    (scale, x_origin)
+}
+
+/// Workspace-x of the middle of the column holding the focused window (or
+/// the workspace's last-focused one)
+fn focused_column_center(layout: &WorkspaceLayout<'_>) -> Option<f64> {
+    let windows = || layout.columns.values().flatten();
+    let window = windows().find(|w| w.is_focused).or_else(|| {
+        let active_id = layout.workspace.active_window_id?;
+        windows().find(|w| w.id == active_id)
+    })?;
+    let col_x = layout
+        .column_x_positions
+        .get(window.column_index)
+        .copied()?;
+    Some(col_x + window.size.0 / 2.0)
+}
+
+/// Fade the current group out towards the cut-off edges of `row` (x, y,
+/// width, height), hinting that there's more content past them.
+fn draw_scroll_fades(cr: &Context, row: (f64, f64, f64, f64), cut_left: bool, cut_right: bool) {
+    let (x, y, width, height) = row;
+    let fade = SCROLL_FADE_WIDTH.min(width / 4.0);
+    cr.set_operator(Operator::DestOut);
+    for (cut, edge, inner) in [
+        (cut_left, x, x + fade),
+        (cut_right, x + width, x + width - fade),
+    ] {
+        if !cut {
+            continue;
+        }
+        let gradient = LinearGradient::new(edge, 0.0, inner, 0.0);
+        gradient.add_color_stop_rgba(0.0, 0.0, 0.0, 0.0, 1.0);
+        gradient.add_color_stop_rgba(1.0, 0.0, 0.0, 0.0, 0.0);
+        cr.set_source(&gradient).ok();
+        cr.rectangle(edge.min(inner), y, fade, height);
+        cr.fill().ok();
+    }
+    cr.set_operator(Operator::Over);
+}
+
+/// Draw all tiled windows of one workspace using viewport-anchored column layout.
+///
+/// Used for `all` mode. Columns are placed in scrolling-layout coordinates,
+/// then shifted by the workspace's own `viewport_offset` so that the workspace
+/// viewport (workspace-x = viewport_offset) lands at the shared
+/// `viewport_anchor_x`. Different workspaces with different viewport offsets
+/// therefore shift horizontally relative to each other (Overview-style).
+/// The drawing is clipped to the row's bounds so content outside the row
+/// doesn't leak into neighbouring rows.
+#[allow(clippy::too_many_arguments)]
+fn draw_workspace_row_viewport(
+    cr: &Context,
+    layout: &WorkspaceLayout<'_>,
+    offset_x: f64,
+    offset_y: f64,
+    row_width: f64,
+    row_height: f64,
+    scale: f64,
+    viewport_anchor_x: f64,
+    viewport_outline: Option<f64>,
+    appearance: &AppearanceConfig,
+    niri_gap: Option<f64>,
+    effects: &DrawEffects,
+) {
+    if !layout.has_tiled || scale <= 0.0 || row_width <= 0.0 || row_height <= 0.0 {
+        return;
+    }
 
-            if fill_alpha > 0.0 {
-                cr.set_source_rgba(fill_color.r, fill_color.g, fill_color.b, fill_alpha);
-                rounded_rectangle(cr, x, y, w, h, appearance.border_radius);
-                cr.fill().ok();
-            }
+    let appearance = scaled_appearance(appearance, scale, niri_gap);
+    let appearance = appearance.as_ref();
+    let palette = Palette::from_appearance(appearance);
 
-            if appearance.border_width > 0.0 {
-                cr.set_source_rgba(
-                    border_color.r,
-                    border_color.g,
-                    border_color.b,
-                    border_color.a,
-                );
-                cr.set_line_width(appearance.border_width);
-                rounded_rectangle(cr, x, y, w, h, appearance.border_radius);
-                cr.stroke().ok();
-            }
-        }
+    // Screen x where this workspace's column at workspace-x = 0 sits.
+    // Equivalent to `viewport_anchor_x + anchored_left * scale`.
+    let row_x_origin = viewport_anchor_x - layout.align_x * scale;
+    let y_origin = offset_y;
+
+    // Clip to the row rect so off-viewport content doesn't leak into
+    // adjacent workspace rows or outside the widget.
+    cr.save().ok();
+    cr.rectangle(offset_x, offset_y, row_width, row_height);
+    cr.clip();
+
+    let mut rects = window_rects(layout, row_x_origin, y_origin, scale, appearance.gap);
+    align_columns(
+        &mut rects,
+        layout,
+        scale,
+        row_height,
+        appearance.vertical_align,
+    );
+    if appearance.reverse_columns {
+        mirror_rects_x(&mut rects, offset_x, offset_x + row_width);
+    }
+    if appearance.flip_vertical {
+        mirror_rects_y(&mut rects, offset_y, offset_y + row_height);
+    }
+    if appearance.pixel_snap {
+        snap_rects(cr, &mut rects, appearance.border_width);
+    }
+    if appearance.column_backdrop {
+        draw_column_backdrops(cr, &rects, appearance);
+    }
+    if appearance.column_separator_width > 0.0 {
+        draw_column_separators(cr, &rects, (offset_y, row_height), appearance);
+    }
+    draw_window_rects(cr, &rects, &palette, appearance, effects);
+    // The row is clipped, so keep the ring inside it rather than letting it
+    // get cut off at the row edges.
+    draw_focus_ring(
+        cr,
+        &rects,
+        (offset_x, offset_y, row_width, row_height),
+        appearance,
+    );
+    if appearance.show_column_counts {
+        draw_column_counts(cr, layout, &rects, appearance);
+    }
+    draw_overflow_indicators(
+        cr,
+        layout,
+        (offset_x, offset_y, row_width, row_height),
+        appearance,
+    );
+    if debug_overlay_enabled() {
+        draw_debug_overlay(cr, layout, &rects, scale, (row_x_origin, y_origin), |w| {
+            *palette.fill_for(w, appearance, effects).0
+        });
+    }
+
+    if let Some(viewport_width) = viewport_outline {
+        draw_viewport_rect(
+            cr,
+            viewport_anchor_x,
+            viewport_width * scale,
+            (offset_x, offset_y, row_width, row_height),
+            appearance,
+        );
     }
 
     cr.restore().ok();
 }
 
-/// Draw a rounded rectangle path
-fn rounded_rectangle(cr: &Context, x: f64, y: f64, width: f64, height: f64, radius: f64) {
+/// Outline the slice of a workspace visible on the monitor, starting at
+/// screen x `x` with width `width`, clamped to `row` (x, y, width, height).
+fn draw_viewport_rect(
+    cr: &Context,
+    x: f64,
+    width: f64,
+    row: (f64, f64, f64, f64),
+    appearance: &AppearanceConfig,
+) {
+    let (row_x, row_y, row_width, row_height) = row;
+    let mut left = x.max(row_x);
+    let mut right = (x + width).min(row_x + row_width);
+    if appearance.reverse_columns {
+        (left, right) = (
+            row_x * 2.0 + row_width - right,
+            row_x * 2.0 + row_width - left,
+        );
+    }
+    if right - left < 1.0 || row_height < 1.0 {
+        return;
+    }
+
+    let color = Color::from_hex(&appearance.viewport_color).unwrap_or(Color {
+        r: 0.8,
+        g: 0.84,
+        b: 0.96,
+        a: 1.0,
+    });
+
+    // Translucent fill with a 1px outline, inset half a pixel so the stroke
+    // stays crisp and inside the row.
+    set_source_color(cr, color.with_alpha(0.12));
+    cr.rectangle(left, row_y, right - left, row_height);
+    cr.fill().ok();
+
+    set_source_color(cr, color.with_alpha(0.8));
+    cr.set_line_width(1.0);
+    cr.rectangle(
+        left + 0.5,
+        row_y + 0.5,
+        (right - left - 1.0).max(0.0),
+        (row_height - 1.0).max(0.0),
+    );
+    cr.stroke().ok();
+}
+
+/// Draw a rectangle path with corners of `radius` shaped by `style`
+fn rounded_rectangle(
+    cr: &Context,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    radius: f64,
+    style: CornerStyle,
+) {
     let radius = radius.min(width / 2.0).min(height / 2.0);
 
     cr.new_path();
+    if style == CornerStyle::Sharp || radius <= 0.0 {
+        cr.rectangle(x, y, width, height);
+        return;
+    }
+    if style == CornerStyle::Squircle {
+        squircle_rectangle(cr, x, y, width, height, radius);
+        return;
+    }
+
     cr.arc(
         x + width - radius,
         y + radius,
@@ -943,3 +3891,934 @@ fn rounded_rectangle(cr: &Context, x: f64, y: f64, width: f64, height: f64, radi
     );
     cr.close_path();
 }
+
+/// Bezier handle length, relative to the corner radius, giving a corner
+/// close to a superellipse of exponent 4 (a circle would be 0.5523).
+const SQUIRCLE_HANDLE: f64 = 0.91;
+
+/// Rectangle path with superellipse-like corners, one cubic curve each
+fn squircle_rectangle(cr: &Context, x: f64, y: f64, width: f64, height: f64, radius: f64) {
+    let (right, bottom) = (x + width, y + height);
+    let k = radius * (1.0 - SQUIRCLE_HANDLE);
+
+    cr.move_to(x + radius, y);
+    cr.line_to(right - radius, y);
+    cr.curve_to(right - k, y, right, y + k, right, y + radius);
+    cr.line_to(right, bottom - radius);
+    cr.curve_to(right, bottom - k, right - k, bottom, right - radius, bottom);
+    cr.line_to(x + radius, bottom);
+    cr.curve_to(x + k, bottom, x, bottom - k, x, bottom - radius);
+    cr.line_to(x, y + radius);
+    cr.curve_to(x, y + k, x + k, y, x + radius, y);
+    cr.close_path();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipc::sanitize_tile_size;
+
+    fn tiled_window(id: u64, column_index: usize, size: (f64, f64)) -> Window {
+        Window {
+            id,
+            workspace_id: None,
+            app_id: None,
+            title: None,
+            output: None,
+            pos: None,
+            size,
+            column_index,
+            window_index: 0,
+            is_focused: false,
+            is_floating: false,
+            is_urgent: false,
+            is_hidden: false,
+            is_column_active: false,
+            is_fullscreen: false,
+        }
+    }
+
+    #[test]
+    fn test_focus_layout_keeps_focused_column_and_neighbors() {
+        let mut display = Config::default().display;
+        let mut state = MinimapState::new();
+        state.set_active_workspace(1);
+        for col in 0..5 {
+            state.upsert_window(1, tiled_window(col as u64, col, (400.0, 800.0)));
+        }
+
+        // Off by default
+        assert!(focus_layout(&state, &display, 1920.0).is_none());
+
+        // No focused window: fall back to the whole workspace
+        display.focus_mode = true;
+        assert!(focus_layout(&state, &display, 1920.0).is_none());
+
+        state.set_focused_window(Some(2));
+        let layout = focus_layout(&state, &display, 1920.0).unwrap();
+        assert_eq!(layout.columns.keys().copied().collect::<Vec<_>>(), vec![2]);
+        assert_eq!(layout.total_width, 400.0);
+
+        display.focus_neighbors = 1;
+        let layout = focus_layout(&state, &display, 1920.0).unwrap();
+        assert_eq!(
+            layout.columns.keys().copied().collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(layout.total_width, 1200.0);
+
+        // Neighbors past the first column are simply absent
+        state.set_focused_window(Some(0));
+        let layout = focus_layout(&state, &display, 1920.0).unwrap();
+        assert_eq!(
+            layout.columns.keys().copied().collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn test_max_columns_windows_around_focused_column() {
+        let mut display = Config::default().display;
+        let mut workspace = Workspace {
+            id: 1,
+            ..Default::default()
+        };
+        for col in 0..10 {
+            workspace
+                .windows
+                .insert(col as u64, tiled_window(col as u64, col, (400.0, 800.0)));
+        }
+        workspace.windows.get_mut(&6).unwrap().is_focused = true;
+
+        // Unlimited by default
+        let layout = build_workspace_layout(&workspace, &display, 1920.0);
+        assert_eq!(layout.columns.len(), 10);
+        assert_eq!(
+            (layout.hidden_columns_left, layout.hidden_columns_right),
+            (0, 0)
+        );
+
+        display.max_columns = 3;
+        let layout = build_workspace_layout(&workspace, &display, 1920.0);
+        assert_eq!(
+            layout.columns.keys().copied().collect::<Vec<_>>(),
+            vec![5, 6, 7]
+        );
+        assert_eq!(
+            (layout.hidden_columns_left, layout.hidden_columns_right),
+            (5, 2)
+        );
+        assert_eq!(layout.total_width, 1200.0);
+
+        // Near the end the window is pinned to the last columns
+        workspace.windows.get_mut(&6).unwrap().is_focused = false;
+        workspace.windows.get_mut(&9).unwrap().is_focused = true;
+        let layout = build_workspace_layout(&workspace, &display, 1920.0);
+        assert_eq!(
+            layout.columns.keys().copied().collect::<Vec<_>>(),
+            vec![7, 8, 9]
+        );
+        assert_eq!(
+            (layout.hidden_columns_left, layout.hidden_columns_right),
+            (7, 0)
+        );
+    }
+
+    #[test]
+    fn test_degenerate_window_sizes_give_finite_dimensions() {
+        let config = Config::default();
+        let mut state = MinimapState::new();
+        state.set_active_workspace(1);
+        state.upsert_window(1, tiled_window(1, 0, sanitize_tile_size((0.0, 0.0), 1)));
+        state.upsert_window(1, tiled_window(2, 1, (f64::NAN, 600.0)));
+        state.upsert_window(1, tiled_window(3, 2, (800.0, f64::INFINITY)));
+
+        for mode in [WorkspaceMode::Current, WorkspaceMode::All] {
+            let mut display = config.display.clone();
+            display.workspace_mode = mode;
+            let dims = compute_widget_dimensions(
+                &state,
+                &display,
+                &config.appearance,
+                &config.behavior,
+                1000.0,
+                800.0,
+                1920.0,
+            );
+            assert!(dims.width.is_finite() && dims.width > 0.0);
+            assert!(dims.height.is_finite() && dims.height > 0.0);
+        }
+
+        // Only the clamped zero-size window makes it into the layout
+        let layout = build_workspace_layout(&state.workspaces[&1], &config.display, 1920.0);
+        assert_eq!(layout.columns.values().flatten().count(), 1);
+        assert!(layout.total_width.is_finite() && layout.total_width > 0.0);
+        assert!(layout.max_height.is_finite() && layout.max_height > 0.0);
+    }
+
+    #[test]
+    fn test_widget_pixel_size_clamps_pathological_sizes() {
+        let size = |width, height, max_width| {
+            widget_pixel_size(&WidgetDimensions { width, height }, 100.0, max_width)
+        };
+        assert_eq!(size(250.4, 100.0, 1000.0), (251, 100));
+        assert_eq!(size(1e300, 100.0, 1000.0), (1000, 100));
+        assert_eq!(size(f64::INFINITY, 100.0, 1000.0), (100, 100));
+        assert_eq!(size(f64::NAN, f64::NAN, 1000.0), (100, 100));
+        assert_eq!(size(250.0, 1e300, 1000.0), (250, i32::MAX));
+        assert_eq!(size(10.0, 0.0, 1000.0), (100, 1));
+        // A broken cap still leaves room for the minimum width
+        assert_eq!(size(250.0, 100.0, f64::NAN), (100, 100));
+        assert_eq!(size(250.0, 100.0, 50.0), (100, 100));
+
+        // A nearly flat workspace scales up enormously
+        let config = Config::default();
+        let mut state = MinimapState::new();
+        state.set_active_workspace(1);
+        state.upsert_window(1, tiled_window(1, 0, (1e300, 1e-300)));
+        let dims = compute_widget_dimensions(
+            &state,
+            &config.display,
+            &config.appearance,
+            &config.behavior,
+            1000.0,
+            800.0,
+            1920.0,
+        );
+        let (width, _) = widget_pixel_size(&dims, config.display.height as f64, 1000.0);
+        assert!((100..=1000).contains(&width));
+    }
+
+    #[test]
+    fn test_floating_strip_reserves_space() {
+        let mut config = Config::default();
+        let mut state = MinimapState::new();
+        state.set_active_workspace(1);
+        state.upsert_window(1, tiled_window(1, 0, (800.0, 600.0)));
+        for id in [5, 3] {
+            let mut floating = tiled_window(id, 0, (400.0, 300.0));
+            floating.is_floating = true;
+            state.upsert_window(1, floating);
+        }
+        let dims = |state: &MinimapState, config: &Config| {
+            compute_widget_dimensions(
+                state,
+                &config.display,
+                &config.appearance,
+                &config.behavior,
+                1000.0,
+                800.0,
+                1920.0,
+            )
+        };
+
+        // Off by default
+        let plain = dims(&state, &config);
+        assert_eq!(floating_strip_height(&state, &config.appearance), 0.0);
+
+        config.appearance.floating_strip = true;
+        let ids: Vec<u64> = floating_strip_windows(&state)
+            .iter()
+            .map(|w| w.id)
+            .collect();
+        assert_eq!(ids, vec![3, 5]);
+        let strip = FLOATING_STRIP_BOX_SIZE + config.appearance.element_spacing;
+        assert_eq!(floating_strip_height(&state, &config.appearance), strip);
+        let with_strip = dims(&state, &config);
+        assert_eq!(with_strip.height, plain.height + strip);
+        assert_eq!(with_strip.width, plain.width);
+
+        // The spacing only widens the band, not the layout above it
+        config.appearance.element_spacing = 10.0;
+        let spaced = dims(&state, &config);
+        assert_eq!(spaced.height, plain.height + FLOATING_STRIP_BOX_SIZE + 10.0);
+        // Spacing alone reserves nothing
+        config.appearance.floating_strip = false;
+        assert_eq!(dims(&state, &config).height, plain.height);
+
+        // No floating windows, no strip
+        state.remove_windows_where(|w| w.is_floating);
+        assert_eq!(floating_strip_height(&state, &config.appearance), 0.0);
+    }
+
+    #[test]
+    fn test_match_aspect() {
+        let dims = |width, height| WidgetDimensions { width, height };
+
+        // A 16:9 box regardless of the content width
+        let matched = match_aspect(dims(400.0, 90.0), 16.0 / 9.0, 1000.0);
+        assert!((matched.width - 160.0).abs() < 1e-9);
+        assert_eq!(matched.height, 90.0);
+        let matched = match_aspect(dims(20.0, 90.0), 16.0 / 9.0, 1000.0);
+        assert!((matched.width - 160.0).abs() < 1e-9);
+
+        // Capped by max_width_percent
+        let matched = match_aspect(dims(20.0, 900.0), 16.0 / 9.0, 1000.0);
+        assert_eq!(matched.width, 1000.0);
+        assert_eq!(matched.height, 900.0);
+    }
+
+    #[test]
+    fn test_duplicate_window_indices_stay_visible() {
+        let display = Config::default().display;
+        let mut state = MinimapState::new();
+        // Four windows all claiming (column 0, window 0): two without a
+        // position, two with one
+        for (id, pos) in [
+            (7, None),
+            (3, None),
+            (2, Some((0.0, 600.0))),
+            (1, Some((0.0, 0.0))),
+        ] {
+            let mut window = tiled_window(id, 0, (800.0, 300.0));
+            window.pos = pos;
+            state.upsert_window(1, window);
+        }
+        let layout = build_workspace_layout(&state.workspaces[&1], &display, 1920.0);
+
+        // Ties fall back to position, then id, and are stacked
+        let rects = window_rects(&layout, 0.0, 0.0, 0.1, 0.0);
+        let ids: Vec<u64> = rects.iter().map(|r| r.window.id).collect();
+        assert_eq!(ids, vec![3, 7, 1, 2]);
+        for pair in rects.windows(2) {
+            assert!(pair[0].y + pair[0].h <= pair[1].y);
+        }
+    }
+
+    #[test]
+    fn test_ignored_app_ids_are_not_drawn() {
+        let mut config = Config::default();
+        config.behavior.ignore_app_ids = vec!["xdg-desktop-portal*".to_string()];
+        let mut state = MinimapState::new();
+        state.set_active_workspace(1);
+        for (id, app_id) in [(1, "firefox"), (2, "xdg-desktop-portal-gtk"), (3, "kitty")] {
+            let mut window = tiled_window(id, id as usize - 1, (800.0, 1000.0));
+            window.app_id = Some(app_id.to_string());
+            state.upsert_window(1, window);
+        }
+        state.remove_windows_where(|w| config.behavior.ignores_app_id(w.app_id.as_deref()));
+
+        let FrameLayout::All(rows) = frame_layout(
+            &state,
+            &config.display,
+            &config.appearance,
+            &config.behavior,
+            1920.0,
+        ) else {
+            panic!("expected all mode");
+        };
+        let rects = window_rects(&rows[0], 0.0, 0.0, 0.1, 0.0);
+        let ids: Vec<u64> = rects.iter().map(|r| r.window.id).collect();
+        assert_eq!(ids, vec![1, 3]);
+        // Its column takes no space
+        assert_eq!(rows[0].total_width, 1600.0);
+    }
+
+    #[test]
+    fn test_enforce_background_contrast() {
+        let dark = Color::from_hex("#1e1e2e").unwrap();
+        let light = Color::from_hex("#eff1f5").unwrap();
+        let distinct = Color::from_hex("#89b4fa").unwrap();
+        let mut fills = vec![(Color::from_hex("#202030").unwrap(), 0.8), (distinct, 1.0)];
+        enforce_background_contrast(&mut fills, dark);
+        let delta = fills[0].0.luminance() - dark.luminance();
+        assert!((delta - MIN_BACKGROUND_LUMINANCE_DELTA).abs() < 1e-9);
+        // Alpha is untouched, distinct fills too
+        assert_eq!(fills[0].1, 0.8);
+        assert_eq!(fills[1].0.to_hex(), distinct.to_hex());
+
+        // Against a light background, fills are darkened instead
+        let mut fills = vec![(light, 1.0)];
+        enforce_background_contrast(&mut fills, light);
+        let delta = light.luminance() - fills[0].0.luminance();
+        assert!((delta - MIN_BACKGROUND_LUMINANCE_DELTA).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_draw_fingerprint_ignores_invisible_changes() {
+        let mut appearance = Config::default().appearance;
+        let mut state = MinimapState::new();
+        state.set_active_workspace(1);
+        let mut window = tiled_window(1, 0, (800.0, 600.0));
+        window.title = Some("vim".to_string());
+        window.app_id = Some("kitty".to_string());
+        state.upsert_window(1, window.clone());
+        let before = draw_fingerprint(&state, &appearance);
+
+        // Titles are never drawn
+        window.title = Some("vim - main.rs".to_string());
+        state.upsert_window(1, window.clone());
+        assert_eq!(draw_fingerprint(&state, &appearance), before);
+
+        // App ids only count with icons on
+        window.app_id = Some("foot".to_string());
+        state.upsert_window(1, window.clone());
+        assert_eq!(draw_fingerprint(&state, &appearance), before);
+        appearance.show_icons = true;
+        let with_icons = draw_fingerprint(&state, &appearance);
+        window.app_id = Some("kitty".to_string());
+        state.upsert_window(1, window.clone());
+        assert_ne!(draw_fingerprint(&state, &appearance), with_icons);
+
+        window.size = (640.0, 600.0);
+        state.upsert_window(1, window);
+        assert_ne!(draw_fingerprint(&state, &appearance), before);
+        let resized = draw_fingerprint(&state, &appearance);
+        state.set_focused_window(Some(1));
+        assert_ne!(draw_fingerprint(&state, &appearance), resized);
+    }
+
+    #[test]
+    fn test_overview_config_shows_all_workspaces_while_open() {
+        let mut config = Config::default();
+        config.display.workspace_mode = WorkspaceMode::Current;
+        config.display.focus_mode = true;
+
+        // Off by default
+        let frame = overview_config(&config, true);
+        assert_eq!(frame.display.workspace_mode, WorkspaceMode::Current);
+
+        config.behavior.overview_all_workspaces = true;
+        let frame = overview_config(&config, true);
+        assert_eq!(frame.display.workspace_mode, WorkspaceMode::All);
+        assert!(!frame.display.focus_mode);
+
+        // Closing the overview restores the configured view
+        let frame = overview_config(&config, false);
+        assert_eq!(frame.display.workspace_mode, WorkspaceMode::Current);
+        assert!(frame.display.focus_mode);
+    }
+
+    #[test]
+    fn test_fullscreen_window_fills_its_row() {
+        let config = Config::default();
+        let mut state = MinimapState::new();
+        state.set_active_workspace(1);
+        for col in 0..3 {
+            state.upsert_window(1, tiled_window(col as u64 + 1, col, (944.0, 1048.0)));
+        }
+        state.set_focused_window(Some(2));
+        let window_ids = |state: &MinimapState| {
+            let FrameLayout::All(rows) = frame_layout(
+                state,
+                &config.display,
+                &config.appearance,
+                &config.behavior,
+                1920.0,
+            ) else {
+                panic!("expected all mode");
+            };
+            let rects = window_rects(&rows[0], 0.0, 0.0, 0.1, 0.0);
+            (
+                rects.iter().map(|r| r.window.id).collect::<Vec<u64>>(),
+                rows[0].total_width,
+            )
+        };
+        assert_eq!(window_ids(&state), (vec![1, 2, 3], 2832.0));
+
+        let mut fullscreen = tiled_window(2, 1, (1920.0, 1080.0));
+        fullscreen.is_fullscreen = true;
+        state.upsert_window(1, fullscreen);
+        state.set_focused_window(Some(2));
+        assert_eq!(window_ids(&state), (vec![2], 1920.0));
+
+        // Leaving fullscreen restores the whole workspace
+        state.upsert_window(1, tiled_window(2, 1, (944.0, 1048.0)));
+        state.set_focused_window(Some(2));
+        assert_eq!(window_ids(&state), (vec![1, 2, 3], 2832.0));
+    }
+
+    #[test]
+    fn test_large_gap_keeps_tiny_windows_centered_in_cells() {
+        let display = Config::default().display;
+        let mut state = MinimapState::new();
+        state.upsert_window(1, tiled_window(1, 0, (40.0, 30.0)));
+        state.upsert_window(1, tiled_window(2, 1, (400.0, 30.0)));
+        let layout = build_workspace_layout(&state.workspaces[&1], &display, 1920.0);
+
+        // At scale 0.1 window 1's cell is 4x3, smaller than the gap
+        let gap = 10.0;
+        let rects = window_rects(&layout, 0.0, 0.0, 0.1, gap);
+        let tiny = rects.iter().find(|r| r.window.id == 1).unwrap();
+        assert_eq!(
+            (tiny.w, tiny.h),
+            (MIN_WINDOW_RECT_SIZE, MIN_WINDOW_RECT_SIZE)
+        );
+        assert!((tiny.x + tiny.w / 2.0 - 2.0).abs() < 1e-9);
+        assert!((tiny.y + tiny.h / 2.0 - 1.5).abs() < 1e-9);
+        assert!(tiny.x >= 0.0 && tiny.x + tiny.w <= 4.0);
+        assert!(tiny.y >= 0.0 && tiny.y + tiny.h <= 3.0);
+
+        // Window 2's 40px cell fits the gap: inset by half the gap on each side
+        let wide = rects.iter().find(|r| r.window.id == 2).unwrap();
+        assert!((wide.x - 9.0).abs() < 1e-9);
+        assert!((wide.w - 30.0).abs() < 1e-9);
+        assert!(wide.x + wide.w <= 44.0 + 1e-9);
+    }
+
+    #[test]
+    fn test_preserve_column_spacing() {
+        let display = Config::default().display;
+        let mut state = MinimapState::new();
+        // Column 0 is scrolled out of view; columns 1 and 2 are shown with
+        // a 16px gap between them
+        state.upsert_window(1, tiled_window(1, 0, (400.0, 1000.0)));
+        let mut shown = tiled_window(2, 1, (800.0, 1000.0));
+        shown.pos = Some((100.0, 0.0));
+        state.upsert_window(1, shown);
+        let mut right = tiled_window(3, 2, (600.0, 1000.0));
+        right.pos = Some((916.0, 0.0));
+        state.upsert_window(1, right);
+
+        let mut layout = build_workspace_layout(&state.workspaces[&1], &display, 1920.0);
+        assert_eq!(layout.column_x_positions, vec![0.0, 400.0, 1200.0]);
+        let align_x = layout.align_x;
+
+        respace_columns(&mut layout);
+        assert_eq!(layout.column_x_positions, vec![0.0, 400.0, 1216.0]);
+        assert_eq!(layout.total_width, 1816.0);
+        assert_eq!(layout.align_x, align_x);
+        assert_eq!(layout.anchored_right, 1816.0 - align_x);
+
+        // Nothing reported (background workspace): stays packed
+        let mut background = tiled_window(4, 0, (400.0, 1000.0));
+        background.pos = None;
+        state.upsert_window(2, background);
+        let mut layout = build_workspace_layout(&state.workspaces[&2], &display, 1920.0);
+        respace_columns(&mut layout);
+        assert_eq!(layout.column_x_positions, vec![0.0]);
+        assert_eq!(layout.total_width, 400.0);
+    }
+
+    #[test]
+    fn test_layout_column_positions() {
+        let display = Config::default().display;
+        let mut state = MinimapState::new();
+        state.upsert_window(1, tiled_window(1, 0, (400.0, 300.0)));
+        let mut below = tiled_window(2, 0, (600.0, 500.0));
+        below.window_index = 1;
+        state.upsert_window(1, below);
+        // Column 1 is missing and takes no width
+        state.upsert_window(1, tiled_window(3, 2, (800.0, 1000.0)));
+
+        let layout = build_workspace_layout(&state.workspaces[&1], &display, 1920.0);
+        assert_eq!(layout.column_x_positions, vec![0.0, 600.0, 600.0]);
+        assert_eq!(layout.total_width, 1400.0);
+        assert_eq!(layout.max_height, 1000.0);
+    }
+
+    #[test]
+    fn test_column_backdrops_cover_columns_without_overlap() {
+        let config = Config::default();
+        let mut state = MinimapState::new();
+        state.upsert_window(1, tiled_window(1, 0, (800.0, 500.0)));
+        let mut stacked = tiled_window(2, 0, (800.0, 500.0));
+        stacked.window_index = 1;
+        state.upsert_window(1, stacked);
+        state.upsert_window(1, tiled_window(3, 1, (600.0, 1000.0)));
+
+        let gap = 4.0;
+        let layout = build_workspace_layout(&state.workspaces[&1], &config.display, 1920.0);
+        let rects = window_rects(&layout, 0.0, 0.0, 0.1, gap);
+        let backdrops = column_backdrop_rects(&rects, gap);
+        assert_eq!(backdrops.len(), 2);
+
+        // The first column's panel spans both stacked windows
+        let (x0, y0, w0, h0) = backdrops[0];
+        for rect in rects.iter().filter(|r| r.window.column_index == 0) {
+            assert!(x0 <= rect.x && rect.x + rect.w <= x0 + w0);
+            assert!(y0 <= rect.y && rect.y + rect.h <= y0 + h0);
+        }
+
+        // Neighbouring panels keep half the gap between them
+        let (x1, ..) = backdrops[1];
+        assert!((x1 - (x0 + w0) - gap / 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_column_separators_only_between_columns() {
+        let config = Config::default();
+        let mut state = MinimapState::new();
+        state.upsert_window(1, tiled_window(1, 0, (800.0, 500.0)));
+        let mut stacked = tiled_window(2, 0, (800.0, 500.0));
+        stacked.window_index = 1;
+        state.upsert_window(1, stacked);
+        state.upsert_window(1, tiled_window(3, 1, (600.0, 1000.0)));
+        state.upsert_window(1, tiled_window(4, 2, (400.0, 1000.0)));
+
+        let gap = 4.0;
+        let layout = build_workspace_layout(&state.workspaces[&1], &config.display, 1920.0);
+        let rects = window_rects(&layout, 0.0, 0.0, 0.1, gap);
+
+        // Three columns, two separators, each centered in the gap
+        assert_eq!(column_separator_xs(&rects), vec![80.0, 140.0]);
+
+        // A single column (even a stacked one) gets none
+        let single: Vec<WindowRect<'_>> = window_rects(&layout, 0.0, 0.0, 0.1, gap)
+            .into_iter()
+            .filter(|r| r.window.column_index == 0)
+            .collect();
+        assert!(column_separator_xs(&single).is_empty());
+    }
+
+    #[test]
+    fn test_compact_density() {
+        let mut display = Config::default().display;
+        let mut state = MinimapState::new();
+        state.upsert_window(1, tiled_window(1, 0, (800.0, 500.0)));
+        let mut stacked = tiled_window(2, 0, (800.0, 500.0));
+        stacked.window_index = 1;
+        stacked.is_column_active = true;
+        state.upsert_window(1, stacked);
+        state.upsert_window(1, tiled_window(3, 1, (600.0, 1000.0)));
+
+        let mut layout = build_workspace_layout(&state.workspaces[&1], &display, 1920.0);
+        assert!(!is_compact(&layout, &display));
+        display.compact_threshold = 3;
+        assert!(is_compact(&layout, &display));
+        display.compact_threshold = 4;
+        assert!(!is_compact(&layout, &display));
+        display.density = Density::Compact;
+        assert!(is_compact(&layout, &display));
+
+        // One bar per column, covering the whole column and standing in for
+        // its active window
+        layout.compact = true;
+        let rects = window_rects(&layout, 0.0, 0.0, 0.1, 4.0);
+        let bars: Vec<_> = rects
+            .iter()
+            .map(|r| (r.window.id, r.x, r.y, r.w, r.h))
+            .collect();
+        assert_eq!(
+            bars,
+            vec![(2, 2.0, 2.0, 76.0, 96.0), (3, 82.0, 2.0, 56.0, 96.0)]
+        );
+    }
+
+    #[test]
+    fn test_align_columns() {
+        let display = Config::default().display;
+        let mut state = MinimapState::new();
+        state.upsert_window(1, tiled_window(1, 0, (800.0, 1000.0)));
+        state.upsert_window(1, tiled_window(2, 1, (600.0, 500.0)));
+        let layout = build_workspace_layout(&state.workspaces[&1], &display, 1920.0);
+
+        // 100 px row: column 0 fills it, column 1 has 50 px to spare
+        let tops = |align| {
+            let mut rects = window_rects(&layout, 0.0, 10.0, 0.1, 0.0);
+            align_columns(&mut rects, &layout, 0.1, 100.0, align);
+            rects.iter().map(|r| r.y).collect::<Vec<_>>()
+        };
+        assert_eq!(tops(VerticalAlign::Top), vec![10.0, 10.0]);
+        assert_eq!(tops(VerticalAlign::Center), vec![10.0, 35.0]);
+        assert_eq!(tops(VerticalAlign::Bottom), vec![10.0, 60.0]);
+    }
+
+    #[test]
+    fn test_cooldown_elapsed() {
+        let cooldown = Duration::from_millis(150);
+        let start = Instant::now();
+        assert!(cooldown_elapsed(None, start, cooldown));
+        assert!(!cooldown_elapsed(
+            Some(start),
+            start + Duration::from_millis(149),
+            cooldown
+        ));
+        assert!(cooldown_elapsed(Some(start), start + cooldown, cooldown));
+        // No cooldown configured
+        assert!(cooldown_elapsed(Some(start), start, Duration::ZERO));
+    }
+
+    #[test]
+    fn test_focus_hold_keeps_final_focus() {
+        let hold = Duration::from_millis(50);
+        let start = Instant::now();
+        let mut focus_hold = FocusHold::default();
+
+        assert_eq!(focus_hold.offer(Some(1), start, hold), Some(Some(1)));
+        // Drag churn: focus bounces to 2 and then 3 within the hold
+        let ms = Duration::from_millis;
+        assert_eq!(focus_hold.offer(Some(2), start + ms(10), hold), None);
+        assert_eq!(focus_hold.offer(Some(3), start + ms(20), hold), None);
+        // Still held while the timer is pending, even past the hold
+        assert_eq!(focus_hold.offer(Some(4), start + ms(60), hold), None);
+        assert_eq!(focus_hold.settle(start + ms(70)), Some(Some(4)));
+        assert_eq!(focus_hold.settle(start + ms(70)), None);
+
+        // A change after the hold is applied right away
+        assert_eq!(focus_hold.offer(None, start + ms(120), hold), Some(None));
+        // No hold configured
+        assert_eq!(
+            focus_hold.offer(Some(5), start + ms(120), Duration::ZERO),
+            Some(Some(5))
+        );
+    }
+
+    #[test]
+    fn test_mirror_rects_y() {
+        let display = Config::default().display;
+        let mut state = MinimapState::new();
+        state.upsert_window(1, tiled_window(1, 0, (800.0, 300.0)));
+        let mut lower = tiled_window(2, 0, (800.0, 700.0));
+        lower.window_index = 1;
+        state.upsert_window(1, lower);
+        let layout = build_workspace_layout(&state.workspaces[&1], &display, 1920.0);
+
+        // 100 px row at y = 10: window 1 now sits at the bottom
+        let mut rects = window_rects(&layout, 0.0, 10.0, 0.1, 0.0);
+        mirror_rects_y(&mut rects, 10.0, 110.0);
+        let spans: Vec<(u64, f64, f64)> = rects.iter().map(|r| (r.window.id, r.y, r.h)).collect();
+        assert_eq!(spans, vec![(1, 80.0, 30.0), (2, 10.0, 70.0)]);
+    }
+
+    #[test]
+    fn test_workspace_number_badge() {
+        let named = Workspace {
+            id: 7,
+            idx: 2,
+            name: Some("main".to_string()),
+            ..Default::default()
+        };
+        let unnamed = Workspace {
+            id: 8,
+            idx: 3,
+            ..Default::default()
+        };
+        assert_eq!(workspace_number_text(&named), "main");
+        assert_eq!(workspace_number_text(&unnamed), "3");
+
+        let badge = (20.0, 13.0);
+        assert_eq!(
+            corner_origin(Corner::TopLeft, 200.0, 100.0, badge, 4.0),
+            (4.0, 4.0)
+        );
+        assert_eq!(
+            corner_origin(Corner::TopRight, 200.0, 100.0, badge, 4.0),
+            (176.0, 4.0)
+        );
+        assert_eq!(
+            corner_origin(Corner::BottomLeft, 200.0, 100.0, badge, 4.0),
+            (4.0, 83.0)
+        );
+        assert_eq!(
+            corner_origin(Corner::BottomRight, 200.0, 100.0, badge, 4.0),
+            (176.0, 83.0)
+        );
+    }
+
+    #[test]
+    fn test_workspace_background() {
+        let mut appearance = Config::default().appearance;
+        appearance
+            .workspace_colors
+            .insert("main".to_string(), "#1e3a5f".to_string());
+        appearance
+            .workspace_colors
+            .insert("2".to_string(), "#3b1f2b".to_string());
+        let named = Workspace {
+            id: 1,
+            name: Some("main".to_string()),
+            ..Default::default()
+        };
+        let by_id = Workspace {
+            id: 2,
+            ..Default::default()
+        };
+        let unthemed = Workspace {
+            id: 3,
+            ..Default::default()
+        };
+
+        assert_eq!(workspace_background(&appearance, Some(&named)), "#1e3a5f");
+        assert_eq!(workspace_background(&appearance, Some(&by_id)), "#3b1f2b");
+        assert_eq!(
+            workspace_background(&appearance, Some(&unthemed)),
+            "#1e1e2e"
+        );
+        assert_eq!(workspace_background(&appearance, None), "#1e1e2e");
+    }
+
+    #[test]
+    fn test_row_placement_overflow() {
+        let display = Config::default().display;
+        let mut state = MinimapState::new();
+        for col in 0..10 {
+            state.upsert_window(1, tiled_window(col as u64, col, (1000.0, 1000.0)));
+        }
+        let mut focused = tiled_window(7, 7, (1000.0, 1000.0));
+        focused.is_focused = true;
+        state.upsert_window(1, focused);
+        let layout = build_workspace_layout(&state.workspaces[&1], &display, 1920.0);
+
+        // 10000 x 1000 workspace, 100 x 50 row
+        let (scale, x_origin) = row_placement(&layout, 10.0, 100.0, 50.0, Overflow::Compress);
+        assert_eq!(scale, 0.01);
+        assert_eq!(x_origin, 10.0);
+
+        // Scrolled to center column 7 (workspace-x 7500)
+        let (scale, x_origin) = row_placement(&layout, 10.0, 100.0, 50.0, Overflow::Scroll);
+        assert_eq!(scale, 0.05);
+        assert!((x_origin + 7500.0 * scale - 60.0).abs() < 1e-9);
+
+        // Never scrolled past the last column
+        state.set_focused_window(Some(9));
+        let layout = build_workspace_layout(&state.workspaces[&1], &display, 1920.0);
+        let (scale, x_origin) = row_placement(&layout, 10.0, 100.0, 50.0, Overflow::Scroll);
+        assert!((x_origin + 10000.0 * scale - 110.0).abs() < 1e-9);
+
+        // A workspace that fits is centered either way
+        let (scale, x_origin) = row_placement(&layout, 10.0, 1000.0, 50.0, Overflow::Scroll);
+        assert_eq!(scale, 0.05);
+        assert_eq!(x_origin, 10.0 + (1000.0 - 500.0) / 2.0);
+    }
+
+    #[test]
+    fn test_window_radius() {
+        let mut appearance = Config::default().appearance;
+        appearance.border_radius = 4.0;
+        // Stacked windows follow border_radius unless set
+        assert_eq!(window_radius(&appearance, true), 4.0);
+
+        appearance.stacked_border_radius = 1.0;
+        assert_eq!(window_radius(&appearance, true), 1.0);
+        assert_eq!(window_radius(&appearance, false), 4.0);
+
+        appearance.scale_borders = true;
+        let scaled = scaled_appearance(&appearance, 0.5, None);
+        assert_eq!(scaled.stacked_border_radius, 0.5);
+    }
+
+    #[test]
+    fn test_window_tooltip() {
+        let mut window = tiled_window(1, 0, (800.0, 600.0));
+        assert_eq!(window_tooltip(&window), None);
+
+        window.app_id = Some("foot".to_string());
+        assert_eq!(window_tooltip(&window).as_deref(), Some("foot"));
+
+        window.title = Some("~/src".to_string());
+        assert_eq!(window_tooltip(&window).as_deref(), Some("~/src\nfoot"));
+
+        window.app_id = Some(String::new());
+        assert_eq!(window_tooltip(&window).as_deref(), Some("~/src"));
+    }
+
+    #[test]
+    fn test_hit_test_prefers_topmost_region() {
+        let regions = [
+            HitRegion {
+                target: HitTarget::Workspace(1),
+                x: 0.0,
+                y: 0.0,
+                w: 100.0,
+                h: 50.0,
+            },
+            HitRegion {
+                target: HitTarget::Window(7),
+                x: 10.0,
+                y: 10.0,
+                w: 20.0,
+                h: 20.0,
+            },
+        ];
+
+        assert_eq!(hit_test(&regions, 15.0, 15.0), Some(HitTarget::Window(7)));
+        assert_eq!(
+            hit_test(&regions, 50.0, 15.0),
+            Some(HitTarget::Workspace(1))
+        );
+        assert_eq!(hit_test(&regions, 50.0, 60.0), None);
+    }
+
+    #[test]
+    fn test_snap_rect_aligns_edges() {
+        // Whole pixels for even (or no) strokes
+        assert_eq!(snap_rect(10.3, 4.6, 20.4, 9.8, 0.0), (10.0, 5.0, 21.0, 9.0));
+        // Pixel centers for odd strokes
+        assert_eq!(
+            snap_rect(10.3, 4.6, 20.4, 9.8, 0.5),
+            (10.5, 4.5, 20.0, 10.0)
+        );
+        // Never collapses a rect
+        assert_eq!(snap_rect(3.2, 3.2, 0.2, 0.2, 0.0), (3.0, 3.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_window_opened_focused_shows_once() {
+        let opened = |window_id, is_focused| FocusShowEvent::NewWindow {
+            window_id,
+            is_focused,
+        };
+        let focused = FocusShowEvent::FocusChange;
+
+        // Window 5 opens focused: shown for the new window...
+        let (show, shown) = focus_show(Some(1), opened(5, true), false);
+        assert!(show);
+        // ...so the focus change that follows doesn't show again
+        assert_eq!(focus_show(shown, focused(Some(5)), false), (false, Some(5)));
+
+        // Real focus changes still do
+        assert_eq!(focus_show(shown, focused(Some(6)), false), (true, Some(6)));
+        assert_eq!(focus_show(shown, focused(None), false), (true, None));
+
+        // A window opening unfocused leaves the next focus change to it
+        let (show, shown) = focus_show(Some(1), opened(5, false), false);
+        assert!(show);
+        assert_eq!(focus_show(shown, focused(Some(5)), false), (true, Some(5)));
+
+        // A suppressed floating window neither shows nor swallows the
+        // focus change back to the previous tile
+        let (show, shown) = focus_show(Some(1), opened(5, true), true);
+        assert!(!show);
+        assert_eq!(focus_show(shown, focused(Some(5)), true), (false, Some(1)));
+        assert_eq!(focus_show(shown, focused(Some(1)), false), (false, Some(1)));
+    }
+
+    #[test]
+    fn test_corner_styles() {
+        let surface =
+            gtk4::cairo::ImageSurface::create(gtk4::cairo::Format::ARgb32, 20, 20).unwrap();
+        let cr = Context::new(&surface).unwrap();
+        let near_corner = |style| {
+            rounded_rectangle(&cr, 0.0, 0.0, 20.0, 20.0, 5.0, style);
+            cr.in_fill(1.2, 1.2).unwrap()
+        };
+
+        // The arc cuts the corner off; the squircle hugs it more tightly
+        assert!(near_corner(CornerStyle::Sharp));
+        assert!(!near_corner(CornerStyle::Rounded));
+        assert!(near_corner(CornerStyle::Squircle));
+
+        // The middle of every edge is covered whatever the style
+        for style in [
+            CornerStyle::Sharp,
+            CornerStyle::Rounded,
+            CornerStyle::Squircle,
+        ] {
+            rounded_rectangle(&cr, 0.0, 0.0, 20.0, 20.0, 5.0, style);
+            for (x, y) in [(10.0, 0.5), (19.5, 10.0), (10.0, 19.5), (0.5, 10.0)] {
+                assert!(cr.in_fill(x, y).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_recency_alpha() {
+        assert_eq!(recency_alpha(Some(0)), 1.0);
+        assert!((recency_alpha(Some(2)) - (1.0 - 2.0 * RECENCY_FADE_STEP)).abs() < 1e-9);
+        // Stale windows bottom out at the floor
+        assert_eq!(recency_alpha(Some(100)), RECENCY_FADE_FLOOR);
+        assert_eq!(recency_alpha(None), RECENCY_FADE_FLOOR);
+    }
+
+    #[test]
+    fn test_icon_size_threshold() {
+        // Too small on either side: no icon
+        assert_eq!(icon_size(100.0, MIN_ICON_BOX_SIZE - 1.0), None);
+        assert_eq!(icon_size(MIN_ICON_BOX_SIZE - 1.0, 100.0), None);
+        // Scales with the shorter side...
+        assert_eq!(icon_size(40.0, 20.0), Some(20.0 * ICON_BOX_FRACTION));
+        // ...up to a cap
+        assert_eq!(icon_size(500.0, 400.0), Some(MAX_ICON_SIZE));
+    }
+}
@@ -1,5 +1,6 @@
+mod animation;
 mod layer;
 mod minimap;
 
 pub use layer::create_layer_window;
-pub use minimap::MinimapWidget;
+pub use minimap::{draw_frame, frame_size, MinimapWidget};
@@ -0,0 +1,95 @@
+//! Helpers shared by the integration tests that run the event loop against
+//! a mock Niri
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use nirimap::ipc::{self, StateUpdate};
+
+#[path = "../../src/ipc/mock.rs"]
+mod mock;
+
+pub use mock::MockNiri;
+
+/// Longest wait for a single update before a test gives up
+const UPDATE_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub fn workspace(id: u64, output: &str) -> niri_ipc::Workspace {
+    serde_json::from_value(serde_json::json!({
+        "id": id,
+        "idx": 1,
+        "name": null,
+        "output": output,
+        "is_urgent": false,
+        "is_active": true,
+        "is_focused": true,
+        "active_window_id": null
+    }))
+    .unwrap()
+}
+
+pub fn window(id: u64, workspace_id: u64) -> niri_ipc::Window {
+    serde_json::from_value(serde_json::json!({
+        "id": id,
+        "title": "Terminal",
+        "app_id": "foot",
+        "pid": 1234,
+        "workspace_id": workspace_id,
+        "is_focused": true,
+        "is_floating": false,
+        "is_urgent": false,
+        "layout": {
+            "pos_in_scrolling_layout": [1, 1],
+            "tile_size": [800.0, 600.0],
+            "window_size": [796, 596],
+            "tile_pos_in_workspace_view": [16.0, 8.0],
+            "window_offset_in_tile": [2.0, 2.0]
+        }
+    }))
+    .unwrap()
+}
+
+pub fn event(json: serde_json::Value) -> niri_ipc::Event {
+    serde_json::from_value(json).expect("event should deserialize")
+}
+
+/// Run `ipc::run_event_loop` on a thread and collect updates until `done`
+/// is satisfied, then shut the loop down
+pub fn collect_updates(done: impl Fn(&[StateUpdate]) -> bool) -> Vec<StateUpdate> {
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let (tx, rx) = mpsc::channel();
+    let event_loop = {
+        let shutdown = Arc::clone(&shutdown);
+        thread::spawn(move || {
+            ipc::run_event_loop(&shutdown, |update| {
+                let _ = tx.send(update);
+            })
+        })
+    };
+
+    let mut updates = Vec::new();
+    while !done(&updates) {
+        match rx.recv_timeout(UPDATE_TIMEOUT) {
+            Ok(update) => updates.push(update),
+            Err(err) => panic!("no update ({:?}), got {:?}", err, updates),
+        }
+    }
+
+    shutdown.store(true, Ordering::Relaxed);
+    event_loop
+        .join()
+        .expect("event loop should not panic")
+        .expect("event loop should stop cleanly");
+    updates
+}
+
+/// Workspace ids of a `FullState`
+pub fn full_state_workspaces(update: &StateUpdate) -> Vec<u64> {
+    let StateUpdate::FullState(state) = update else {
+        panic!("expected FullState, got {:?}", update);
+    };
+    state.workspaces.keys().copied().collect()
+}
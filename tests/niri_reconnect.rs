@@ -0,0 +1,33 @@
+//! Reconnecting when Niri closes the event stream
+
+mod common;
+
+use common::{collect_updates, event, full_state_workspaces, window, workspace, MockNiri};
+use nirimap::ipc::StateUpdate;
+
+#[test]
+fn test_event_loop_reconnects_when_stream_closes() {
+    // Closes the event stream after the focus change
+    let mock = MockNiri::start(
+        vec![workspace(3, "DP-1")],
+        vec![window(1, 3)],
+        vec![event(
+            serde_json::json!({ "WindowFocusChanged": { "id": null } }),
+        )],
+    );
+    std::env::set_var("NIRI_SOCKET", mock.path());
+
+    let updates = collect_updates(|updates| {
+        updates
+            .iter()
+            .filter(|update| matches!(update, StateUpdate::FullState(_)))
+            .count()
+            == 2
+    });
+
+    assert_eq!(updates.len(), 3, "got {:?}", updates);
+    assert_eq!(full_state_workspaces(&updates[0]), vec![3]);
+    assert!(matches!(updates[1], StateUpdate::FocusChanged(None)));
+    // Starts over from the complete state after reconnecting
+    assert_eq!(full_state_workspaces(&updates[2]), vec![3]);
+}
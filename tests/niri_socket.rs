@@ -0,0 +1,27 @@
+//! Socket path resolution through `NIRI_SOCKET` and `set_socket_path`. Its
+//! own test binary since both are process-wide.
+
+mod common;
+
+use common::{collect_updates, event, full_state_workspaces, window, workspace, MockNiri};
+use nirimap::ipc::{self, StateUpdate};
+
+#[test]
+fn test_event_loop_resolves_socket_path() {
+    let from_env = MockNiri::start(
+        vec![workspace(3, "DP-1")],
+        vec![window(1, 3)],
+        vec![event(serde_json::json!({ "WindowClosed": { "id": 1 } }))],
+    );
+    let from_override = MockNiri::start(vec![workspace(4, "HDMI-A-1")], Vec::new(), Vec::new());
+
+    std::env::set_var("NIRI_SOCKET", from_env.path());
+    let updates = collect_updates(|updates| updates.len() == 2);
+    assert_eq!(full_state_workspaces(&updates[0]), vec![3]);
+    assert!(matches!(updates[1], StateUpdate::WindowClosed(1)));
+
+    // `--socket` wins over the environment
+    ipc::set_socket_path(from_override.path().to_string());
+    let updates = collect_updates(|updates| !updates.is_empty());
+    assert_eq!(full_state_workspaces(&updates[0]), vec![4]);
+}